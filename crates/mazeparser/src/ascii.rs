@@ -0,0 +1,120 @@
+use glam::vec2;
+
+use crate::{
+    grid::{Cell, Grid, EAST, NORTH, SOUTH, WEST},
+    Finish, Maze, StartDirection,
+};
+
+/// Characters per cell in a line of the drawing.
+const CELL_WIDTH: usize = 4;
+
+fn is_post_line(line: &str) -> bool {
+    line.starts_with(['o', '+'])
+}
+
+/// The three characters between two posts or walls, or spaces past the end of the line.
+fn inside(line: &str, x: usize) -> &str {
+    let start = x * CELL_WIDTH + 1;
+    line.get(start..start + CELL_WIDTH - 1).unwrap_or("")
+}
+
+impl Maze {
+    /// Loads a maze drawn with ASCII characters, as used by most public maze collections.
+    ///
+    /// Posts are `o` or `+`, walls `---` and `|`. `S` marks the start cell and `G` the goal cells.
+    /// Without markers the mouse starts in the bottom left corner, facing north,
+    /// and the finish is the center of the maze.
+    pub fn from_ascii(text: &str) -> Result<Self, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim_end)
+            .skip_while(|line| !is_post_line(line))
+            .take_while(|line| !line.is_empty())
+            .collect();
+        let width = lines.first().map_or(0, |line| line.len() / CELL_WIDTH);
+        let height = lines.len() / 2;
+        if width == 0 || height == 0 || !lines.iter().step_by(2).all(|line| is_post_line(line)) {
+            return Err(String::from("Expected a maze drawn with o or + as posts"));
+        }
+
+        let mut grid = Grid::new(width, height);
+        let mut start = None;
+        let mut goals = Vec::new();
+        for y in 0..height {
+            let (top, middle) = (lines[y * 2], lines[y * 2 + 1]);
+            let bottom = lines.get(y * 2 + 2).copied().unwrap_or("");
+            for x in 0..width {
+                let cell = (x, y);
+                if inside(top, x).contains('-') {
+                    grid.set_wall(cell, NORTH, true);
+                }
+                if middle.as_bytes().get(x * CELL_WIDTH) == Some(&b'|') {
+                    grid.set_wall(cell, WEST, true);
+                }
+                if middle.as_bytes().get((x + 1) * CELL_WIDTH) == Some(&b'|') {
+                    grid.set_wall(cell, EAST, true);
+                }
+                if inside(bottom, x).contains('-') {
+                    grid.set_wall(cell, SOUTH, true);
+                }
+                let marker = inside(middle, x);
+                if marker.contains('S') {
+                    start = Some(cell);
+                }
+                if marker.contains('G') {
+                    goals.push(cell);
+                }
+            }
+        }
+
+        let (start, start_direction) = match start {
+            Some(cell) => (cell, open_direction(&grid, cell)),
+            None => ((0, height - 1), StartDirection::Up),
+        };
+        let finish = if goals.is_empty() {
+            let (x, y) = ((width - 1) / 2, (height - 1) / 2);
+            Finish {
+                start: vec2(x as f32, y as f32),
+                end: vec2((width / 2 + 1) as f32, (height / 2 + 1) as f32),
+            }
+        } else {
+            let min_x = goals.iter().map(|c| c.0).min().unwrap_or(0);
+            let max_x = goals.iter().map(|c| c.0).max().unwrap_or(0);
+            let min_y = goals.iter().map(|c| c.1).min().unwrap_or(0);
+            let max_y = goals.iter().map(|c| c.1).max().unwrap_or(0);
+            Finish {
+                start: vec2(min_x as f32, min_y as f32),
+                end: vec2((max_x + 1) as f32, (max_y + 1) as f32),
+            }
+        };
+
+        Ok(Maze {
+            walls: grid.to_walls(),
+            friction: 1.0,
+            start: vec2(start.0 as f32 + 0.5, start.1 as f32 + 0.5),
+            start_direction,
+            finish,
+            wall_thickness: 1.0,
+            wall_color: 0x000000FF,
+            finish_color: 0x00FF0080,
+            ramps: Vec::new(),
+            name: None,
+            author: None,
+            declared_size: None,
+            cell_size: None,
+        })
+    }
+}
+
+/// The first side of the start cell without a wall, so the mouse doesn't face one.
+fn open_direction(grid: &Grid, cell: Cell) -> StartDirection {
+    [
+        (NORTH, StartDirection::Up),
+        (EAST, StartDirection::Right),
+        (SOUTH, StartDirection::Down),
+        (WEST, StartDirection::Left),
+    ]
+    .into_iter()
+    .find(|(direction, _)| !grid.has_wall(cell, *direction))
+    .map_or(StartDirection::Up, |(_, start)| start)
+}
@@ -0,0 +1,345 @@
+use std::collections::VecDeque;
+
+use glam::vec2;
+
+use crate::{Maze, Orientation, Wall};
+
+pub const NORTH: u8 = 1;
+pub const EAST: u8 = 2;
+pub const SOUTH: u8 = 4;
+pub const WEST: u8 = 8;
+
+pub const DIRECTIONS: [u8; 4] = [NORTH, EAST, SOUTH, WEST];
+
+pub type Cell = (usize, usize);
+
+/// Moves one cell into the given direction, if that stays inside the grid.
+pub fn step((x, y): Cell, direction: u8, width: usize, height: usize) -> Option<Cell> {
+    match direction {
+        NORTH if y > 0 => Some((x, y - 1)),
+        EAST if x + 1 < width => Some((x + 1, y)),
+        SOUTH if y + 1 < height => Some((x, y + 1)),
+        WEST if x > 0 => Some((x - 1, y)),
+        _ => None,
+    }
+}
+
+pub fn opposite(direction: u8) -> u8 {
+    match direction {
+        NORTH => SOUTH,
+        EAST => WEST,
+        SOUTH => NORTH,
+        _ => EAST,
+    }
+}
+
+/// The walls of a maze as a grid of cells, with one bit per side.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<u8>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    /// The walls of the floor of the maze.
+    pub fn from_maze(maze: &Maze) -> Self {
+        Self::from_layer(maze, 0)
+    }
+
+    /// The walls of a single layer of the maze, the size of the grid covers all layers.
+    pub fn from_layer(maze: &Maze, layer: usize) -> Self {
+        let (width, height) = maze.size();
+        let mut grid = Grid::new(width, height);
+        for wall in maze.walls.iter().filter(|wall| wall.layer == layer) {
+            match wall.orientation {
+                Orientation::Horizontal => {
+                    let row = wall.start.y as usize;
+                    let (from, to) = (wall.start.x.min(wall.end.x), wall.start.x.max(wall.end.x));
+                    for x in from as usize..to as usize {
+                        if row < height {
+                            grid.set_wall((x, row), NORTH, true);
+                        } else if row > 0 {
+                            grid.set_wall((x, row - 1), SOUTH, true);
+                        }
+                    }
+                }
+                Orientation::Vertical => {
+                    let col = wall.start.x as usize;
+                    let (from, to) = (wall.start.y.min(wall.end.y), wall.start.y.max(wall.end.y));
+                    for y in from as usize..to as usize {
+                        if col < width {
+                            grid.set_wall((col, y), WEST, true);
+                        } else if col > 0 {
+                            grid.set_wall((col - 1, y), EAST, true);
+                        }
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// Converts the walls back into segments, joining neighboring walls on the same line.
+    pub fn to_walls(&self) -> Vec<Wall> {
+        let mut walls = Vec::new();
+        let mut push_runs =
+            |line: usize, length: usize, present: &dyn Fn(usize) -> bool, horizontal: bool| {
+                let mut start = None;
+                for i in 0..=length {
+                    match (start, i < length && present(i)) {
+                        (None, true) => start = Some(i),
+                        (Some(from), false) => {
+                            let (from, to, line) = (from as f32, i as f32, line as f32);
+                            walls.push(if horizontal {
+                                Wall {
+                                    start: vec2(from, line),
+                                    end: vec2(to, line),
+                                    orientation: Orientation::Horizontal,
+                                    layer: 0,
+                                }
+                            } else {
+                                Wall {
+                                    start: vec2(line, from),
+                                    end: vec2(line, to),
+                                    orientation: Orientation::Vertical,
+                                    layer: 0,
+                                }
+                            });
+                            start = None;
+                        }
+                        _ => (),
+                    }
+                }
+            };
+
+        for row in 0..=self.height {
+            let present = |x: usize| {
+                if row < self.height {
+                    self.has_wall((x, row), NORTH)
+                } else {
+                    self.has_wall((x, row - 1), SOUTH)
+                }
+            };
+            push_runs(row, self.width, &present, true);
+        }
+        for col in 0..=self.width {
+            let present = |y: usize| {
+                if col < self.width {
+                    self.has_wall((col, y), WEST)
+                } else {
+                    self.has_wall((col - 1, y), EAST)
+                }
+            };
+            push_runs(col, self.height, &present, false);
+        }
+        walls
+    }
+
+    pub fn contains(&self, (x, y): Cell) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// The wall bits of a cell. Cells outside the grid are treated as closed.
+    pub fn walls(&self, (x, y): Cell) -> u8 {
+        if self.contains((x, y)) {
+            self.cells[y * self.width + x]
+        } else {
+            NORTH | EAST | SOUTH | WEST
+        }
+    }
+
+    /// The wall bits of all cells, row by row.
+    pub fn cell_walls(&self) -> &[u8] {
+        &self.cells
+    }
+
+    pub fn has_wall(&self, cell: Cell, direction: u8) -> bool {
+        self.walls(cell) & direction != 0
+    }
+
+    /// Sets or removes a wall, keeping the neighboring cell consistent.
+    pub fn set_wall(&mut self, cell: Cell, direction: u8, present: bool) {
+        if !self.contains(cell) {
+            return;
+        }
+        let mut apply = |(x, y): Cell, direction: u8| {
+            let walls = &mut self.cells[y * self.width + x];
+            if present {
+                *walls |= direction;
+            } else {
+                *walls &= !direction;
+            }
+        };
+        apply(cell, direction);
+        if let Some(neighbor) = step(cell, direction, self.width, self.height) {
+            apply(neighbor, opposite(direction));
+        }
+    }
+
+    /// The grid turned clockwise by 90°.
+    pub fn rotated(&self) -> Grid {
+        let mut rotated = Grid::new(self.height, self.width);
+        for (x, y) in self.cells() {
+            let walls = self.walls((x, y));
+            // North becomes east, east becomes south and so on
+            rotated.cells[x * rotated.width + (self.height - 1 - y)] =
+                (walls << 1 | walls >> 3) & 0xF;
+        }
+        rotated
+    }
+
+    /// The grid flipped from left to right.
+    pub fn mirrored(&self) -> Grid {
+        let mut mirrored = Grid::new(self.width, self.height);
+        for (x, y) in self.cells() {
+            let walls = self.walls((x, y));
+            mirrored.cells[y * self.width + (self.width - 1 - x)] =
+                walls & (NORTH | SOUTH) | (walls & EAST) << 2 | (walls & WEST) >> 2;
+        }
+        mirrored
+    }
+
+    /// The grid in all eight ways it can be turned and flipped, named by how it was changed.
+    pub fn symmetries(&self) -> [(&'static str, Grid); 8] {
+        let rotated = self.rotated();
+        let half_turn = rotated.rotated();
+        let mirrored = self.mirrored();
+        let mirrored_rotated = mirrored.rotated();
+        let mirrored_half_turn = mirrored_rotated.rotated();
+        [
+            ("unchanged", self.clone()),
+            ("rotated by 270°", half_turn.rotated()),
+            ("rotated by 180°", half_turn),
+            ("rotated by 90°", rotated),
+            ("mirrored and rotated by 270°", mirrored_half_turn.rotated()),
+            ("mirrored and rotated by 180°", mirrored_half_turn),
+            ("mirrored and rotated by 90°", mirrored_rotated),
+            ("mirrored", mirrored),
+        ]
+    }
+
+    /// The orientation all turned and flipped copies of the grid have in common.
+    pub fn canonical(&self) -> Grid {
+        self.symmetries()
+            .into_iter()
+            .map(|(_, grid)| grid)
+            .fold(self.clone(), Ord::min)
+    }
+
+    /// All cells reachable from the given cell in a single step.
+    pub fn neighbors(&self, cell: Cell) -> impl Iterator<Item = Cell> + '_ {
+        DIRECTIONS.into_iter().filter_map(move |direction| {
+            if self.has_wall(cell, direction) {
+                None
+            } else {
+                step(cell, direction, self.width, self.height)
+            }
+        })
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Cell> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Number of cells with only a single way out.
+    pub fn dead_ends(&self) -> usize {
+        self.cells()
+            .filter(|&cell| self.walls(cell).count_ones() == 3)
+            .count()
+    }
+
+    /// Number of steps it takes from every cell to the closest goal, `None` if unreachable.
+    pub fn distances(&self, goals: &[Cell]) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.width * self.height];
+        let mut queue = VecDeque::new();
+        for &goal in goals.iter().filter(|&&g| self.contains(g)) {
+            distances[goal.1 * self.width + goal.0] = Some(0);
+            queue.push_back(goal);
+        }
+        while let Some(cell) = queue.pop_front() {
+            let distance = distances[cell.1 * self.width + cell.0].unwrap_or_default();
+            for neighbor in self.neighbors(cell) {
+                let entry = &mut distances[neighbor.1 * self.width + neighbor.0];
+                if entry.is_none() {
+                    *entry = Some(distance + 1);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        distances
+    }
+
+    /// The shortest path from the start to any of the goals, including both ends.
+    pub fn shortest_path(&self, start: Cell, goals: &[Cell]) -> Option<Vec<Cell>> {
+        let distances = self.distances(goals);
+        let distance_of = |(x, y): Cell| distances[y * self.width + x];
+        if !self.contains(start) {
+            return None;
+        }
+        let mut current = start;
+        let mut path = vec![current];
+        let mut distance = distance_of(current)?;
+        while distance > 0 {
+            current = self
+                .neighbors(current)
+                .find(|&n| distance_of(n) == Some(distance - 1))?;
+            distance -= 1;
+            path.push(current);
+        }
+        Some(path)
+    }
+
+    /// Draws the grid with ASCII characters, marking the start with `S` and goals with `G`.
+    pub fn to_ascii(&self, start: Cell, goals: &[Cell]) -> String {
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push('+');
+                out.push_str(if self.has_wall((x, y), NORTH) {
+                    "---"
+                } else {
+                    "   "
+                });
+            }
+            out.push_str("+\n");
+            for x in 0..self.width {
+                out.push(if self.has_wall((x, y), WEST) {
+                    '|'
+                } else {
+                    ' '
+                });
+                out.push_str(if (x, y) == start {
+                    " S "
+                } else if goals.contains(&(x, y)) {
+                    " G "
+                } else {
+                    "   "
+                });
+            }
+            let last = (self.width.saturating_sub(1), y);
+            out.push(if self.has_wall(last, EAST) { '|' } else { ' ' });
+            out.push('\n');
+        }
+        for x in 0..self.width {
+            out.push('+');
+            let bottom = (x, self.height.saturating_sub(1));
+            out.push_str(if self.has_wall(bottom, SOUTH) {
+                "---"
+            } else {
+                "   "
+            });
+        }
+        out.push_str("+\n");
+        out
+    }
+}
@@ -1,4 +1,9 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
+
+mod ascii;
+pub mod grid;
+mod maz;
+pub mod path;
 
 use glam::{vec2, Vec2};
 use serde::{Deserialize, Serialize};
@@ -10,7 +15,7 @@ pub struct Vec2Def {
     pub y: f32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum Orientation {
     Vertical,
     Horizontal,
@@ -23,6 +28,82 @@ pub struct Wall {
     #[serde(with = "Vec2Def")]
     pub end: Vec2,
     pub orientation: Orientation,
+    #[serde(default)]
+    pub layer: usize, // 0 for the floor, 1 for the upper layer of a bridge
+}
+
+impl Wall {
+    /// The row or column line the wall is on.
+    fn line(&self) -> f32 {
+        match self.orientation {
+            Orientation::Horizontal => self.start.y,
+            Orientation::Vertical => self.start.x,
+        }
+    }
+
+    /// Where the wall starts and ends along its line.
+    fn span(&self) -> (f32, f32) {
+        let (a, b) = match self.orientation {
+            Orientation::Horizontal => (self.start.x, self.end.x),
+            Orientation::Vertical => (self.start.y, self.end.y),
+        };
+        (a.min(b), a.max(b))
+    }
+}
+
+/// A stretch of a row or column line, written like the walls in the text format:
+/// `R3:2-5` is row line 3 from x 2 to 5, `C7:1-2` is column line 7 from y 1 to 2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallRun {
+    pub orientation: Orientation,
+    pub line: u32,
+    pub from: u32,
+    pub to: u32,
+}
+
+impl WallRun {
+    fn wall(&self, from: f32, to: f32, layer: usize) -> Wall {
+        let line = self.line as f32;
+        let (start, end) = match self.orientation {
+            Orientation::Horizontal => (vec2(from, line), vec2(to, line)),
+            Orientation::Vertical => (vec2(line, from), vec2(line, to)),
+        };
+        Wall {
+            start,
+            end,
+            orientation: self.orientation,
+            layer,
+        }
+    }
+}
+
+impl FromStr for WallRun {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid wall '{s}', expected e.g. R3:2-5 or C7:1-2");
+        let (line, range) = s.split_once(':').ok_or_else(invalid)?;
+        let line = line.trim().to_uppercase();
+        let (orientation, line) = if let Some(row) = line.strip_prefix('R') {
+            (Orientation::Horizontal, row)
+        } else if let Some(column) = line.strip_prefix('C') {
+            (Orientation::Vertical, column)
+        } else {
+            return Err(invalid());
+        };
+        let (from, to) = range.split_once('-').ok_or_else(invalid)?;
+        let number = |s: &str| s.trim().parse::<u32>().map_err(|_| invalid());
+        let (from, to) = (number(from)?, number(to)?);
+        if from == to {
+            return Err(format!("The wall '{s}' has no length"));
+        }
+        Ok(Self {
+            orientation,
+            line: number(line)?,
+            from: from.min(to),
+            to: from.max(to),
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -33,7 +114,27 @@ pub struct Finish {
     pub end: Vec2,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A cell where the mouse drives between the floor and the upper layer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ramp {
+    pub cell: grid::Cell,
+    pub up: u8, // Side of the cell that leads to the upper layer, one of the directions of the grid
+}
+
+impl Ramp {
+    /// The layer the mouse is on after leaving the ramp towards the given direction.
+    pub fn layer_after(&self, direction: u8, layer: usize) -> usize {
+        if direction == self.up {
+            1
+        } else if direction == grid::opposite(self.up) {
+            0
+        } else {
+            layer
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum StartDirection {
     Up,
     Right,
@@ -49,37 +150,469 @@ pub struct Maze {
     pub start: Vec2,
     pub start_direction: StartDirection,
     pub finish: Finish,
+    pub wall_thickness: f32,
+    pub wall_color: u32,   // RGBA
+    pub finish_color: u32, // RGBA
+    #[serde(default)]
+    pub ramps: Vec<Ramp>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub declared_size: Option<(usize, usize)>, // From the SIZE header, which the walls don't have to match
+    #[serde(default)]
+    pub cell_size: Option<f32>, // mm, None for the 180 mm of a classic maze
 }
 
-impl FromStr for Maze {
-    type Err = String;
+impl Maze {
+    /// Number of cells in x and y direction, derived from the extents of the walls.
+    pub fn size(&self) -> (usize, usize) {
+        self.walls.iter().fold((0, 0), |(w, h), wall| {
+            (
+                w.max(wall.start.x.max(wall.end.x) as usize),
+                h.max(wall.start.y.max(wall.end.y) as usize),
+            )
+        })
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    /// The cell the mouse starts in.
+    pub fn start_cell(&self) -> grid::Cell {
+        (self.start.x.floor() as usize, self.start.y.floor() as usize)
+    }
+
+    /// All cells covered by the finish.
+    pub fn finish_cells(&self) -> Vec<grid::Cell> {
+        let (x1, x2) = (
+            self.finish.start.x.min(self.finish.end.x) as usize,
+            self.finish.start.x.max(self.finish.end.x) as usize,
+        );
+        let (y1, y2) = (
+            self.finish.start.y.min(self.finish.end.y) as usize,
+            self.finish.start.y.max(self.finish.end.y) as usize,
+        );
+        (y1..y2)
+            .flat_map(|y| (x1..x2).map(move |x| (x, y)))
+            .collect()
+    }
+
+    /// Adds or removes the walls along a run on a layer.
+    ///
+    /// Walls on the same line that only partly overlap the run are cut or joined with it.
+    pub fn set_walls(&mut self, run: &WallRun, layer: usize, present: bool) {
+        let on_line = |wall: &Wall| {
+            wall.layer == layer
+                && wall.orientation == run.orientation
+                && wall.line() == run.line as f32
+        };
+        let spans: Vec<(f32, f32)> = self
+            .walls
+            .iter()
+            .filter(|w| on_line(w))
+            .map(Wall::span)
+            .collect();
+        self.walls.retain(|wall| !on_line(wall));
+
+        let (from, to) = (run.from as f32, run.to as f32);
+        let mut kept = Vec::new();
+        for (start, end) in spans {
+            if end <= from || start >= to {
+                kept.push((start, end));
+                continue;
+            }
+            if start < from {
+                kept.push((start, from));
+            }
+            if end > to {
+                kept.push((to, end));
+            }
+        }
+        if present {
+            kept.push((from, to));
+        }
+
+        kept.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut joined: Vec<(f32, f32)> = Vec::new();
+        for (start, end) in kept {
+            match joined.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => joined.push((start, end)),
+            }
+        }
+        self.walls.extend(
+            joined
+                .into_iter()
+                .map(|(start, end)| run.wall(start, end, layer)),
+        );
+    }
+
+    /// Adds the outer walls of a maze of the size in cells where it has none yet, keeping the walls it has.
+    ///
+    /// Returns how many walls were added.
+    pub fn close_boundary(&mut self, (width, height): (usize, usize)) -> usize {
+        let (width, height) = (width as u32, height as u32);
+        let sides = [
+            (Orientation::Horizontal, 0, width),
+            (Orientation::Horizontal, height, width),
+            (Orientation::Vertical, 0, height),
+            (Orientation::Vertical, width, height),
+        ];
+        let before = self.walls.len();
+        for (orientation, line, length) in sides {
+            let run = WallRun {
+                orientation,
+                line,
+                from: 0,
+                to: length,
+            };
+            let mut spans: Vec<(f32, f32)> = self
+                .walls
+                .iter()
+                .filter(|w| w.layer == 0 && w.orientation == orientation && w.line() == line as f32)
+                .map(Wall::span)
+                .collect();
+            spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut at = 0.0;
+            let mut gaps = Vec::new();
+            for (start, end) in spans {
+                gaps.push((at, start.min(length as f32)));
+                at = f32::max(at, end);
+            }
+            gaps.push((at, length as f32));
+            for (from, to) in gaps.into_iter().filter(|(from, to)| from < to) {
+                self.walls.push(run.wall(from, to, 0));
+            }
+        }
+        self.walls.len() - before
+    }
+
+    /// Number of layers, 2 if the maze has a bridge.
+    pub fn layers(&self) -> usize {
+        self.walls
+            .iter()
+            .map(|wall| wall.layer + 1)
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+fn parse_direction(s: &str) -> Option<u8> {
+    match s.trim().to_uppercase().as_str() {
+        "U" => Some(grid::NORTH),
+        "R" => Some(grid::EAST),
+        "D" => Some(grid::SOUTH),
+        "L" => Some(grid::WEST),
+        _ => None,
+    }
+}
+
+fn direction_letter(direction: u8) -> &'static str {
+    match direction {
+        grid::NORTH => "U",
+        grid::EAST => "R",
+        grid::SOUTH => "D",
+        _ => "L",
+    }
+}
+
+/// Writes runs of walls as `a-b, c-d`.
+fn write_runs(
+    f: &mut std::fmt::Formatter<'_>,
+    prefix: &str,
+    runs: &[(u32, f32, f32)],
+) -> std::fmt::Result {
+    let mut lines: Vec<u32> = runs.iter().map(|(line, _, _)| *line).collect();
+    lines.dedup();
+    for line in lines {
+        let segments: Vec<String> = runs
+            .iter()
+            .filter(|(l, _, _)| *l == line)
+            .map(|(_, from, to)| format!("{from}-{to}"))
+            .collect();
+        writeln!(f, "{prefix}{line}: {}", segments.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Writes the maze in the text format.
+impl Display for Maze {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "NAME: {name}")?;
+        }
+        if let Some(author) = &self.author {
+            writeln!(f, "AUTHOR: {author}")?;
+        }
+        if let Some((width, height)) = self.declared_size {
+            writeln!(f, "SIZE: {width}x{height}")?;
+        }
+        if let Some(cell_size) = self.cell_size {
+            writeln!(f, "CELL_SIZE: {cell_size}")?;
+        }
+        writeln!(f, "SP: {},{}", self.start.x - 0.5, self.start.y - 0.5)?;
+        let direction = match self.start_direction {
+            StartDirection::Up => "U",
+            StartDirection::Right => "R",
+            StartDirection::Down => "D",
+            StartDirection::Left => "L",
+        };
+        writeln!(f, "SD: {direction}")?;
+        writeln!(
+            f,
+            "FI: {},{};{},{}",
+            self.finish.start.x, self.finish.start.y, self.finish.end.x, self.finish.end.y
+        )?;
+        writeln!(f, "FR: {}", self.friction)?;
+        writeln!(f, "WT: {}", self.wall_thickness)?;
+        writeln!(f, "WC: {:08X}", self.wall_color)?;
+        writeln!(f, "FC: {:08X}", self.finish_color)?;
+        for ramp in &self.ramps {
+            let (x, y) = ramp.cell;
+            writeln!(f, "RA: {x},{y},{}", direction_letter(ramp.up))?;
+        }
+
+        for layer in 0..self.layers() {
+            if layer > 0 {
+                writeln!(f, "LA: {layer}")?;
+            }
+            write_walls(f, self.walls.iter().filter(|wall| wall.layer == layer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the walls of a layer as rows and columns.
+fn write_walls<'a>(
+    f: &mut std::fmt::Formatter<'_>,
+    walls: impl Iterator<Item = &'a Wall>,
+) -> std::fmt::Result {
+    let mut rows = Vec::new();
+    let mut columns = Vec::new();
+    for wall in walls {
+        match wall.orientation {
+            Orientation::Horizontal => rows.push((
+                wall.start.y as u32,
+                wall.start.x.min(wall.end.x),
+                wall.start.x.max(wall.end.x),
+            )),
+            Orientation::Vertical => columns.push((
+                wall.start.x as u32,
+                wall.start.y.min(wall.end.y),
+                wall.start.y.max(wall.end.y),
+            )),
+        }
+    }
+    rows.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    columns.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    write_runs(f, ".R", &rows)?;
+    write_runs(f, ".C", &columns)
+}
+
+fn parse_color(s: &str) -> Result<u32, String> {
+    let s = s.trim().trim_start_matches("#");
+    let value = u32::from_str_radix(s, 16).map_err(|e| e.to_string())?;
+    match s.len() {
+        6 => Ok((value << 8) | 0xFF),
+        8 => Ok(value),
+        _ => Err(String::from(
+            "Expected a color in the format RRGGBB or RRGGBBAA",
+        )),
+    }
+}
+
+/// Parses a number, ignoring the whitespace around it.
+///
+/// A single comma is taken as decimal separator, since many editors and spreadsheets write them that way.
+/// In strict mode it is rejected with the fix instead.
+fn number<T: FromStr>(s: &str, strict: bool) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let s = s.trim();
+    let dotted = s.replace(',', ".");
+    if s.matches(',').count() == 1 && !s.contains('.') {
+        if strict {
+            return Err(format!(
+                "'{s}' uses a comma as decimal separator, write {dotted} instead"
+            ));
+        }
+        if let Ok(value) = dotted.parse() {
+            return Ok(value);
+        }
+    }
+    s.parse().map_err(|e| format!("'{s}': {e}"))
+}
+
+/// Splits a list of numbers separated by commas, with a hint if decimal commas made it too long.
+fn split_numbers(s: &str, expected: &[&str]) -> Result<Vec<String>, String> {
+    let parts: Vec<String> = s.split(',').map(String::from).collect();
+    if parts.len() == expected.len() {
+        return Ok(parts);
+    }
+    let format = expected.join(",");
+    if parts.len() > expected.len() {
+        Err(format!(
+            "Expected {format}, but found {} values. Numbers with a decimal comma need a '.' here, like 0.5 instead of 0,5",
+            parts.len()
+        ))
+    } else {
+        Err(format!("Expected {format}"))
+    }
+}
+
+/// Cuts off a comment at the end of a line, which starts with a `#` after a space.
+///
+/// A `#` right after the key, like in `WC: #FF0000`, starts a color instead.
+/// NAME and AUTHOR keep the whole line, so `NAME: Maze #3` stays `Maze #3`.
+fn strip_comment(line: &str) -> &str {
+    if let Some((key, _)) = line.split_once(':') {
+        if ["NAME", "AUTHOR"].contains(&key.trim().to_uppercase().as_str()) {
+            return line;
+        }
+    }
+    for (i, c) in line.char_indices() {
+        let before = &line[..i];
+        if c == '#' && before.ends_with(char::is_whitespace) && !before.trim_end().ends_with(':') {
+            return before;
+        }
+    }
+    line
+}
+
+/// Parses the SIZE header, `16x16` or just `16` for a square maze.
+fn parse_size(s: &str) -> Result<(usize, usize), String> {
+    let s = s.trim().to_lowercase();
+    let (width, height) = s.split_once('x').unwrap_or((&s, &s));
+    let number = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|e| format!("'{}': {e}", s.trim()))
+    };
+    Ok((number(width)?, number(height)?))
+}
+
+/// A line of the maze text that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize, // Counted from 1
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error in line {}! {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The lines of the text a maze was defined in, to point at them in messages.
+#[derive(Debug, Default, Clone)]
+pub struct SourceLines {
+    pub start: Option<usize>,
+    pub finish: Option<usize>,
+    pub size: Option<usize>,
+    pub walls: Vec<usize>, // One per wall, in the same order
+}
+
+impl Maze {
+    /// Parses the text format like `from_str` and also returns where each part was defined.
+    pub fn parse_with_lines(s: &str) -> Result<(Self, SourceLines), ParseError> {
+        Self::parse(s, false)
+    }
+
+    /// Like `parse_with_lines`, but rejects decimal commas, so competition mazes are written the same way everywhere.
+    pub fn parse_strict(s: &str) -> Result<(Self, SourceLines), ParseError> {
+        Self::parse(s, true)
+    }
+
+    fn parse(s: &str, strict: bool) -> Result<(Self, SourceLines), ParseError> {
+        let mut lines = SourceLines::default();
         let mut friction = 1.0;
         let mut start = vec2(0.0, 0.0);
         let mut start_direction = StartDirection::Right;
         let mut walls = Vec::new();
         let mut finish = Finish::default();
+        let mut wall_thickness = 1.0;
+        let mut wall_color = 0x000000FF;
+        let mut finish_color = 0x00FF0080;
+        let mut ramps = Vec::new();
+        let mut layer = 0;
+        let (mut name, mut author, mut declared_size) = (None, None, None);
+        let mut cell_size = None;
+        let mut bounds = None; // Line of the BOUNDS header and the size, if it isn't taken from the walls
 
         for (i, line) in s.lines().enumerate() {
             let i = i + 1;
             if line.trim().starts_with("#") {
                 continue;
             }
-            if let Some((left, right)) = line.split_once(":") {
+            if let Some((left, right)) = strip_comment(line).split_once(":") {
                 let left = left.trim().to_uppercase();
                 match left.as_str() {
                     "#" => (),
+                    "NAME" => name = Some(right.trim().to_string()).filter(|s| !s.is_empty()),
+                    "AUTHOR" => author = Some(right.trim().to_string()).filter(|s| !s.is_empty()),
+                    "SIZE" => {
+                        declared_size = Some(parse_size(right).map_err(|e| {
+                            ParseError::new(i, format!("Expected a size like 16x16. {e}"))
+                        })?);
+                        lines.size = Some(i);
+                    }
+                    "CELL_SIZE" => {
+                        let size: f32 = number(right, strict).map_err(|e| {
+                            ParseError::new(i, format!("Could not parse cell size: {e}"))
+                        })?;
+                        if size <= 0.0 {
+                            Err(ParseError::new(i, "The cell size has to be positive"))?;
+                        }
+                        cell_size = Some(size);
+                    }
+                    "BOUNDS" => {
+                        let size = if right.trim().eq_ignore_ascii_case("auto") {
+                            None
+                        } else {
+                            Some(parse_size(right).map_err(|e| {
+                                ParseError::new(
+                                    i,
+                                    format!("Expected bounds like 16x16 or auto. {e}"),
+                                )
+                            })?)
+                        };
+                        bounds = Some((i, size));
+                    }
                     "SP" => {
-                        if let Some((left, right)) = right.split_once(",") {
+                        if right.contains(',') {
+                            let parts = split_numbers(right, &["x", "y"])
+                                .map_err(|e| ParseError::new(i, e))?;
                             start = vec2(
-                                left.trim().parse().map_err(|e| {
-                                    format!("Error in line {i}! X value of starting point is not a valid number. {e}")
+                                number(&parts[0], strict).map_err(|e| {
+                                    ParseError::new(
+                                        i,
+                                        format!(
+                                            "X value of starting point is not a valid number. {e}"
+                                        ),
+                                    )
                                 })?,
-                                right.parse().map_err(|e| {
-                                    format!("Error in line {i}! Y value of starting point is not a valid number. {e}")
+                                number(&parts[1], strict).map_err(|e| {
+                                    ParseError::new(
+                                        i,
+                                        format!(
+                                            "Y value of starting point is not a valid number. {e}"
+                                        ),
+                                    )
                                 })?,
                             ) + vec2(0.5, 0.5);
+                            lines.start = Some(i);
                         }
                     }
                     "SD" => {
@@ -88,57 +621,105 @@ impl FromStr for Maze {
                             "U" => StartDirection::Up,
                             "D" => StartDirection::Down,
                             "R" => StartDirection::Right,
-                            _ => Err(format!("Error in line {i}! Invalid Starting Direction"))?,
+                            _ => Err(ParseError::new(i, "Invalid Starting Direction"))?,
                         };
                     }
                     "FI" => {
+                        lines.finish = Some(i);
                         if let Some((left, right)) = right.split_once(";") {
-                            if let Some((left, right)) = left.split_once(",") {
-                                let x: f32 = left.trim().parse().map_err(|e| format!("Error in line {i}! X value of start point of finish is not a valid number. {e}"))?;
-                                let y: f32 = right.trim().parse().map_err(|e| format!("Error in line {i}! Y value of start point of finish is not a valid number. {e}"))?;
-                                finish.start.x = x;
-                                finish.start.y = y;
-                            } else {
-                                Err(format!(
-                                    "Error in line {i}! Could not parse start point of finish"
-                                ))?;
-                            }
+                            let parts = split_numbers(left, &["x", "y"]).map_err(|e| {
+                                ParseError::new(
+                                    i,
+                                    format!("Could not parse start point of finish. {e}"),
+                                )
+                            })?;
+                            finish.start.x = number(&parts[0], strict).map_err(|e| ParseError::new(i, format!("X value of start point of finish is not a valid number. {e}")))?;
+                            finish.start.y = number(&parts[1], strict).map_err(|e| ParseError::new(i, format!("Y value of start point of finish is not a valid number. {e}")))?;
 
-                            if let Some((left, right)) = right.split_once(",") {
-                                let x: f32 = left.trim().parse().map_err(|e| {
+                            let parts = split_numbers(right, &["x", "y"]).map_err(|e| {
+                                ParseError::new(
+                                    i,
+                                    format!("Could not parse end point of finish. {e}"),
+                                )
+                            })?;
+                            finish.end.x = number(&parts[0], strict).map_err(|e| {
+                                ParseError::new(
+                                    i,
                                     format!(
-                                        "Error in line {i}! X value of end point of finish is not a valid number. {e}"
-                                    )
-                                })?;
-                                let y: f32 = right.trim().parse().map_err(|e| {
+                                        "X value of end point of finish is not a valid number. {e}"
+                                    ),
+                                )
+                            })?;
+                            finish.end.y = number(&parts[1], strict).map_err(|e| {
+                                ParseError::new(
+                                    i,
                                     format!(
-                                        "Error in line {i}! Y value of end point of finish is not a valid number. {e}"
-                                    )
-                                })?;
-                                finish.end.x = x;
-                                finish.end.y = y;
-                            } else {
-                                Err(format!(
-                                    "Error in line {i}! Could not parse end point of finish"
-                                ))?;
-                            }
+                                        "Y value of end point of finish is not a valid number. {e}"
+                                    ),
+                                )
+                            })?;
                         }
                     }
                     "FR" => {
-                        friction = right.trim().parse().map_err(|e| {
-                            format!("Error in line {i}! Could not parse friction: {e}")
+                        friction = number(right, strict).map_err(|e| {
+                            ParseError::new(i, format!("Could not parse friction: {e}"))
+                        })?;
+                    }
+                    "WT" => {
+                        wall_thickness = number(right, strict).map_err(|e| {
+                            ParseError::new(i, format!("Could not parse wall thickness: {e}"))
+                        })?;
+                    }
+                    "WC" => {
+                        wall_color = parse_color(right).map_err(|e| {
+                            ParseError::new(i, format!("Could not parse wall color: {e}"))
+                        })?;
+                    }
+                    "FC" => {
+                        finish_color = parse_color(right).map_err(|e| {
+                            ParseError::new(i, format!("Could not parse finish color: {e}"))
                         })?;
                     }
+                    "LA" => {
+                        layer = number(right, strict).map_err(|e| {
+                            ParseError::new(i, format!("Could not parse layer: {e}"))
+                        })?;
+                        if layer > 1 {
+                            Err(ParseError::new(
+                                i,
+                                "Only the floor (0) and one upper layer (1) are supported",
+                            ))?;
+                        }
+                    }
+                    "RA" => {
+                        let parts = split_numbers(right, &["x", "y", "direction"])
+                            .map_err(|e| ParseError::new(i, format!("Invalid ramp. {e}")))?;
+                        let x = number(&parts[0], strict).map_err(|e| {
+                            ParseError::new(
+                                i,
+                                format!("X value of the ramp is not a valid number. {e}"),
+                            )
+                        })?;
+                        let y = number(&parts[1], strict).map_err(|e| {
+                            ParseError::new(
+                                i,
+                                format!("Y value of the ramp is not a valid number. {e}"),
+                            )
+                        })?;
+                        let up = parse_direction(&parts[2])
+                            .ok_or_else(|| ParseError::new(i, "Invalid direction of the ramp"))?;
+                        ramps.push(Ramp { cell: (x, y), up });
+                    }
                     _ => {
                         if let Some(left) = left.strip_prefix(".R") {
-                            let row: f32 = left.parse().map_err(|e| {
-                                format!("Error in line {i}! Not a valid row number: {e}")
+                            let row: f32 = number(left, strict).map_err(|e| {
+                                ParseError::new(i, format!("Not a valid row number: {e}"))
                             })?;
                             for (min, max) in right.split(",").flat_map(|s| {
                                 if let Some((left, right)) = s.split_once("-") {
                                     Some((
-                                        left.trim().parse::<u32>().map_err(|e| format!("Error in line {i}! Starting point of the wall is not a valid number: {e}")),
-                                        right.trim().parse::<u32>().map_err(|e| format!("Error in line {i}! End point of the wall is not a valid number: {e}")),
+                                        left.trim().parse::<u32>().map_err(|e| ParseError::new(i, format!("Starting point of the wall is not a valid number: {e}"))),
+                                        right.trim().parse::<u32>().map_err(|e| ParseError::new(i, format!("End point of the wall is not a valid number: {e}"))),
                                     ))
                                 } else {
                                     None
@@ -148,17 +729,19 @@ impl FromStr for Maze {
                                     start: vec2(min? as f32, row),
                                     end: vec2(max? as f32, row),
                                     orientation: Orientation::Horizontal,
+                                    layer,
                                 });
+                                lines.walls.push(i);
                             }
                         } else if let Some(left) = left.strip_prefix(".C") {
-                            let col: f32 = left.parse().map_err(|e| {
-                                format!("Error in line {i}! Not a valid column number: {e}")
+                            let col: f32 = number(left, strict).map_err(|e| {
+                                ParseError::new(i, format!("Not a valid column number: {e}"))
                             })?;
                             for (min, max) in right.split(",").flat_map(|s| {
                                 if let Some((left, right)) = s.split_once("-") {
                                     Some((
-                                        left.trim().parse::<u32>().map_err(|e| format!("Error in line {i}! Starting point of the wall is not a valid number: {e}")),
-                                        right.trim().parse::<u32>().map_err(|e| format!("Error in line {i}! End point of the wall is not a valid number: {e}")),
+                                        left.trim().parse::<u32>().map_err(|e| ParseError::new(i, format!("Starting point of the wall is not a valid number: {e}"))),
+                                        right.trim().parse::<u32>().map_err(|e| ParseError::new(i, format!("End point of the wall is not a valid number: {e}"))),
                                     ))
                                 } else {
                                     None
@@ -168,22 +751,143 @@ impl FromStr for Maze {
                                     start: vec2(col, min? as f32),
                                     end: vec2(col, max? as f32),
                                     orientation: Orientation::Vertical,
+                                    layer,
                                 });
+                                lines.walls.push(i);
                             }
                         } else {
-                            Err(format!("Error in line {i}! Invalid line: {line}"))?
+                            Err(ParseError::new(i, format!("Invalid line: {line}")))?
                         }
                     }
                 }
             }
         }
 
-        Ok(Maze {
+        let mut maze = Maze {
             friction,
             start,
             walls,
             start_direction,
             finish,
-        })
+            wall_thickness,
+            wall_color,
+            finish_color,
+            ramps,
+            name,
+            author,
+            declared_size,
+            cell_size,
+        };
+        if let Some((line, size)) = bounds {
+            let added = maze.close_boundary(size.unwrap_or_else(|| maze.size()));
+            lines.walls.extend(std::iter::repeat_n(line, added));
+        }
+        Ok((maze, lines))
+    }
+}
+
+impl FromStr for Maze {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_lines(s).map(|(maze, _)| maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grid::{Grid, EAST, NORTH, SOUTH, WEST};
+
+    const MAZE: &str = "SP: 0,0\nSD: U\nFI: 1,1;2,2\n.R1: 0-2\n";
+
+    /// The spans of the walls on a row line, sorted.
+    fn row(maze: &Maze, line: f32) -> Vec<(f32, f32)> {
+        let mut spans: Vec<_> = maze
+            .walls
+            .iter()
+            .filter(|wall| wall.orientation == Orientation::Horizontal && wall.line() == line)
+            .map(Wall::span)
+            .collect();
+        spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+        spans
+    }
+
+    #[test]
+    fn bounds_close_the_maze_at_the_given_size() {
+        let (maze, lines) = Maze::parse_with_lines(&format!("{MAZE}BOUNDS: 4x4\n")).unwrap();
+        assert_eq!(maze.size(), (4, 4));
+        let grid = Grid::from_maze(&maze);
+        assert_eq!(grid.walls((0, 0)), NORTH | SOUTH | WEST);
+        assert_eq!(grid.walls((3, 0)), NORTH | EAST);
+        assert_eq!(grid.walls((3, 3)), SOUTH | EAST);
+        // The added walls point at the BOUNDS line
+        assert_eq!(lines.walls.len(), maze.walls.len());
+        assert!(lines.walls[1..].iter().all(|line| *line == 5));
+    }
+
+    #[test]
+    fn auto_bounds_close_the_maze_around_its_walls() {
+        let maze: Maze = format!("{MAZE}BOUNDS: auto\n").parse().unwrap();
+        assert_eq!(maze.size(), (2, 1));
+        let grid = Grid::from_maze(&maze);
+        assert_eq!(grid.walls((0, 0)), NORTH | SOUTH | WEST);
+        assert_eq!(grid.walls((1, 0)), NORTH | SOUTH | EAST);
+        // The wall that was already there isn't doubled
+        assert_eq!(row(&maze, 1.0), [(0.0, 2.0)]);
+
+        let error = format!("{MAZE}BOUNDS: big\n").parse::<Maze>().unwrap_err();
+        assert_eq!(error.line, 5);
+    }
+
+    #[test]
+    fn wall_runs_are_written_like_the_walls_in_the_text() {
+        let run: WallRun = "R3:2-5".parse().unwrap();
+        assert_eq!(
+            run,
+            WallRun {
+                orientation: Orientation::Horizontal,
+                line: 3,
+                from: 2,
+                to: 5,
+            }
+        );
+        let run: WallRun = "c7:5-1".parse().unwrap();
+        assert_eq!(
+            (run.orientation, run.line, run.from, run.to),
+            (Orientation::Vertical, 7, 1, 5)
+        );
+        assert!("R3:2-2".parse::<WallRun>().is_err());
+        assert!("X3:1-2".parse::<WallRun>().is_err());
+        assert!("R3".parse::<WallRun>().is_err());
+    }
+
+    #[test]
+    fn setting_walls_joins_and_cuts_the_walls_on_the_line() {
+        let mut maze: Maze = MAZE.parse().unwrap();
+        maze.set_walls(&"R1:1-4".parse().unwrap(), 0, true);
+        assert_eq!(row(&maze, 1.0), [(0.0, 4.0)]);
+        maze.set_walls(&"R1:1-2".parse().unwrap(), 0, false);
+        assert_eq!(row(&maze, 1.0), [(0.0, 1.0), (2.0, 4.0)]);
+
+        // The upper layer has walls of its own
+        maze.set_walls(&"R1:0-1".parse().unwrap(), 1, true);
+        assert_eq!(maze.layers(), 2);
+        assert_eq!(row(&maze, 1.0).len(), 3);
+    }
+
+    #[test]
+    fn decimal_commas_are_only_accepted_when_not_strict() {
+        assert_eq!(number::<f32>("0,5", false), Ok(0.5));
+        assert_eq!(number::<f32>(" 0.5 ", true), Ok(0.5));
+        let error = number::<f32>("0,5", true).unwrap_err();
+        assert!(error.contains("write 0.5 instead"), "{error}");
+
+        let text = format!("{MAZE}FR: 0,5\n");
+        let (maze, _) = Maze::parse_with_lines(&text).unwrap();
+        assert_eq!(maze.friction, 0.5);
+        let error = Maze::parse_strict(&text).unwrap_err();
+        assert_eq!(error.line, 5);
+        assert!(error.message.contains("write 0.5 instead"), "{error}");
     }
 }
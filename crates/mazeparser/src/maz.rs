@@ -0,0 +1,118 @@
+use glam::vec2;
+
+use crate::{
+    grid::{Grid, EAST, NORTH, SOUTH, WEST},
+    Finish, Maze, StartDirection,
+};
+
+/// Side length of the mazes in the classic binary format.
+const SIZE: usize = 16;
+
+impl Maze {
+    /// Loads a maze in the classic 16x16 `.maz` format.
+    ///
+    /// The format has one byte per cell with a bit for each wall (N = 1, E = 2, S = 4, W = 8).
+    /// The cells are stored column by column, starting in the bottom left corner,
+    /// which is also where the mouse starts, facing north. The finish is the 2x2 center.
+    pub fn from_maz(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != SIZE * SIZE {
+            return Err(format!(
+                "Expected {} bytes in a .maz file, got {}",
+                SIZE * SIZE,
+                bytes.len()
+            ));
+        }
+
+        let mut grid = Grid::new(SIZE, SIZE);
+        for (i, walls) in bytes.iter().enumerate() {
+            // Rows in the file count from the bottom, ours from the top
+            let cell = (i / SIZE, SIZE - 1 - i % SIZE);
+            for direction in [NORTH, EAST, SOUTH, WEST] {
+                if walls & direction != 0 {
+                    grid.set_wall(cell, direction, true);
+                }
+            }
+        }
+
+        Ok(Maze {
+            walls: grid.to_walls(),
+            friction: 1.0,
+            start: vec2(0.5, SIZE as f32 - 0.5),
+            start_direction: StartDirection::Up,
+            finish: Finish {
+                start: vec2(7.0, 7.0),
+                end: vec2(9.0, 9.0),
+            },
+            wall_thickness: 1.0,
+            wall_color: 0x000000FF,
+            finish_color: 0x00FF0080,
+            ramps: Vec::new(),
+            name: None,
+            author: None,
+            declared_size: None,
+            cell_size: None,
+        })
+    }
+
+    /// Writes the walls in the classic 16x16 `.maz` format.
+    ///
+    /// The format has no room for the start, finish, colors or headers, readers assume the classic ones.
+    /// Mazes of another size or with a bridge can't be written.
+    pub fn to_maz(&self) -> Result<Vec<u8>, String> {
+        let (width, height) = self.size();
+        if (width, height) != (SIZE, SIZE) {
+            return Err(format!(
+                "The .maz format only holds {SIZE}x{SIZE} mazes, this one is {width}x{height}"
+            ));
+        }
+        if self.layers() > 1 {
+            return Err(String::from("The .maz format can't hold a bridge"));
+        }
+
+        let grid = Grid::from_maze(self);
+        let mut bytes = vec![0; SIZE * SIZE];
+        for (i, walls) in bytes.iter_mut().enumerate() {
+            *walls = grid.walls((i / SIZE, SIZE - 1 - i % SIZE));
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A closed 16x16 maze with a wall between the second cells of the first two columns.
+    fn bytes() -> Vec<u8> {
+        let mut bytes: Vec<u8> = (0..SIZE * SIZE)
+            .map(|i| {
+                let (column, row) = (i / SIZE, i % SIZE); // Row counted from the bottom
+                let side = |at_edge: bool, direction: u8| if at_edge { direction } else { 0 };
+                side(row == SIZE - 1, NORTH)
+                    | side(column == SIZE - 1, EAST)
+                    | side(row == 0, SOUTH)
+                    | side(column == 0, WEST)
+            })
+            .collect();
+        bytes[1] |= EAST;
+        bytes[SIZE + 1] |= WEST;
+        bytes
+    }
+
+    #[test]
+    fn maz_round_trip_keeps_the_walls() {
+        let maze = Maze::from_maz(&bytes()).unwrap();
+        assert_eq!(maze.to_maz().unwrap(), bytes());
+    }
+
+    #[test]
+    fn maz_cells_go_column_by_column_from_the_bottom_left() {
+        let maze = Maze::from_maz(&bytes()).unwrap();
+        let grid = Grid::from_maze(&maze);
+        // Byte 1 is the second cell from the bottom of the first column
+        assert_eq!(grid.walls((0, SIZE - 2)), WEST | EAST);
+        assert_eq!(grid.walls((1, SIZE - 2)), WEST);
+        assert_eq!(grid.walls((0, SIZE - 1)), WEST | SOUTH);
+        assert_eq!(maze.to_maz().unwrap()[1], WEST | EAST);
+    }
+}
@@ -0,0 +1,94 @@
+use glam::{vec2, Vec2};
+
+use crate::grid::Cell;
+
+/// A straight piece of a path, in cell units with the cell centers at `x + 0.5`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    pub fn length(&self) -> f32 {
+        self.start.distance(self.end)
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        let delta = self.end - self.start;
+        delta.x != 0.0 && delta.y != 0.0
+    }
+}
+
+fn center((x, y): Cell) -> Vec2 {
+    vec2(x as f32 + 0.5, y as f32 + 0.5)
+}
+
+/// Joins points that lie on the same line into a single segment.
+fn merge(points: &[Vec2]) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    for pair in points.windows(2) {
+        let segment = Segment {
+            start: pair[0],
+            end: pair[1],
+        };
+        match segments.last_mut() {
+            Some(last)
+                if (last.end - last.start)
+                    .normalize()
+                    .abs_diff_eq((segment.end - segment.start).normalize(), 1e-4) =>
+            {
+                last.end = segment.end;
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments
+}
+
+/// Straight segments through the cell centers, turning only by 90°.
+pub fn straight_path(path: &[Cell]) -> Vec<Segment> {
+    let points: Vec<_> = path.iter().copied().map(center).collect();
+    merge(&points)
+}
+
+/// Segments through the middle of the crossed cell sides.
+///
+/// Corners are cut by 45° and zigzags between two directions become a single diagonal
+/// running between the wall posts.
+pub fn diagonal_path(path: &[Cell]) -> Vec<Segment> {
+    let (Some(&first), Some(&last)) = (path.first(), path.last()) else {
+        return Vec::new();
+    };
+    let mut points = vec![center(first)];
+    points.extend(
+        path.windows(2)
+            .map(|pair| (center(pair[0]) + center(pair[1])) / 2.0),
+    );
+    points.push(center(last));
+    merge(&points)
+}
+
+pub fn path_length(segments: &[Segment]) -> f32 {
+    segments.iter().map(Segment::length).sum()
+}
+
+/// A rough estimate of how long driving the segments takes.
+///
+/// Every segment is driven with a trapezoidal profile that starts and ends at rest,
+/// so the estimate errs on the slow side.
+pub fn estimate_time(segments: &[Segment], max_speed: f32, acceleration: f32) -> f32 {
+    segments
+        .iter()
+        .map(|segment| {
+            let length = segment.length();
+            // Distance needed to reach the maximum speed and to stop again
+            let ramps = max_speed * max_speed / acceleration;
+            if length >= ramps {
+                length / max_speed + max_speed / acceleration
+            } else {
+                2.0 * (length / acceleration).sqrt()
+            }
+        })
+        .sum()
+}
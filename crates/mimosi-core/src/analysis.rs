@@ -0,0 +1,126 @@
+use std::fmt::Display;
+
+use rhai::{ASTNode, Dynamic, Expr, OptimizationLevel, Position, Scope, Stmt};
+
+use crate::engine::{build_engine, ApiCategory, EngineConfig, READONLY_FIELDS};
+
+/// A likely mistake found in a script before running it.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub position: Position,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.position.line(), self.position.position()) {
+            (Some(line), Some(column)) => {
+                write!(f, "line {line}, column {column}: {}", self.message)
+            }
+            (Some(line), None) => write!(f, "line {line}: {}", self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Variables the simulator provides to every script.
+fn script_scope() -> Scope<'static> {
+    let mut scope = Scope::new();
+    scope.push_dynamic("mouse", Dynamic::UNIT);
+    scope.push_dynamic("state", Dynamic::UNIT);
+    scope
+}
+
+/// Returns the name of the `mouse` field accessed by the expression, if any.
+fn mouse_property(expr: &Expr) -> Option<(&str, Position)> {
+    if let Expr::Dot(x, _, _) = expr {
+        if let (Expr::Variable(v, _, _), Expr::Property(p, pos)) = (&x.lhs, &x.rhs) {
+            if v.1 == "mouse" {
+                return Some((p.2.as_str(), *pos));
+            }
+        }
+    }
+    None
+}
+
+/// Checks a script for undefined variables, unreachable branches,
+/// writes to readonly fields and calls to disabled functions.
+pub fn analyze(script: &str, config: &EngineConfig) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    let mut engine = build_engine(config);
+    // Keep constant branches around, so they can be reported
+    engine.set_optimization_level(OptimizationLevel::None);
+
+    let scope = script_scope();
+    engine.set_strict_variables(true);
+    if let Err(e) = engine.compile_with_scope(&scope, script) {
+        warnings.push(Warning {
+            position: e.position(),
+            message: e.err_type().to_string(),
+        });
+    }
+    engine.set_strict_variables(false);
+    let Ok(ast) = engine.compile_with_scope(&scope, script) else {
+        return warnings;
+    };
+
+    let disabled: Vec<(&str, _)> = ApiCategory::ALL
+        .iter()
+        .filter(|c| !config.is_enabled(**c))
+        .flat_map(|c| c.functions().iter().map(move |f| (*f, c)))
+        .collect();
+    let check_disabled = |name: &str, position: Position, warnings: &mut Vec<Warning>| {
+        if let Some((_, category)) = disabled.iter().find(|(f, _)| *f == name) {
+            let message = if category.is_opt_in() && !config.disabled.contains(category) {
+                format!("'{name}' has to be enabled with enabled_api ({category:?})")
+            } else {
+                format!("'{name}' is disabled in this scenario ({category:?})")
+            };
+            warnings.push(Warning { position, message });
+        }
+    };
+
+    ast.walk(&mut |path: &[ASTNode]| {
+        match path.last() {
+            Some(ASTNode::Stmt(Stmt::If(flow, pos))) => {
+                if let Expr::BoolConstant(value, _) = flow.expr {
+                    let branch = if value { "else" } else { "if" };
+                    warnings.push(Warning {
+                        position: *pos,
+                        message: format!("The {branch} branch can never be reached"),
+                    });
+                }
+            }
+            Some(ASTNode::Stmt(Stmt::Assignment(x))) => {
+                if let Some((name, pos)) = mouse_property(&x.1.lhs) {
+                    if READONLY_FIELDS.contains(&name) {
+                        warnings.push(Warning {
+                            position: pos,
+                            message: format!("mouse.{name} is readonly"),
+                        });
+                    }
+                }
+            }
+            Some(ASTNode::Expr(Expr::FnCall(call, pos)))
+            | Some(ASTNode::Expr(Expr::MethodCall(call, pos))) => {
+                check_disabled(call.name.as_str(), *pos, &mut warnings);
+            }
+            Some(ASTNode::Stmt(Stmt::FnCall(call, pos))) => {
+                check_disabled(call.name.as_str(), *pos, &mut warnings);
+            }
+            // Only fields of `mouse` belong to a category, `mouse.odometry.x` is always fine
+            Some(ASTNode::Expr(Expr::Property(p, pos))) => {
+                if let Some(ASTNode::Expr(parent)) = path.iter().rev().nth(1) {
+                    if mouse_property(parent).is_some() {
+                        check_disabled(p.2.as_str(), *pos, &mut warnings);
+                    }
+                }
+            }
+            _ => (),
+        }
+        true
+    });
+
+    warnings
+}
@@ -0,0 +1,87 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use rhai::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Shortest time between two recorded values of the same channel, in simulated seconds.
+const MIN_INTERVAL: f32 = 0.01;
+/// Values logged to more channels than this are dropped.
+const MAX_CHANNELS: usize = 32;
+
+/// The value of a channel the script logged, as saved in a frame.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelValue {
+    pub name: String,
+    pub value: f32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct State {
+    time: f32,
+    values: BTreeMap<String, (f32, f32)>, // Value and when it was recorded
+}
+
+fn record(state: &RefCell<State>, name: &str, value: f32) {
+    let state = &mut *state.borrow_mut();
+    let time = state.time;
+    let count = state.values.len();
+    match state.values.get_mut(name) {
+        Some(entry) if time - entry.1 >= MIN_INTERVAL => *entry = (value, time),
+        Some(_) => (),
+        None if count < MAX_CHANNELS => {
+            state.values.insert(name.to_string(), (value, time));
+        }
+        None => (),
+    }
+}
+
+/// Named values the script logs with `log_value`, recorded alongside the built-in telemetry.
+///
+/// Each channel keeps its last value until the script logs a new one,
+/// which is accepted at most every `MIN_INTERVAL` seconds.
+#[derive(Debug, Default, Clone)]
+pub struct Channels(Rc<RefCell<State>>);
+
+impl Channels {
+    /// Registers `log_value` in the engine.
+    pub fn attach(engine: &mut Engine) -> Self {
+        let channels = Self::default();
+        let state = channels.0.clone();
+        engine.register_fn("log_value", move |name: &str, value: f32| {
+            record(&state, name, value)
+        });
+        let state = channels.0.clone();
+        engine.register_fn("log_value", move |name: &str, value: rhai::INT| {
+            record(&state, name, value as f32)
+        });
+        channels
+    }
+
+    /// Sets the simulated time the next logged values belong to.
+    pub fn set_time(&self, time: f32) {
+        self.0.borrow_mut().time = time;
+    }
+
+    /// A copy of the values that isn't attached to the engine, to put them back with `restore`.
+    pub fn save(&self) -> Self {
+        Self(Rc::new(RefCell::new(self.0.borrow().clone())))
+    }
+
+    /// Replaces the values with the ones saved before.
+    pub fn restore(&self, saved: &Self) {
+        *self.0.borrow_mut() = saved.0.borrow().clone();
+    }
+
+    /// The current values of all channels, sorted by name.
+    pub fn values(&self) -> Vec<ChannelValue> {
+        self.0
+            .borrow()
+            .values
+            .iter()
+            .map(|(name, (value, _))| ChannelValue {
+                name: name.clone(),
+                value: *value,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,40 @@
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::sensor::gaussian;
+
+/// The clock of the controller, set in the `[clock]` section of the mouse config.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct ClockConfig {
+    pub drift_ppm: f32, // How much faster the clock runs than the simulated time, in parts per million
+    pub offset: f32,    // Time the clock shows at the start of the simulation, in seconds
+    pub jitter: f32,    // Standard deviation of the timestamp of each sample, in seconds
+    pub seed: u64,
+}
+
+/// A slightly wrong clock that stamps the samples of the sensors and encoders.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    config: ClockConfig,
+    rng: StdRng,
+}
+
+impl Clock {
+    pub fn new(config: ClockConfig) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+        }
+    }
+
+    /// What the clock shows at the simulated time.
+    pub fn now(&self, time: f32) -> f32 {
+        self.config.offset + time * (1.0 + self.config.drift_ppm * 1e-6)
+    }
+
+    /// Timestamp of a sample taken at the simulated time, off by the jitter.
+    pub fn stamp(&mut self, time: f32) -> f32 {
+        self.now(time) + gaussian(&mut self.rng) * self.config.jitter
+    }
+}
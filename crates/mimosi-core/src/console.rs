@@ -0,0 +1,86 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use rhai::{Dynamic, Engine};
+
+/// How many lines the console keeps before dropping the oldest.
+const MAX_LINES: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub time: f32, // Simulated time of the step the line was logged in
+    pub message: String,
+}
+
+/// Collects what the script logs and prints, stamped with the simulated time.
+#[derive(Debug, Default)]
+pub struct Console {
+    pub lines: VecDeque<LogLine>,
+    /// Print the lines to stdout as they come in, for runs without a window
+    pub echo: bool,
+    pending: Rc<RefCell<Vec<String>>>,
+}
+
+fn join(values: &[Dynamic]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Console {
+    /// Registers `log` and redirects `print` and `debug` of the engine into the console.
+    pub fn attach(engine: &mut Engine) -> Self {
+        let console = Self::default();
+
+        let pending = console.pending.clone();
+        engine.on_print(move |s| pending.borrow_mut().push(s.to_string()));
+        let pending = console.pending.clone();
+        engine.on_debug(move |s, _, _| pending.borrow_mut().push(s.to_string()));
+
+        let pending = console.pending.clone();
+        engine.register_fn("log", move |a: Dynamic| {
+            pending.borrow_mut().push(join(&[a]))
+        });
+        let pending = console.pending.clone();
+        engine.register_fn("log", move |a: Dynamic, b: Dynamic| {
+            pending.borrow_mut().push(join(&[a, b]))
+        });
+        let pending = console.pending.clone();
+        engine.register_fn("log", move |a: Dynamic, b: Dynamic, c: Dynamic| {
+            pending.borrow_mut().push(join(&[a, b, c]))
+        });
+        let pending = console.pending.clone();
+        engine.register_fn(
+            "log",
+            move |a: Dynamic, b: Dynamic, c: Dynamic, d: Dynamic| {
+                pending.borrow_mut().push(join(&[a, b, c, d]))
+            },
+        );
+
+        console
+    }
+
+    /// Moves the lines logged since the last call into the console.
+    pub fn collect(&mut self, time: f32) {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        for message in pending {
+            self.push(time, message);
+        }
+    }
+
+    /// Adds a line that didn't come from the script.
+    pub fn push(&mut self, time: f32, message: String) {
+        if self.echo {
+            println!("[{time:.3}s] {message}");
+        }
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(LogLine { time, message });
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
@@ -0,0 +1,75 @@
+use glam::{vec2, Vec2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::helper::Vec2Def;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DisturbanceKind {
+    /// A force that always pushes in the same direction
+    Constant {
+        #[serde(with = "Vec2Def")]
+        force: Vec2,
+    },
+    /// A force oscillating between `force` and `-force`
+    Sinusoidal {
+        #[serde(with = "Vec2Def")]
+        force: Vec2,
+        frequency: f32, // Hz
+    },
+    /// A force that randomly changes direction and strength over time
+    RandomWalk {
+        magnitude: f32, // Maximum strength of the force
+        step: f32,      // How much the force can change per second
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DisturbanceConfig {
+    #[serde(flatten)]
+    pub kind: DisturbanceKind,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Generates an external force acting on the mouse.
+#[derive(Clone)]
+pub struct Disturbance {
+    kind: DisturbanceKind,
+    rng: StdRng,
+    time: f32,
+    force: Vec2,
+    pub scale: f32, // Multiplier for the force, to tune it live
+}
+
+impl Disturbance {
+    pub fn new(DisturbanceConfig { kind, seed }: DisturbanceConfig) -> Self {
+        Self {
+            kind,
+            rng: StdRng::seed_from_u64(seed),
+            time: 0.0,
+            force: Vec2::ZERO,
+            scale: 1.0,
+        }
+    }
+
+    pub fn sample(&mut self, dt: f32) -> Vec2 {
+        self.time += dt;
+        self.force = match self.kind {
+            DisturbanceKind::Constant { force } => force,
+            DisturbanceKind::Sinusoidal { force, frequency } => {
+                force * (self.time * frequency * std::f32::consts::TAU).sin()
+            }
+            DisturbanceKind::RandomWalk { magnitude, step } => {
+                let change = vec2(
+                    self.rng.gen_range(-1.0..=1.0),
+                    self.rng.gen_range(-1.0..=1.0),
+                ) * step
+                    * dt;
+                (self.force + change).clamp_length_max(magnitude)
+            }
+        };
+        self.force * self.scale
+    }
+}
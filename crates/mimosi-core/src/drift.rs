@@ -0,0 +1,72 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DriftConfig {
+    pub ambient_temperature: f32,   // °C at the start of the run
+    pub operating_temperature: f32, // °C the electronics settle at
+    pub time_constant: f32,         // Seconds until ~63% of the warm up is done
+    pub fluctuation: f32,           // Random temperature change in °C per second
+    pub ir_scale_per_degree: f32,   // Relative change of the IR sensor readings per °C
+    pub gyro_bias_per_degree: f32,  // Gyro bias in rad/s per °C
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Slowly warms up the electronics of the mouse, which skews sensor readings.
+#[derive(Clone)]
+pub struct Drift {
+    config: DriftConfig,
+    rng: StdRng,
+    noise: f32,
+    pub temperature: f32,
+}
+
+impl Drift {
+    pub fn new(config: DriftConfig) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            temperature: config.ambient_temperature,
+            noise: 0.0,
+            config,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let DriftConfig {
+            operating_temperature,
+            time_constant,
+            fluctuation,
+            ..
+        } = self.config;
+
+        // Exponential approach towards the operating temperature
+        let base = self.temperature - self.noise;
+        let base = base + (operating_temperature - base) * (dt / time_constant.max(dt)).min(1.0);
+
+        self.noise += self.rng.gen_range(-1.0..=1.0) * fluctuation * dt;
+        self.temperature = base + self.noise;
+    }
+
+    pub fn fluctuation(&self) -> f32 {
+        self.config.fluctuation
+    }
+
+    pub fn set_fluctuation(&mut self, fluctuation: f32) {
+        self.config.fluctuation = fluctuation;
+    }
+
+    fn delta(&self) -> f32 {
+        self.temperature - self.config.ambient_temperature
+    }
+
+    /// Factor the IR sensor readings get multiplied with.
+    pub fn ir_scale(&self) -> f32 {
+        1.0 + self.config.ir_scale_per_degree * self.delta()
+    }
+
+    /// Offset added to the angular velocity measured by the gyro.
+    pub fn gyro_bias(&self) -> f32 {
+        self.config.gyro_bias_per_degree * self.delta()
+    }
+}
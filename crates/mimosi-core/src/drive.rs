@@ -0,0 +1,150 @@
+use glam::Vec2;
+
+use crate::mouse::Micromouse;
+
+/// Advances the wheels and pose of the mouse based on the commanded wheel powers.
+///
+/// Implement this to swap in alternative dynamics without changing the script API.
+pub trait DriveModel {
+    fn update(&self, mouse: &mut Micromouse, dt: f32, maze_friction: f32, external_force: Vec2);
+}
+
+/// The default drive model, a simple differential drive with linear friction.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DifferentialDrive;
+
+impl DriveModel for DifferentialDrive {
+    fn update(&self, mouse: &mut Micromouse, dt: f32, maze_friction: f32, external_force: Vec2) {
+        // The motors only get what the driver makes of the commanded powers
+        let (left_power, right_power) = mouse.applied_powers();
+        mouse.in_deadband = (left_power == 0.0 && mouse.left_power != 0.0)
+            || (right_power == 0.0 && mouse.right_power != 0.0);
+
+        // Calculate acceleration based on power input and friction
+        let left_acceleration =
+            self.calculate_acceleration(mouse, left_power, mouse.left_velocity, maze_friction);
+        let right_acceleration =
+            self.calculate_acceleration(mouse, right_power, mouse.right_velocity, maze_friction);
+
+        let (left_acceleration, left_limited) =
+            self.limit_acceleration(mouse, left_acceleration, mouse.left_acceleration, dt);
+        let (right_acceleration, right_limited) =
+            self.limit_acceleration(mouse, right_acceleration, mouse.right_acceleration, dt);
+        mouse.left_acceleration = left_acceleration;
+        mouse.right_acceleration = right_acceleration;
+        mouse.limited = left_limited || right_limited;
+
+        // Split the external force into a part along the heading and one across it
+        let heading = Vec2::from_angle(mouse.orientation);
+        let push_acceleration = external_force.dot(heading) / mouse.mass;
+        let slip_acceleration = external_force.dot(heading.perp()) / mouse.mass;
+
+        // Update velocities
+        mouse.left_velocity += (left_acceleration + push_acceleration) * dt;
+        mouse.right_velocity += (right_acceleration + push_acceleration) * dt;
+        mouse.lateral_velocity += slip_acceleration * dt;
+
+        // Cap velocities at max speed
+        mouse.left_velocity = mouse.left_velocity.clamp(-mouse.max_speed, mouse.max_speed);
+        mouse.right_velocity = mouse
+            .right_velocity
+            .clamp(-mouse.max_speed, mouse.max_speed);
+
+        // Calculate average speed and turning rate
+        let average_velocity = (mouse.left_velocity + mouse.right_velocity) / 2.0;
+        let turning_rate = (mouse.left_velocity - mouse.right_velocity) / mouse.wheel_base;
+
+        // Update orientation and position
+        mouse.orientation += turning_rate * dt;
+        mouse.position.x += average_velocity * mouse.orientation.cos() * dt;
+        mouse.position.y += average_velocity * mouse.orientation.sin() * dt;
+        mouse.position += heading.perp() * mouse.lateral_velocity * dt;
+
+        mouse.update_wheel_encoders(dt);
+
+        // Apply friction to slow down
+        self.apply_friction(mouse, dt, maze_friction);
+    }
+}
+
+impl DifferentialDrive {
+    pub fn calculate_acceleration(
+        &self,
+        mouse: &Micromouse,
+        power: f32,
+        current_velocity: f32,
+        maze_friction: f32,
+    ) -> f32 {
+        // Frictional force
+        let friction_force = (mouse.wheel_friction + maze_friction) * current_velocity.abs();
+
+        if let Some(motor) = &mouse.motor {
+            let motor_force = motor.force(power, current_velocity, mouse.wheel_radius);
+            // Friction always works against the direction the wheel turns
+            let net_force = motor_force - friction_force.copysign(current_velocity);
+            // Each motor moves half of the mouse
+            return net_force / (mouse.mass / 2.0);
+        }
+
+        // Force applied by the motor (simple model: power * max force)
+        let motor_force = power * mouse.max_speed;
+
+        // Net force = motor force - frictional force
+        let net_force = motor_force - friction_force.copysign(motor_force);
+
+        // Acceleration = net force / mass
+        net_force / mouse.mass
+    }
+
+    /// Clamps the acceleration of a wheel to the configured acceleration and jerk limits.
+    ///
+    /// Returns the clamped acceleration and whether any limit engaged.
+    pub fn limit_acceleration(
+        &self,
+        mouse: &Micromouse,
+        acceleration: f32,
+        previous_acceleration: f32,
+        dt: f32,
+    ) -> (f32, bool) {
+        let mut limited = acceleration;
+        if let Some(max) = mouse.max_acceleration {
+            limited = limited.clamp(-max, max);
+        }
+        if let Some(max) = mouse.max_jerk {
+            let change = max * dt;
+            limited = limited.clamp(
+                previous_acceleration - change,
+                previous_acceleration + change,
+            );
+        }
+        (limited, limited != acceleration)
+    }
+
+    pub fn apply_friction(&self, mouse: &mut Micromouse, dt: f32, maze_friction: f32) {
+        // Reduce the wheel velocities due to friction
+        let friction_force = mouse.wheel_friction + maze_friction;
+
+        mouse.left_velocity -= mouse.left_velocity * friction_force * dt;
+        mouse.right_velocity -= mouse.right_velocity * friction_force * dt;
+        mouse.lateral_velocity -= mouse.lateral_velocity * friction_force * dt;
+
+        // A braking wheel without power is slowed down by the shorted motor
+        if mouse.left_brake && mouse.left_power == 0.0 {
+            mouse.left_velocity -= mouse.left_velocity * (mouse.brake_friction * dt).min(1.0);
+        }
+        if mouse.right_brake && mouse.right_power == 0.0 {
+            mouse.right_velocity -= mouse.right_velocity * (mouse.brake_friction * dt).min(1.0);
+        }
+
+        // Clamp small velocities to zero to simulate stopping due to friction
+        if mouse.left_velocity.abs() < 0.001 {
+            mouse.left_velocity = 0.0;
+        }
+        if mouse.right_velocity.abs() < 0.001 {
+            mouse.right_velocity = 0.0;
+        }
+        if mouse.lateral_velocity.abs() < 0.001 {
+            mouse.lateral_velocity = 0.0;
+        }
+    }
+}
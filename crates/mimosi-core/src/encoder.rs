@@ -0,0 +1,59 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// How the wheel encoders count, besides their resolution.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct EncoderConfig {
+    #[serde(default)]
+    pub quadrature: bool, // Count every edge of both channels, four counts per line of the encoder
+    #[serde(default)]
+    pub missed_ticks: f32, // Chance that a count gets lost, e.g. on a dirty code wheel
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl EncoderConfig {
+    /// Counts per turn of the wheel of an encoder with this many lines.
+    pub fn counts_per_turn(&self, resolution: usize) -> usize {
+        if self.quadrature {
+            resolution * 4
+        } else {
+            resolution
+        }
+    }
+}
+
+/// The count of a wheel encoder, which follows the exact rotation of the wheel in fractions of a count.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    config: EncoderConfig,
+    rng: StdRng,
+    exact: f64, // Counts the wheel turned, without the missed ones
+    pub count: i64,
+}
+
+impl Encoder {
+    pub fn new(config: EncoderConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            exact: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Turns the wheel by a number of counts, negative when driving backwards.
+    pub fn advance(&mut self, counts: f64) {
+        let before = self.exact.floor();
+        self.exact += counts;
+        let ticks = (self.exact.floor() - before) as i64;
+        let missed = if self.config.missed_ticks > 0.0 {
+            (0..ticks.abs())
+                .filter(|_| self.rng.gen::<f32>() < self.config.missed_ticks)
+                .count() as i64
+        } else {
+            0
+        };
+        self.count += ticks - ticks.signum() * missed;
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::{vec2, Vec2};
+use mazeparser::grid::Cell;
+use rhai::{
+    packages::{CorePackage, Package},
+    Array, CustomType, Dynamic, Engine, TypeBuilder, INT,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    motion::MotionProfile,
+    mouse::Sensor,
+    odometry::PoseEstimate,
+    pid::Pid,
+    planner::register_grid,
+    run_state::RunState,
+    turn::{Turn, TurnConfig},
+};
+
+#[derive(Clone, CustomType, Debug)]
+pub struct MouseData {
+    #[rhai_type(readonly)]
+    pub wheel_base: f32,
+    #[rhai_type(readonly)]
+    pub wheel_friction: f32,
+    #[rhai_type(readonly)]
+    pub mass: f32, // Mass of the micromouse
+
+    pub encoder_resolution: usize,
+
+    #[rhai_type(readonly)]
+    pub crashed: bool,
+
+    #[rhai_type(readonly)]
+    pub calibrating: bool, // If the run hasn't started yet and the mouse may calibrate its sensors
+
+    #[rhai_type(readonly)]
+    pub delta_time: f32,
+
+    #[rhai_type(readonly)]
+    pub width: f32, // Width of the mouse
+    #[rhai_type(readonly)]
+    pub length: f32, // Length of the mouse (not including the triangle)
+    #[rhai_type(readonly)]
+    pub sensors: Sensors,
+
+    #[rhai_type(readonly)]
+    pub temperature: f32, // Temperature of the electronics in °C
+
+    #[rhai_type(readonly)]
+    pub limited: bool, // If the acceleration or jerk limit engaged in the last update
+
+    #[rhai_type(readonly)]
+    pub in_deadband: bool, // If a commanded power was too small to move its motor in the last update
+
+    #[rhai_type(readonly)]
+    pub gyro_z: f32, // Turning rate in degrees per second, positive to the right
+    #[rhai_type(readonly)]
+    pub accel_x: f32, // Acceleration along the heading
+    #[rhai_type(readonly)]
+    pub accel_y: f32, // Acceleration to the right of the heading
+
+    #[rhai_type(readonly)]
+    pub left_encoder: INT, // Counts down when the wheel turns backwards
+    #[rhai_type(readonly)]
+    pub right_encoder: INT,
+    #[rhai_type(readonly)]
+    pub encoder_timestamp: f32, // Time on the controller clock when the encoders were read
+
+    #[rhai_type(readonly)]
+    pub clock: f32, // Time on the controller clock, which may drift from the simulated time
+
+    #[rhai_type(readonly)]
+    pub run: RunState, // Phase, timers and score of the run
+
+    #[rhai_type(set=MouseData::set_left_power, get=MouseData::get_left_power)]
+    pub left_power: f32,
+
+    #[rhai_type(set=MouseData::set_right_power, get=MouseData::get_right_power)]
+    pub right_power: f32,
+
+    pub left_brake: bool, // Short the motor when the power is zero instead of coasting
+    pub right_brake: bool,
+
+    #[rhai_type(skip)]
+    pub position: Vec2,
+    #[rhai_type(skip)]
+    pub orientation: f32,
+    #[rhai_type(skip)]
+    pub turns: TurnConfig,
+    #[rhai_type(skip)]
+    pub planned_turn: Option<Turn>,
+    #[rhai_type(skip)]
+    pub odometry: Option<PoseEstimate>,
+    #[rhai_type(skip)]
+    pub calibrated: bool, // Set by the script to start the run
+    #[rhai_type(skip)]
+    pub localized: Option<Cell>, // Cell the script believes the mouse is in
+    #[rhai_type(skip)]
+    pub maze_width: usize, // In cells
+    #[rhai_type(skip)]
+    pub maze_height: usize, // In cells
+    #[rhai_type(skip)]
+    pub cell_size: f32, // Side of a cell in the units of the positions
+}
+
+impl MouseData {
+    pub fn set_left_power(&mut self, power: f32) {
+        self.left_power = power.clamp(-1.0, 1.0);
+    }
+
+    pub fn get_left_power(&self) -> f32 {
+        self.left_power
+    }
+
+    pub fn set_right_power(&mut self, power: f32) {
+        self.right_power = power.clamp(-1.0, 1.0);
+    }
+
+    pub fn get_right_power(&self) -> f32 {
+        self.right_power
+    }
+
+    /// Draws the rest of the turn in front of the mouse.
+    pub fn show_turn(&mut self, turn: Turn) {
+        self.planned_turn = Some(turn);
+    }
+
+    /// Ends the calibration, the run starts with the next step.
+    pub fn finish_calibration(&mut self) {
+        self.calibrated = true;
+    }
+
+    /// Reports the cell the script believes the mouse is in, to score the re-localization after a kidnapping.
+    pub fn localize(&mut self, x: rhai::INT, y: rhai::INT) {
+        self.localized = usize::try_from(x).ok().zip(usize::try_from(y).ok());
+    }
+
+    /// The cell containing a position as `[x, y]`, which is outside the maze for positions outside of it.
+    pub fn cell_at(&mut self, x: f32, y: f32) -> Array {
+        let cell = (vec2(x, y) / self.cell_size).floor();
+        vec![(cell.x as INT).into(), (cell.y as INT).into()]
+    }
+
+    /// The position of the center of a cell as `[x, y]`.
+    pub fn cell_center(&mut self, x: INT, y: INT) -> Array {
+        let center = (vec2(x as f32, y as f32) + 0.5) * self.cell_size;
+        vec![center.x.into(), center.y.into()]
+    }
+}
+
+#[derive(Clone, CustomType, Debug, Default)]
+pub struct SensorInfo {
+    #[rhai_type(readonly)]
+    pub position_offset: Vec2, // Offset relative to the center of the rectangle
+    #[rhai_type(readonly)]
+    pub angle: f32, // Angle in radians
+    #[rhai_type(readonly)]
+    pub value: f32,
+    #[rhai_type(readonly)]
+    pub timestamp: f32, // Time on the controller clock when the value was measured
+}
+
+impl From<&Sensor> for SensorInfo {
+    fn from(
+        Sensor {
+            position_offset,
+            angle,
+            value,
+            timestamp,
+            ..
+        }: &Sensor,
+    ) -> Self {
+        Self {
+            position_offset: *position_offset,
+            angle: angle.to_degrees(),
+            value: *value,
+            timestamp: *timestamp,
+        }
+    }
+}
+
+impl From<Sensor> for SensorInfo {
+    fn from(sensor: Sensor) -> Self {
+        (&sensor).into()
+    }
+}
+
+#[derive(Clone, CustomType, Debug)]
+pub struct Sensors(#[rhai_type(skip)] pub HashMap<String, SensorInfo>);
+
+impl IntoIterator for Sensors {
+    type Item = (String, SensorInfo);
+
+    type IntoIter = std::collections::hash_map::IntoIter<String, SensorInfo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Sensors {
+    fn get_sensors(&mut self, index: &str) -> SensorInfo {
+        self.0[index].clone()
+    }
+}
+
+/// Groups of functions that can be taken away from scripts, e.g. to keep competitions fair.
+///
+/// Opt-in categories are only available when a scenario enables them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiCategory {
+    /// Functions that print internal state
+    Debug,
+    /// Access to the true pose of the mouse, opt-in
+    CheatPose,
+    /// Information about the maze layout
+    MazeInfo,
+    /// Path planning on the wall map of the script
+    Planner,
+}
+
+impl ApiCategory {
+    pub const ALL: [ApiCategory; 4] = [
+        ApiCategory::Debug,
+        ApiCategory::CheatPose,
+        ApiCategory::MazeInfo,
+        ApiCategory::Planner,
+    ];
+
+    /// Whether scripts only get these functions when a scenario enables them.
+    pub fn is_opt_in(&self) -> bool {
+        matches!(self, ApiCategory::CheatPose)
+    }
+
+    /// Names of the functions and properties belonging to this category.
+    pub fn functions(&self) -> &'static [&'static str] {
+        match self {
+            ApiCategory::Debug => &["to_debug"],
+            ApiCategory::CheatPose => &["x", "y", "orientation"],
+            ApiCategory::MazeInfo => &[
+                "maze_width",
+                "maze_height",
+                "cell_size",
+                "cell_at",
+                "cell_center",
+            ],
+            ApiCategory::Planner => &[
+                "shortest_path",
+                "next_move",
+                "diagonal_path",
+                "estimate_time",
+            ],
+        }
+    }
+}
+
+/// Fields of `MouseData` that scripts can't write to.
+#[cfg(feature = "internal")]
+pub const READONLY_FIELDS: &[&str] = &[
+    "wheel_base",
+    "wheel_friction",
+    "mass",
+    "crashed",
+    "calibrating",
+    "delta_time",
+    "width",
+    "length",
+    "sensors",
+    "temperature",
+    "limited",
+    "in_deadband",
+    "gyro_z",
+    "accel_x",
+    "accel_y",
+    "left_encoder",
+    "right_encoder",
+    "encoder_timestamp",
+    "clock",
+    "run",
+    "odometry",
+    "maze_width",
+    "maze_height",
+    "cell_size",
+    "x",
+    "y",
+    "orientation",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct EngineConfig {
+    pub enabled: HashSet<ApiCategory>, // Opt-in categories that scripts may use
+    pub disabled: HashSet<ApiCategory>,
+}
+
+impl EngineConfig {
+    pub fn is_enabled(&self, category: ApiCategory) -> bool {
+        (!category.is_opt_in() || self.enabled.contains(&category))
+            && !self.disabled.contains(&category)
+    }
+}
+
+pub fn build_engine(config: &EngineConfig) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_expr_depths(128, 64);
+
+    let package = CorePackage::new();
+
+    // Register the package into the 'Engine' by converting it into a shared module.
+    engine.register_global_module(package.as_shared_module());
+
+    engine
+        .build_type::<MouseData>()
+        .build_type::<SensorInfo>()
+        .build_type::<Sensors>()
+        .build_type::<MotionProfile>()
+        .build_type::<Pid>()
+        .build_type::<RunState>()
+        .build_type::<Turn>()
+        .build_type::<PoseEstimate>()
+        .register_fn("show_turn", MouseData::show_turn)
+        .register_fn("finish_calibration", MouseData::finish_calibration)
+        .register_fn("localize", MouseData::localize)
+        // `()` if the mouse has no odometry configured
+        .register_get("odometry", |d: &mut MouseData| {
+            d.odometry.map_or(Dynamic::UNIT, Dynamic::from)
+        })
+        .register_iterator::<Sensors>()
+        .register_indexer_get(Sensors::get_sensors)
+        .register_fn("to_int", |v: usize| v as rhai::INT);
+
+    register_grid(&mut engine, config.is_enabled(ApiCategory::Planner));
+
+    if config.is_enabled(ApiCategory::Debug) {
+        engine.register_fn("to_debug", |d: MouseData| format!("{d:#?}"));
+    }
+
+    if config.is_enabled(ApiCategory::MazeInfo) {
+        engine
+            .register_get("maze_width", |d: &mut MouseData| d.maze_width as INT)
+            .register_get("maze_height", |d: &mut MouseData| d.maze_height as INT)
+            .register_get("cell_size", |d: &mut MouseData| d.cell_size)
+            .register_fn("cell_at", MouseData::cell_at)
+            .register_fn("cell_center", MouseData::cell_center);
+    }
+
+    if config.is_enabled(ApiCategory::CheatPose) {
+        engine
+            .register_get("x", |d: &mut MouseData| d.position.x)
+            .register_get("y", |d: &mut MouseData| d.position.y)
+            .register_get("orientation", |d: &mut MouseData| {
+                d.orientation.to_degrees()
+            });
+    }
+
+    engine
+}
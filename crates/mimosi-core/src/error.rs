@@ -0,0 +1,141 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+/// What can go wrong while loading the files of a simulation, setting it up and running it.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A config file that isn't valid TOML or doesn't fit the expected structure
+    #[error("{name}:{line}:{column}: {message}")]
+    Parse {
+        name: String, // File name, or what the text is if it didn't come from a file
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A maze the parser rejected
+    #[error("{name}:{line}: {message}")]
+    Maze {
+        name: String,
+        line: usize,
+        message: String,
+    },
+    #[error("Script error in line {line}, column {column}: {message}")]
+    Script {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("The finish can't be reached from the start. Set `allow_unreachable_goal = true` in the scenario to explore the maze anyway.")]
+    UnreachableFinish,
+    /// A file that isn't in the format it should be, or a value that can't be written in it
+    #[error("{name}: {message}")]
+    Format { name: String, message: String },
+    /// A controller that couldn't be connected or loaded
+    #[error("Controller: {0}")]
+    Controller(String),
+    #[error("{address}: {message}")]
+    Network { address: String, message: String },
+    /// An error in one of several files of the same kind, like the scripts of a race
+    #[error("{name}: {source}")]
+    In { name: String, source: Box<Error> },
+    /// The window of the binary couldn't be opened
+    #[error("{0}")]
+    Window(String),
+    /// An argument or setting that doesn't fit, like a start cell outside of the maze
+    #[error("{0}")]
+    Invalid(String),
+    /// Problems a check found, after it listed them
+    #[error("Found {count} {what}")]
+    Found { count: usize, what: &'static str },
+}
+
+impl Error {
+    /// Wraps an error of the TOML parser, with the position of the problem counted from 1.
+    pub fn toml(name: &str, source: &str, error: toml::de::Error) -> Self {
+        let offset = error.span().map_or(0, |span| span.start).min(source.len());
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        Self::Parse {
+            name: name.to_string(),
+            line,
+            column,
+            message: error.message().to_string(),
+        }
+    }
+
+    /// Wraps an error of the JSON parser, which already counts lines and columns from 1.
+    pub fn json(name: &str, error: serde_json::Error) -> Self {
+        let position = format!(" at line {} column {}", error.line(), error.column());
+        Self::Parse {
+            name: name.to_string(),
+            line: error.line(),
+            column: error.column(),
+            message: error.to_string().trim_end_matches(&position).to_string(),
+        }
+    }
+
+    pub fn maze(name: &str, error: mazeparser::ParseError) -> Self {
+        Self::Maze {
+            name: name.to_string(),
+            line: error.line,
+            message: error.message,
+        }
+    }
+
+    pub fn format(name: impl Display, message: impl Display) -> Self {
+        Self::Format {
+            name: name.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn script(error: rhai::ParseError) -> Self {
+        Self::Script {
+            line: error.1.line().unwrap_or(0),
+            column: error.1.position().unwrap_or(0),
+            message: error.0.to_string(),
+        }
+    }
+
+    /// Wraps an error of a running script, like a function it called that doesn't exist.
+    pub fn runtime(mut error: Box<rhai::EvalAltResult>) -> Self {
+        let position = error.take_position();
+        Self::Script {
+            line: position.line().unwrap_or(0),
+            column: position.position().unwrap_or(0),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Reads a whole file, with its path in the error.
+pub fn read(path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|source| Error::io(path, source))
+}
+
+/// Writes a whole file, with its path in the error.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    std::fs::write(path, contents).map_err(|source| Error::io(path, source))
+}
+
+/// Parses a config in TOML, with the name and position in the error.
+pub fn from_toml<T: DeserializeOwned>(name: &str, source: &str) -> Result<T, Error> {
+    toml::from_str(source).map_err(|e| Error::toml(name, source, e))
+}
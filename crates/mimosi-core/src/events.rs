@@ -0,0 +1,58 @@
+use glam::Vec2;
+use mazeparser::grid::Cell;
+use serde::{Deserialize, Serialize};
+
+use crate::helper::Vec2Def;
+
+/// A cell marked on the floor of the maze.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Marker {
+    Start,
+    Goal, // Any cell of the finish
+}
+
+/// Something noteworthy that happened during a run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Event {
+    /// The mouse was moved to a new pose by hand
+    Teleport {
+        #[serde(with = "Vec2Def")]
+        position: Vec2,
+        orientation: f32,
+    },
+    /// The mouse was moved to a random cell by the scenario, without telling the script
+    Kidnap {
+        #[serde(with = "Vec2Def")]
+        position: Vec2,
+        orientation: f32,
+    },
+    /// The script reported the right cell after a kidnapping
+    Relocalized { after: f32 },
+    /// The mouse drove into a wall
+    Crash,
+    /// The script failed with an error, which the scenario ended the run for or skipped
+    ScriptError { message: String },
+    /// The mouse touched a wall and slid along it
+    WallTouch,
+    /// The calibration ended and the mouse was put back to the start
+    RunStart,
+    /// The mouse reached the finish
+    Finish,
+    /// The mouse entered a different cell
+    CellChange { cell: Cell },
+    /// The mouse drove over a ramp onto another layer of the maze
+    LayerChange { layer: usize },
+    /// The mouse entered or left the start cell or the goal area, judged by the cell its center is in
+    Marker {
+        marker: Marker,
+        entered: bool,
+        tick: u64, // Physics step it happened in
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimedEvent {
+    pub time: f32, // Simulated time in seconds
+    pub event: Event,
+}
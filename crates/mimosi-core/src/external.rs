@@ -0,0 +1,171 @@
+#[cfg(feature = "internal")]
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+use mazeparser::grid::Cell;
+use serde::Deserialize;
+#[cfg(feature = "internal")]
+use serde::Serialize;
+
+use crate::engine::MouseData;
+#[cfg(feature = "internal")]
+use crate::{error::Error, mouse::Micromouse, run_state::RunState};
+
+/// How long to wait for the controller to answer a tick before giving up.
+#[cfg(feature = "internal")]
+pub(crate) const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the simulator sends, one JSON object per line.
+#[cfg(feature = "internal")]
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Message<'a> {
+    /// Sent once after connecting, with the dimensions of the mouse
+    Hello {
+        wheel_base: f32,
+        width: f32,
+        length: f32,
+        mass: f32,
+        encoder_resolution: usize,
+        sensors: BTreeMap<&'a str, f32>, // Mounting angle in degrees
+    },
+    /// Sent every physics step, the controller answers with a `Command`
+    Tick {
+        time: f32,
+        delta_time: f32,
+        calibrating: bool,
+        crashed: bool,
+        sensors: BTreeMap<&'a str, f32>,
+        left_encoder: i64,
+        right_encoder: i64,
+        gyro_z: f32,
+        accel_x: f32,
+        accel_y: f32,
+        temperature: f32,
+        run: &'a RunState,
+    },
+}
+
+#[cfg(feature = "internal")]
+impl<'a> Message<'a> {
+    pub(crate) fn hello(mouse: &'a Micromouse) -> Self {
+        Message::Hello {
+            wheel_base: mouse.wheel_base,
+            width: mouse.width,
+            length: mouse.length,
+            mass: mouse.mass,
+            encoder_resolution: mouse.encoder_resolution,
+            sensors: mouse
+                .sensors
+                .iter()
+                .map(|(name, sensor)| (name.as_str(), sensor.angle.to_degrees()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn tick(time: f32, mouse: &'a MouseData) -> Self {
+        Message::Tick {
+            time,
+            delta_time: mouse.delta_time,
+            calibrating: mouse.calibrating,
+            crashed: mouse.crashed,
+            sensors: mouse
+                .sensors
+                .0
+                .iter()
+                .map(|(name, sensor)| (name.as_str(), sensor.value))
+                .collect(),
+            left_encoder: mouse.left_encoder,
+            right_encoder: mouse.right_encoder,
+            gyro_z: mouse.gyro_z,
+            accel_x: mouse.accel_x,
+            accel_y: mouse.accel_y,
+            temperature: mouse.temperature,
+            run: &mouse.run,
+        }
+    }
+
+    /// The message as a line of JSON, which is how it goes over every transport.
+    pub(crate) fn to_line(&self) -> Result<String, String> {
+        let mut json = serde_json::to_string(self).map_err(|e| format!("{e}"))?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// The answer of the controller to a tick. Missing fields keep their default.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Command {
+    pub left_power: f32,
+    pub right_power: f32,
+    pub left_brake: bool,
+    pub right_brake: bool,
+    pub calibrated: bool, // Ends the calibration, like `mouse.finish_calibration()`
+    pub localized: Option<Cell>, // Cell the controller believes the mouse is in, like `mouse.localize(x, y)`
+    pub log: Option<String>,     // Shown in the console
+}
+
+/// Something outside the script that drives the mouse.
+pub trait Controller {
+    /// Gets the commands for the next step from the state of the mouse.
+    fn exchange(&mut self, time: f32, mouse: &MouseData) -> Result<Command, String>;
+}
+
+/// A controller running in another process, which drives the mouse over TCP instead of the script.
+#[cfg(feature = "internal")]
+pub struct ExternalController {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    line: String,
+}
+
+#[cfg(feature = "internal")]
+impl ExternalController {
+    /// Waits until a controller connects to the address and greets it with the dimensions of the mouse.
+    pub fn listen(address: &str, mouse: &Micromouse) -> Result<Self, Error> {
+        let error = |e: std::io::Error| Error::Controller(format!("{address}: {e}"));
+        let listener = TcpListener::bind(address).map_err(error)?;
+        let (stream, _) = listener.accept().map_err(error)?;
+        stream.set_nodelay(true).map_err(error)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(error)?;
+        let mut controller = Self {
+            reader: BufReader::new(stream.try_clone().map_err(error)?),
+            writer: stream,
+            line: String::new(),
+        };
+        controller
+            .send(&Message::hello(mouse))
+            .map_err(Error::Controller)?;
+        Ok(controller)
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), String> {
+        self.writer
+            .write_all(message.to_line()?.as_bytes())
+            .map_err(|e| format!("Controller disconnected: {e}"))
+    }
+}
+
+#[cfg(feature = "internal")]
+impl Controller for ExternalController {
+    /// Sends the state of the mouse and waits for the wheel powers to use for the next step.
+    fn exchange(&mut self, time: f32, mouse: &MouseData) -> Result<Command, String> {
+        self.send(&Message::tick(time, mouse))?;
+
+        self.line.clear();
+        let read = self
+            .reader
+            .read_line(&mut self.line)
+            .map_err(|e| format!("Controller didn't answer: {e}"))?;
+        if read == 0 {
+            return Err(String::from("Controller disconnected"));
+        }
+        serde_json::from_str(&self.line)
+            .map_err(|e| format!("Invalid command from controller: {e}"))
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+
+use glam::Vec2;
+use mazeparser::grid::{Cell, DIRECTIONS, EAST, NORTH, SOUTH};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    helper::{DOWN, LEFT, RIGHT, UP},
+    maze::Maze,
+};
+
+/// Moves the mouse to a random cell during the run without telling the script,
+/// to test if it finds out where it is again (the kidnapped robot problem).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct KidnapConfig {
+    pub times: Vec<f32>, // Seconds into the run at which the mouse is moved
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// A single kidnapping and how the script dealt with it.
+#[derive(Debug, Clone, Copy)]
+pub struct Kidnapping {
+    pub time: f32, // Seconds into the run
+    pub cell: Cell,
+    pub relocalized: Option<f32>, // Seconds until the script reported the right cell
+}
+
+/// Kidnaps the mouse at the configured times and keeps track of the re-localization.
+#[derive(Clone)]
+pub struct Kidnapper {
+    times: Vec<f32>, // Still to come, latest first
+    rng: StdRng,
+    pub kidnappings: Vec<Kidnapping>,
+    pub finished: bool, // If the mouse reached the finish after the first kidnapping
+}
+
+impl Kidnapper {
+    pub fn new(KidnapConfig { mut times, seed }: KidnapConfig) -> Self {
+        times.sort_by(|a, b| b.total_cmp(a));
+        Self {
+            times,
+            rng: StdRng::seed_from_u64(seed),
+            kidnappings: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// The pose to move the mouse to if a kidnapping is due.
+    ///
+    /// The mouse is put in the center of a random cell reachable from the start, which is neither
+    /// the one it is in nor part of the finish, facing a side without a wall.
+    pub fn update(
+        &mut self,
+        run_time: f32,
+        maze: &Maze,
+        current: Option<Cell>,
+        finished: bool,
+    ) -> Option<(Vec2, f32)> {
+        if finished {
+            self.finished |= !self.kidnappings.is_empty();
+            return None;
+        }
+        if !self.times.last().is_some_and(|&time| run_time >= time) {
+            return None;
+        }
+        self.times.pop();
+
+        let grid = &maze.grid;
+        let distances = grid.distances(&[maze.start_cell]);
+        let cells: Vec<Cell> = grid
+            .cells()
+            .filter(|&cell| distances[cell.1 * grid.width + cell.0].is_some())
+            .filter(|&cell| Some(cell) != current && !maze.finish_cells.contains(&cell))
+            .collect();
+        let &cell = cells.choose(&mut self.rng)?;
+        let open: Vec<u8> = DIRECTIONS
+            .into_iter()
+            .filter(|&direction| !grid.has_wall(cell, direction))
+            .collect();
+        let orientation = match open.choose(&mut self.rng) {
+            Some(&NORTH) => UP,
+            Some(&EAST) => RIGHT,
+            Some(&SOUTH) => DOWN,
+            _ => LEFT,
+        };
+        self.kidnappings.push(Kidnapping {
+            time: run_time,
+            cell,
+            relocalized: None,
+        });
+        Some((maze.cell_center(cell), orientation))
+    }
+
+    /// Checks the cell the script believes the mouse is in against the actual one.
+    /// Returns how long the re-localization took if this is the first correct report since the last kidnapping.
+    pub fn report(&mut self, run_time: f32, believed: Cell, actual: Option<Cell>) -> Option<f32> {
+        let kidnapping = self.kidnappings.last_mut()?;
+        if kidnapping.relocalized.is_some() || Some(believed) != actual {
+            return None;
+        }
+        let after = run_time - kidnapping.time;
+        kidnapping.relocalized = Some(after);
+        Some(after)
+    }
+
+    /// Number of kidnappings after which the script found the mouse again.
+    pub fn relocalized(&self) -> usize {
+        self.kidnappings
+            .iter()
+            .filter(|k| k.relocalized.is_some())
+            .count()
+    }
+}
+
+impl Display for Kidnapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, kidnapping) in self.kidnappings.iter().enumerate() {
+            let (x, y) = kidnapping.cell;
+            write!(
+                f,
+                "Kidnapping {}: at {:.2}s to ({x}, {y}), ",
+                i + 1,
+                kidnapping.time
+            )?;
+            match kidnapping.relocalized {
+                Some(after) => writeln!(f, "re-localized after {after:.2}s")?,
+                None => writeln!(f, "not re-localized")?,
+            }
+        }
+        if !self.kidnappings.is_empty() {
+            writeln!(
+                f,
+                "Finished after being kidnapped: {}",
+                if self.finished { "yes" } else { "no" }
+            )?;
+        }
+        Ok(())
+    }
+}
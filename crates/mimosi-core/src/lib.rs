@@ -0,0 +1,110 @@
+//! The simulation behind mimosi: the maze, the mouse, its sensors and the Rhai engine running the script.
+//!
+//! Nothing here draws, so it can be used by tools that run the simulation on their own.
+//! Use the [`prelude`], which stays stable between minor versions.
+
+// Everything the prelude hands out has to be reachable by name from it
+#![warn(unnameable_types)]
+
+pub mod prelude;
+
+// Shared with the mimosi binary through `internal`
+#[cfg(feature = "internal")]
+mod analysis;
+mod channels;
+mod console;
+mod error;
+mod events;
+mod external;
+mod kidnap;
+mod manual;
+mod maze;
+mod mouse;
+mod replay;
+mod run_state;
+mod scenario;
+#[cfg(feature = "internal")]
+mod shared_memory;
+mod simulation;
+#[cfg(feature = "internal")]
+mod validate;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+mod clock;
+mod disturbance;
+mod drift;
+mod drive;
+mod encoder;
+mod engine;
+mod helper;
+mod motion;
+mod motor;
+mod odometry;
+mod pid;
+mod planner;
+mod ray;
+mod score;
+mod sensor;
+mod trail;
+mod turn;
+mod wall_index;
+
+/// The modules the mimosi binary is built on, enabled by the `internal` feature.
+///
+/// Unstable: anything in here can change with any release, unlike the [`prelude`].
+#[cfg(feature = "internal")]
+pub mod internal {
+    pub mod analysis {
+        pub use crate::analysis::*;
+    }
+    pub mod channels {
+        pub use crate::channels::*;
+    }
+    pub mod console {
+        pub use crate::console::*;
+    }
+    pub mod error {
+        pub use crate::error::*;
+    }
+    pub mod events {
+        pub use crate::events::*;
+    }
+    pub mod external {
+        pub use crate::external::*;
+    }
+    pub mod kidnap {
+        pub use crate::kidnap::*;
+    }
+    pub mod manual {
+        pub use crate::manual::*;
+    }
+    pub mod maze {
+        pub use crate::maze::*;
+    }
+    pub mod mouse {
+        pub use crate::mouse::*;
+    }
+    pub mod replay {
+        pub use crate::replay::*;
+    }
+    pub mod run_state {
+        pub use crate::run_state::*;
+    }
+    pub mod scenario {
+        pub use crate::scenario::*;
+    }
+    pub mod shared_memory {
+        pub use crate::shared_memory::*;
+    }
+    pub mod simulation {
+        pub use crate::simulation::*;
+    }
+    pub mod validate {
+        pub use crate::validate::*;
+    }
+    #[cfg(feature = "wasm")]
+    pub mod wasm {
+        pub use crate::wasm::*;
+    }
+}
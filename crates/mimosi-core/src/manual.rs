@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+/// Power of the wheels while driving straight by hand.
+#[cfg(feature = "internal")]
+const DRIVE_POWER: f32 = 0.8;
+/// Power difference between the wheels while turning by hand.
+#[cfg(feature = "internal")]
+const TURN_POWER: f32 = 0.4;
+
+/// Wheel powers for the arrow keys that are held down.
+#[cfg(feature = "internal")]
+pub fn manual_powers(up: bool, down: bool, left: bool, right: bool) -> (f32, f32) {
+    let forward = (up as i32 - down as i32) as f32 * DRIVE_POWER;
+    let turn = (right as i32 - left as i32) as f32 * TURN_POWER;
+    (
+        (forward + turn).clamp(-1.0, 1.0),
+        (forward - turn).clamp(-1.0, 1.0),
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DriveCommand {
+    pub time: f32,
+    pub left_power: f32,
+    pub right_power: f32,
+}
+
+/// Wheel powers over time, recorded while driving by hand.
+///
+/// Played back with a fixed physics step, a track reproduces the same run every time.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommandTrack {
+    pub commands: Vec<DriveCommand>,
+}
+
+impl CommandTrack {
+    /// Adds a command, unless the powers didn't change.
+    pub fn record(&mut self, time: f32, left_power: f32, right_power: f32) {
+        let last = self.commands.last().map(|c| (c.left_power, c.right_power));
+        if last != Some((left_power, right_power)) {
+            self.commands.push(DriveCommand {
+                time,
+                left_power,
+                right_power,
+            });
+        }
+    }
+
+    /// The wheel powers of the last command issued at or before `time`.
+    pub fn powers_at(&self, time: f32) -> (f32, f32) {
+        let index = self.commands.partition_point(|c| c.time <= time);
+        match index.checked_sub(1).map(|i| self.commands[i]) {
+            Some(command) => (command.left_power, command.right_power),
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// A rhai script that drives the same commands.
+    pub fn to_script(&self) -> String {
+        let mut script =
+            String::from("// Generated from a recorded command track\nlet track = [\n");
+        for c in &self.commands {
+            let _ = writeln!(
+                script,
+                "    [{:?}, {:?}, {:?}],",
+                c.time, c.left_power, c.right_power
+            );
+        }
+        script.push_str(
+            r#"];
+
+if state.time == () {
+    state.time = 0.0;
+    state.index = -1;
+}
+
+// Move on to the last command that is due
+while state.index + 1 < track.len() && track[state.index + 1][0] <= state.time {
+    state.index += 1;
+}
+
+if state.index >= 0 {
+    mouse.left_power = track[state.index][1];
+    mouse.right_power = track[state.index][2];
+}
+
+state.time += mouse.delta_time;
+"#,
+        );
+        script
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    ops::Deref,
+    str::FromStr,
+};
+
+use glam::{vec2, Vec2};
+use mazeparser::grid::{self, Cell, Grid, DIRECTIONS};
+pub use mazeparser::{Ramp, StartDirection};
+
+use crate::{ray::Ray, wall_index::WallIndex};
+
+/// Physical size of a classic micromouse maze cell.
+pub const CELL_SIZE_METERS: f32 = 0.18;
+
+/// Side of a classic maze cell in the units of the positions.
+pub const CLASSIC_CELL_SIZE: f32 = 50.0;
+
+#[derive(Debug)]
+pub struct Wall(Rectangle);
+
+impl Deref for Wall {
+    type Target = Rectangle;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Rectangle {
+    pub p1: Vec2,
+    pub p2: Vec2,
+    pub p3: Vec2,
+    pub p4: Vec2,
+}
+
+impl Rectangle {
+    /// If the point lies inside the rectangle, not on its edges.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let corners = [self.p1, self.p2, self.p3, self.p4];
+        let sides =
+            [0, 1, 2, 3].map(|i| (corners[(i + 1) % 4] - corners[i]).perp_dot(point - corners[i]));
+        sides.iter().all(|side| *side > 0.0) || sides.iter().all(|side| *side < 0.0)
+    }
+}
+
+impl From<Rectangle> for Wall {
+    fn from(value: Rectangle) -> Self {
+        Wall(value)
+    }
+}
+
+/// The upper layer of a maze with a bridge, which the mouse reaches over ramps.
+#[derive(Debug)]
+pub struct Layer {
+    pub walls: Vec<Wall>,
+    pub index: WallIndex,
+    pub grid: Grid,
+}
+
+/// How a surface of the maze is drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub color: u32, // RGBA
+}
+
+#[derive(Debug)]
+pub struct Maze {
+    pub walls: Vec<Wall>, // 2D grid representing walls in each cell
+    pub index: WallIndex, // Finds the walls close to a position
+    pub friction: f32,    // Friction coefficient of the maze surface
+    pub start: Vec2,
+    pub start_direction: StartDirection,
+    pub finish: Rectangle,
+    pub wall_material: Material,
+    pub finish_material: Material,
+    pub cell_size: f32,
+    pub cell_size_meters: f32, // Physical side of a cell, 0.09 in a half-size maze
+    pub grid: Grid,
+    pub start_cell: Cell,
+    pub finish_cells: Vec<Cell>,
+    pub upper: Option<Layer>, // Only mazes with a bridge have one
+    pub ramps: Vec<Ramp>,
+    pub name: Option<String>, // From the NAME header of the maze file
+    pub author: Option<String>,
+}
+
+impl Maze {
+    /// Meters per unit of the positions.
+    pub fn meters_per_unit(&self) -> f32 {
+        self.cell_size_meters / self.cell_size
+    }
+
+    /// Returns the cell containing the given world position.
+    pub fn cell_at(&self, position: Vec2) -> (i32, i32) {
+        let cell = (position / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    /// Returns the grid cell containing the given world position, if it is inside the maze.
+    pub fn grid_cell_at(&self, position: Vec2) -> Option<Cell> {
+        let (x, y) = self.cell_at(position);
+        let cell = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        self.grid.contains(cell).then_some(cell)
+    }
+
+    /// Returns the world position of the center of a cell.
+    pub fn cell_center(&self, (x, y): Cell) -> Vec2 {
+        vec2(x as f32 + 0.5, y as f32 + 0.5) * self.cell_size
+    }
+
+    /// The point where a ray first hits a wall of the layer, and its distance from the origin.
+    ///
+    /// This is what the default sensor model measures, without any noise.
+    pub fn nearest_wall(&self, origin: Vec2, direction: Vec2, layer: usize) -> Option<(Vec2, f32)> {
+        let (walls, index) = self.layer_walls(layer);
+        index
+            .cast(&Ray { origin, direction }, walls)
+            .map(|(point, _)| (point, origin.distance(point)))
+    }
+
+    /// If no wall of the layer blocks the straight line between the centers of two cells.
+    pub fn line_of_sight(&self, from: Cell, to: Cell, layer: usize) -> bool {
+        let (start, end) = (self.cell_center(from), self.cell_center(to));
+        if start == end {
+            return true;
+        }
+        self.nearest_wall(start, end - start, layer)
+            .is_none_or(|(_, distance)| distance > start.distance(end))
+    }
+
+    /// The walls of a layer and the index to find them. The floor for layers the maze doesn't have.
+    pub fn layer_walls(&self, layer: usize) -> (&[Wall], &WallIndex) {
+        match &self.upper {
+            Some(upper) if layer > 0 => (&upper.walls, &upper.index),
+            _ => (&self.walls, &self.index),
+        }
+    }
+
+    pub fn ramp_at(&self, cell: Cell) -> Option<&Ramp> {
+        self.ramps.iter().find(|ramp| ramp.cell == cell)
+    }
+
+    /// The layer the mouse is on after moving from one cell to a neighboring one.
+    pub fn layer_after(&self, layer: usize, from: Cell, to: Cell) -> usize {
+        let direction = DIRECTIONS.into_iter().find(|&direction| {
+            grid::step(from, direction, self.grid.width, self.grid.height) == Some(to)
+        });
+        match (self.ramp_at(from), direction) {
+            (Some(ramp), Some(direction)) => ramp.layer_after(direction, layer),
+            _ => layer,
+        }
+    }
+
+    /// If the upper layer covers the cell, which it does where it has walls or a ramp.
+    fn on_bridge(&self, cell: Cell) -> bool {
+        self.upper
+            .as_ref()
+            .is_some_and(|upper| upper.grid.walls(cell) != 0 || self.ramp_at(cell).is_some())
+    }
+
+    /// The cells the mouse can reach from the start on each layer, crossing between them over the ramps.
+    fn reachable(&self) -> HashSet<(usize, Cell)> {
+        let start = (0, self.start_cell);
+        let mut reached = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some((layer, cell)) = queue.pop_front() {
+            let grid = match &self.upper {
+                Some(upper) if layer > 0 => &upper.grid,
+                _ => &self.grid,
+            };
+            for direction in DIRECTIONS {
+                if grid.has_wall(cell, direction) {
+                    continue;
+                }
+                let Some(next) = grid::step(cell, direction, grid.width, grid.height) else {
+                    continue;
+                };
+                let next_layer = self
+                    .ramp_at(cell)
+                    .map_or(layer, |ramp| ramp.layer_after(direction, layer));
+                if (next_layer == 0 || self.on_bridge(next)) && reached.insert((next_layer, next)) {
+                    queue.push_back((next_layer, next));
+                }
+            }
+        }
+        reached
+    }
+
+    /// Whether the mouse can drive from the start to the finish.
+    pub fn is_finish_reachable(&self) -> bool {
+        if self.upper.is_none() {
+            return self
+                .grid
+                .shortest_path(self.start_cell, &self.finish_cells)
+                .is_some();
+        }
+        let reachable = self.reachable();
+        self.finish_cells
+            .iter()
+            .any(|&cell| reachable.contains(&(0, cell)))
+    }
+
+    /// Number of cells that can be reached from the start, counting cells under a bridge once.
+    pub fn reachable_cells(&self) -> usize {
+        if self.upper.is_none() {
+            return self
+                .grid
+                .distances(&[self.start_cell])
+                .iter()
+                .filter(|d| d.is_some())
+                .count();
+        }
+        let cells: HashSet<Cell> = self.reachable().into_iter().map(|(_, cell)| cell).collect();
+        cells.len()
+    }
+
+    /// Parses a maze, with its cells scaled to their CELL_SIZE, so a classic cell is `CLASSIC_CELL_SIZE` wide.
+    pub fn parse(s: &str) -> Result<Maze, mazeparser::ParseError> {
+        Self::from_string(s, CLASSIC_CELL_SIZE)
+    }
+
+    /// Parses a maze with `cell_size` as the side of a classic cell.
+    ///
+    /// A maze with another CELL_SIZE, like a half-size maze, gets cells of the same physical size in these units.
+    pub fn from_string(s: &str, cell_size: f32) -> Result<Maze, mazeparser::ParseError> {
+        let maze = mazeparser::Maze::from_str(s)?;
+        let cell_size_meters = maze.cell_size.map_or(CELL_SIZE_METERS, |mm| mm / 1000.0);
+        let cell_size = cell_size * cell_size_meters / CELL_SIZE_METERS;
+        let grid = Grid::from_maze(&maze);
+        let upper_grid = (maze.layers() > 1).then(|| Grid::from_layer(&maze, 1));
+        let start_cell = maze.start_cell();
+        let finish_cells = maze.finish_cells();
+        let mut walls = Vec::new();
+        let mut upper_walls = Vec::new();
+        let wall_thickness = maze.wall_thickness;
+        for wall in maze.walls {
+            let walls = if wall.layer > 0 {
+                &mut upper_walls
+            } else {
+                &mut walls
+            };
+            if let mazeparser::Orientation::Vertical = wall.orientation {
+                walls.push(
+                    Rectangle {
+                        p1: wall.start * cell_size,
+                        p2: wall.end * cell_size,
+                        p3: wall.end * cell_size + vec2(wall_thickness, 0.0),
+                        p4: wall.start * cell_size + vec2(wall_thickness, 0.0),
+                    }
+                    .into(),
+                );
+            } else {
+                walls.push(
+                    Rectangle {
+                        p1: wall.start * cell_size,
+                        p2: wall.end * cell_size,
+                        p3: wall.end * cell_size + vec2(0.0, wall_thickness),
+                        p4: wall.start * cell_size + vec2(0.0, wall_thickness),
+                    }
+                    .into(),
+                );
+            }
+        }
+        Ok(Maze {
+            index: WallIndex::new(&walls, cell_size),
+            walls,
+            friction: maze.friction,
+            start: maze.start * cell_size,
+            start_direction: maze.start_direction,
+            finish: Rectangle {
+                p1: maze.finish.start * cell_size,
+                p2: vec2(maze.finish.start.x, maze.finish.end.y) * cell_size,
+                p3: maze.finish.end * cell_size,
+                p4: vec2(maze.finish.end.x, maze.finish.start.y) * cell_size,
+            },
+            wall_material: Material {
+                color: maze.wall_color,
+            },
+            finish_material: Material {
+                color: maze.finish_color,
+            },
+            cell_size,
+            cell_size_meters,
+            grid,
+            start_cell,
+            finish_cells,
+            upper: upper_grid.map(|grid| Layer {
+                index: WallIndex::new(&upper_walls, cell_size),
+                walls: upper_walls,
+                grid,
+            }),
+            ramps: maze.ramps,
+            name: maze.name,
+            author: maze.author,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use rhai::{CustomType, TypeBuilder};
+
+/// A trapezoidal speed profile: accelerate to the maximum speed, cruise, then brake to stop at the target.
+#[derive(Clone, Debug, CustomType)]
+#[rhai_type(extra = Self::build_extra)]
+pub struct MotionProfile {
+    #[rhai_type(readonly)]
+    pub distance: f32,
+    #[rhai_type(readonly)]
+    pub max_speed: f32,
+    #[rhai_type(readonly)]
+    pub acceleration: f32,
+    #[rhai_type(readonly)]
+    pub travelled: f32,
+    #[rhai_type(readonly)]
+    pub speed: f32,
+}
+
+impl MotionProfile {
+    pub fn new(distance: f32, max_speed: f32, acceleration: f32) -> Self {
+        Self {
+            distance: distance.abs(),
+            max_speed: max_speed.abs(),
+            acceleration: acceleration.abs(),
+            travelled: 0.0,
+            speed: 0.0,
+        }
+    }
+
+    pub fn remaining(&self) -> f32 {
+        (self.distance - self.travelled).max(0.0)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.remaining() <= 0.0
+    }
+
+    /// Advances the profile by `dt` seconds and returns the speed setpoint for this tick.
+    pub fn step(&mut self, dt: f32) -> f32 {
+        let remaining = self.remaining();
+        if remaining <= 0.0 || dt <= 0.0 {
+            self.speed = 0.0;
+            return 0.0;
+        }
+
+        let braking_distance = self.speed * self.speed / (2.0 * self.acceleration);
+        let speed = if remaining <= braking_distance {
+            // Follow the braking curve, but keep creeping so the target is actually reached
+            (2.0 * self.acceleration * remaining)
+                .sqrt()
+                .max(self.acceleration * dt)
+        } else {
+            (self.speed + self.acceleration * dt).min(self.max_speed)
+        };
+
+        // Don't overshoot the target in the last tick
+        self.speed = speed.min(remaining / dt);
+        self.travelled += self.speed * dt;
+        self.speed
+    }
+
+    fn build_extra(builder: &mut TypeBuilder<Self>) {
+        builder
+            .with_fn("motion_profile", Self::new)
+            .with_fn("step", Self::step)
+            .with_get("remaining", |p: &mut Self| p.remaining())
+            .with_get("finished", |p: &mut Self| p.finished());
+    }
+}
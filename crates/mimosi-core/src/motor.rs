@@ -0,0 +1,60 @@
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+/// A brushed DC motor with a gearbox, described by its datasheet values.
+///
+/// The torque falls linearly from the stall torque at rest to zero at the free speed,
+/// both scaled by the voltage the motor gets from the supply.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MotorConfig {
+    pub stall_torque: f32,   // Torque at rest with the rated voltage
+    pub free_speed: f32,     // Speed without load with the rated voltage, in rpm
+    pub rated_voltage: f32,  // Voltage the stall torque and free speed were measured at
+    pub gear_ratio: f32,     // Turns of the motor per turn of the wheel
+    pub supply_voltage: f32, // Voltage of the battery at full power
+}
+
+impl MotorConfig {
+    /// Force the motor drives the wheel with, for a power between -1 and 1.
+    pub fn force(&self, power: f32, wheel_velocity: f32, wheel_radius: f32) -> f32 {
+        let voltage = power.clamp(-1.0, 1.0) * self.supply_voltage;
+        let motor_speed = wheel_velocity / wheel_radius * self.gear_ratio; // rad/s
+        let free_speed = self.free_speed * TAU / 60.0;
+        // The back EMF of the spinning motor eats into the applied voltage
+        let torque = self.stall_torque * (voltage / self.rated_voltage - motor_speed / free_speed);
+        torque * self.gear_ratio / wheel_radius
+    }
+
+    /// Fastest speed of the wheel at full power, where the motor has no torque left.
+    pub fn top_speed(&self, wheel_radius: f32) -> f32 {
+        let free_speed = self.free_speed * TAU / 60.0;
+        free_speed * self.supply_voltage / self.rated_voltage / self.gear_ratio * wheel_radius
+    }
+}
+
+/// The motor driver between the script and the motors, which only has so many PWM steps
+/// and can't turn a motor with very little power.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct DriverConfig {
+    #[serde(default)]
+    pub pwm_bits: Option<u32>, // Resolution of the PWM, e.g. 8 for 255 steps in each direction
+    #[serde(default)]
+    pub deadband: f32, // Powers below this don't move the motor (0..1)
+}
+
+impl DriverConfig {
+    /// The power the motor gets from the driver for the commanded one.
+    pub fn apply(&self, power: f32) -> f32 {
+        let mut power = power.clamp(-1.0, 1.0);
+        if let Some(bits) = self.pwm_bits {
+            let steps = ((1u32 << bits.clamp(1, 24)) - 1) as f32;
+            power = (power * steps).round() / steps;
+        }
+        if power.abs() < self.deadband {
+            0.0
+        } else {
+            power
+        }
+    }
+}
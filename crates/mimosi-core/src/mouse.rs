@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+
+use glam::{vec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::{Clock, ClockConfig},
+    encoder::{Encoder, EncoderConfig},
+    engine::{MouseData, SensorInfo, Sensors},
+    helper::Vec2Def,
+    maze::Maze,
+    motor::{DriverConfig, MotorConfig},
+    odometry::{Odometry, OdometryConfig},
+    run_state::RunState,
+    turn::{Turn, TurnConfig},
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sensor {
+    #[serde(with = "Vec2Def")]
+    pub position_offset: Vec2, // Offset relative to the center of the rectangle
+    pub angle: f32, // Angle in radians
+    #[serde(default)]
+    pub noise_stddev: f32, // Standard deviation of the gaussian noise in mm
+    #[serde(default)]
+    pub resolution_mm: Option<f32>, // Step size of the readings
+    #[serde(default)]
+    pub max_range: Option<f32>, // Largest distance in mm the sensor can measure
+    #[serde(default)]
+    pub field_of_view: f32, // Opening angle of the cone in degrees, 0 for a single ray
+    #[serde(default = "default_cone_rays")]
+    pub cone_rays: usize, // Rays spread over the cone
+    #[serde(default)]
+    pub group: Option<String>, // Rays of a group share a color when colored by group
+    #[serde(default)]
+    pub hide_ray: bool, // Don't draw the ray of the sensor
+    #[serde(skip)]
+    pub value: f32,
+    #[serde(skip)]
+    pub timestamp: f32, // Time on the clock of the controller when the value was measured
+    #[serde(skip)]
+    pub closest_point: Vec2,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MouseConfig {
+    pub wheel_base: f32, // Distance between the wheels
+    pub wheel_radius: f32,
+    pub wheel_friction: f32,
+    pub mass: f32, // Mass of the micromouse
+    pub max_speed: f32,
+
+    pub width: f32,  // Width of the mouse
+    pub length: f32, // Length of the mouse (not including the triangle)
+    #[serde(default)]
+    pub body: Option<Vec<[f32; 2]>>, // Corners of a convex chassis, instead of the rectangle with a triangular front
+
+    pub encoder_resolution: usize,
+    #[serde(default)]
+    pub encoder: EncoderConfig,
+
+    #[serde(default = "default_brake_friction")]
+    pub brake_friction: f32, // Friction added by a braking wheel
+
+    #[serde(default)]
+    pub max_acceleration: Option<f32>, // Limit for the acceleration of each wheel
+    #[serde(default)]
+    pub max_jerk: Option<f32>, // Limit for the change of acceleration per second
+
+    #[serde(default)]
+    pub turns: TurnConfig,
+
+    #[serde(default)]
+    pub odometry: Option<OdometryConfig>, // Pose estimate for the script, off by default
+
+    #[serde(default)]
+    pub motor: Option<MotorConfig>, // DC motor model instead of power * max_speed
+    #[serde(default)]
+    pub driver: Option<DriverConfig>, // PWM resolution and deadband of the motor driver
+    #[serde(default)]
+    pub clock: Option<ClockConfig>, // Drift and jitter of the controller clock, exact by default
+
+    pub sensors: HashMap<String, Sensor>,
+}
+
+impl MouseConfig {
+    /// The config with the seed of the run added to the seeds of the encoders, odometry and clock.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.encoder.seed = self.encoder.seed.wrapping_add(seed);
+        if let Some(odometry) = &mut self.odometry {
+            odometry.seed = odometry.seed.wrapping_add(seed);
+        }
+        if let Some(clock) = &mut self.clock {
+            clock.seed = clock.seed.wrapping_add(seed);
+        }
+        self
+    }
+}
+
+fn default_cone_rays() -> usize {
+    7
+}
+
+fn default_brake_friction() -> f32 {
+    10.0
+}
+
+/// The chassis without a configured body: rear left, front left, the tip of the triangular front, front right and rear right.
+fn default_body(width: f32, length: f32) -> Vec<Vec2> {
+    let (half_width, half_length) = (width / 2.0, length / 2.0);
+    vec![
+        vec2(-half_length, -half_width),
+        vec2(half_length, -half_width),
+        vec2(half_length + half_width, 0.0),
+        vec2(half_length, half_width),
+        vec2(-half_length, half_width),
+    ]
+}
+
+/// The corners in counterclockwise order, whichever way they were given.
+fn counterclockwise(mut corners: Vec<Vec2>) -> Vec<Vec2> {
+    let area: f32 = (0..corners.len())
+        .map(|i| corners[i].perp_dot(corners[(i + 1) % corners.len()]))
+        .sum();
+    if area < 0.0 {
+        corners.reverse();
+    }
+    corners
+}
+
+#[derive(Clone)]
+pub struct Micromouse {
+    pub position: Vec2,
+    pub layer: usize,    // Layer of the maze the mouse drives on, 1 on a bridge
+    pub width: f32,      // Width of the mouse
+    pub length: f32,     // Length of the mouse (not including the triangle)
+    pub body: Vec<Vec2>, // Corners of the chassis relative to its center, facing along the x axis, counterclockwise
+    pub sensors: HashMap<String, Sensor>,
+
+    pub wheel_friction: f32,
+    pub orientation: f32, // Orientation angle in radians
+    pub wheel_base: f32,  // Distance between the wheels
+    pub left_power: f32,
+    pub right_power: f32,
+    pub left_brake: bool,
+    pub right_brake: bool,
+    pub brake_friction: f32,
+    pub left_encoder: i64,
+    pub right_encoder: i64,
+    pub encoder_resolution: usize, // Counts per turn of a wheel
+    pub encoders: [Encoder; 2],    // Left and right, with the exact rotation of the wheels
+    pub encoder_timestamp: f32, // Time on the clock of the controller when the encoders were read
+
+    pub wheel_radius: f32,
+    pub left_velocity: f32,    // Current velocity of the left wheels
+    pub right_velocity: f32,   // Current velocity of the right wheels
+    pub lateral_velocity: f32, // Sideways slip caused by external forces
+    pub left_acceleration: f32,
+    pub right_acceleration: f32,
+    pub max_speed: f32,
+    pub max_acceleration: Option<f32>,
+    pub max_jerk: Option<f32>,
+    pub limited: bool, // If the acceleration or jerk limit engaged in the last update
+    pub mass: f32,     // Mass of the micromouse
+    pub motor: Option<MotorConfig>,
+    pub driver: Option<DriverConfig>,
+    pub in_deadband: bool, // If a commanded power was too small to move its motor in the last update
+
+    pub temperature: f32, // Temperature of the electronics in °C
+
+    pub gyro_z: f32, // Measured turning rate in degrees per second, positive to the right
+    pub accel_x: f32, // Measured acceleration along the heading
+    pub accel_y: f32, // Measured acceleration to the right of the heading
+
+    pub turns: TurnConfig,
+    pub planned_turn: Option<Turn>, // Turn the script wants to have drawn
+    pub odometry: Option<Odometry>,
+    pub clock: Option<Clock>,
+}
+
+impl Micromouse {
+    pub fn new(
+        MouseConfig {
+            wheel_base,
+            wheel_radius,
+            width,
+            length,
+            body,
+            sensors,
+            mass,
+            max_speed,
+            wheel_friction,
+            encoder_resolution,
+            encoder,
+            brake_friction,
+            max_acceleration,
+            max_jerk,
+            turns,
+            odometry,
+            motor,
+            driver,
+            clock,
+        }: MouseConfig,
+        position: Vec2,
+        orientation: f32,
+    ) -> Self {
+        Self {
+            position,
+            layer: 0,
+            wheel_base,
+            width,
+            mass,
+            length,
+            body: body.map_or_else(
+                || default_body(width, length),
+                |corners| counterclockwise(corners.into_iter().map(Vec2::from).collect()),
+            ),
+            max_speed,
+            wheel_radius,
+            left_encoder: 0,
+            right_encoder: 0,
+            encoder_resolution: encoder.counts_per_turn(encoder_resolution),
+            // The wheels lose counts independently of each other
+            encoders: [
+                Encoder::new(encoder, encoder.seed),
+                Encoder::new(encoder, encoder.seed.wrapping_add(1)),
+            ],
+            encoder_timestamp: 0.0,
+            sensors: sensors
+                .into_iter()
+                .map(|(n, s)| {
+                    (
+                        n,
+                        Sensor {
+                            angle: s.angle.to_radians(),
+                            ..s
+                        },
+                    )
+                })
+                .collect(),
+            orientation,
+            wheel_friction,
+            left_velocity: 0.0,
+            right_velocity: 0.0,
+            lateral_velocity: 0.0,
+            left_acceleration: 0.0,
+            right_acceleration: 0.0,
+            max_acceleration,
+            max_jerk,
+            limited: false,
+            in_deadband: false,
+            left_power: 0.0,
+            right_power: 0.0,
+            left_brake: false,
+            right_brake: false,
+            brake_friction,
+            motor,
+            driver,
+            temperature: 20.0,
+            gyro_z: 0.0,
+            accel_x: 0.0,
+            accel_y: 0.0,
+            turns,
+            planned_turn: None,
+            odometry: odometry.map(|config| Odometry::new(config, position, orientation)),
+            clock: clock.map(Clock::new),
+        }
+    }
+
+    /// What the script sees of the mouse at the simulated time, except the state of the run, which the simulation fills in.
+    pub fn get_data(
+        &self,
+        maze: &Maze,
+        time: f32,
+        delta_time: f32,
+        crashed: bool,
+        calibrating: bool,
+    ) -> MouseData {
+        let Micromouse {
+            width,
+            length,
+            sensors,
+            wheel_friction,
+            wheel_base,
+            left_power,
+            right_power,
+            left_brake,
+            right_brake,
+            left_encoder,
+            right_encoder,
+            encoder_resolution,
+            encoder_timestamp,
+            mass,
+            temperature,
+            limited,
+            in_deadband,
+            gyro_z,
+            accel_x,
+            accel_y,
+            position,
+            orientation,
+            turns,
+            odometry,
+            clock,
+            ..
+        } = &self;
+        MouseData {
+            delta_time,
+            wheel_base: *wheel_base,
+            wheel_friction: *wheel_friction,
+            mass: *mass,
+            width: *width,
+            length: *length,
+            sensors: Sensors(
+                sensors
+                    .iter()
+                    .map(|(n, v)| (n.clone(), SensorInfo::from(v)))
+                    .collect(),
+            ),
+            left_encoder: *left_encoder,
+            right_encoder: *right_encoder,
+            left_power: *left_power,
+            right_power: *right_power,
+            left_brake: *left_brake,
+            right_brake: *right_brake,
+            encoder_resolution: *encoder_resolution,
+            encoder_timestamp: *encoder_timestamp,
+            clock: clock.as_ref().map_or(time, |c| c.now(time)),
+            temperature: *temperature,
+            limited: *limited,
+            in_deadband: *in_deadband,
+            gyro_z: *gyro_z,
+            accel_x: *accel_x,
+            accel_y: *accel_y,
+            position: *position,
+            orientation: *orientation,
+            turns: *turns,
+            planned_turn: None,
+            odometry: odometry.as_ref().map(Odometry::estimate),
+            crashed,
+            calibrating,
+            calibrated: false,
+            localized: None,
+            maze_width: maze.grid.width,
+            maze_height: maze.grid.height,
+            cell_size: maze.cell_size,
+            run: RunState::default(),
+        }
+    }
+
+    /// The powers the motors get from the driver for the commanded ones.
+    pub fn applied_powers(&self) -> (f32, f32) {
+        match &self.driver {
+            Some(driver) => (
+                driver.apply(self.left_power),
+                driver.apply(self.right_power),
+            ),
+            None => (self.left_power, self.right_power),
+        }
+    }
+
+    pub fn set_left_power(&mut self, power: f32) {
+        self.left_power = power.clamp(-1.0, 1.0);
+    }
+
+    pub fn set_right_power(&mut self, power: f32) {
+        self.right_power = power.clamp(-1.0, 1.0);
+    }
+
+    pub fn update_from_data(&mut self, data: MouseData) {
+        self.set_left_power(data.left_power);
+        self.set_right_power(data.right_power);
+        self.left_brake = data.left_brake;
+        self.right_brake = data.right_brake;
+        self.planned_turn = data.planned_turn;
+    }
+
+    /// Corners of the chassis relative to its center, facing along the x axis, counterclockwise.
+    pub fn outline(&self) -> &[Vec2] {
+        &self.body
+    }
+
+    /// If a point relative to the center of the chassis, like the offset of a sensor, lies on or inside it.
+    pub fn covers(&self, offset: Vec2) -> bool {
+        let outline = self.outline();
+        let tolerance = 1e-3 * (self.width + self.length);
+        (0..outline.len()).all(|i| {
+            let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+            (b - a).perp_dot(offset - a) >= -tolerance
+        })
+    }
+
+    /// Velocity of the mouse in the world, including sideways slip.
+    pub fn velocity(&self) -> Vec2 {
+        let heading = Vec2::from_angle(self.orientation);
+        heading * (self.left_velocity + self.right_velocity) / 2.0
+            + heading.perp() * self.lateral_velocity
+    }
+
+    /// Updates the gyro and accelerometer readings from the pose and velocity before the last step.
+    pub fn update_imu(
+        &mut self,
+        previous_orientation: f32,
+        previous_velocity: Vec2,
+        gyro_bias: f32,
+        dt: f32,
+    ) {
+        if dt <= 0.0 {
+            return;
+        }
+        self.gyro_z = ((self.orientation - previous_orientation) / dt + gyro_bias).to_degrees();
+
+        // The accelerometer measures in the frame of the mouse
+        let acceleration = (self.velocity() - previous_velocity) / dt;
+        let heading = Vec2::from_angle(self.orientation);
+        self.accel_x = acceleration.dot(heading);
+        self.accel_y = acceleration.dot(heading.perp());
+    }
+
+    pub fn update_wheel_encoders(&mut self, dt: f32) {
+        // Calculate the distance each wheel has traveled
+        let left_distance = self.left_velocity * dt;
+        let right_distance = self.right_velocity * dt;
+
+        // Calculate the number of rotations for each wheel
+        let left_rotations = left_distance / (2.0 * std::f32::consts::PI * self.wheel_radius);
+        let right_rotations = right_distance / (2.0 * std::f32::consts::PI * self.wheel_radius);
+
+        // Convert rotations to encoder ticks, keeping the fractions for the next step
+        let resolution = self.encoder_resolution as f64;
+        self.encoders[0].advance(left_rotations as f64 * resolution);
+        self.encoders[1].advance(right_rotations as f64 * resolution);
+        self.left_encoder = self.encoders[0].count;
+        self.right_encoder = self.encoders[1].count;
+    }
+}
@@ -0,0 +1,93 @@
+use glam::Vec2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rhai::{CustomType, TypeBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct OdometryConfig {
+    pub encoder_noise: f32, // Relative error of the distance measured by each wheel
+    pub gyro_noise: f32,    // Random error of the gyro in rad/s
+    pub gyro_weight: f32,   // How much the heading follows the gyro instead of the encoders (0..=1)
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// The pose estimated by the odometry, as seen by the script.
+#[derive(Clone, Copy, CustomType, Debug, Default)]
+pub struct PoseEstimate {
+    #[rhai_type(readonly)]
+    pub x: f32,
+    #[rhai_type(readonly)]
+    pub y: f32,
+    #[rhai_type(readonly)]
+    pub orientation: f32, // Degrees, like the true orientation
+}
+
+/// Fuses noisy encoder and gyro readings into a pose estimate with a complementary filter.
+///
+/// The encoders are accurate on straights but drift when turning, the gyro is affected by
+/// noise and the bias of the warming electronics. The noise and the weight of the gyro
+/// decide how good the estimate is.
+#[derive(Debug, Clone)]
+pub struct Odometry {
+    config: OdometryConfig,
+    rng: StdRng,
+    position: Vec2,
+    orientation: f32, // Radians
+}
+
+impl Odometry {
+    pub fn new(config: OdometryConfig, position: Vec2, orientation: f32) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            position,
+            orientation,
+        }
+    }
+
+    /// Starts estimating from a known pose again.
+    pub fn reset(&mut self, position: Vec2, orientation: f32) {
+        self.position = position;
+        self.orientation = orientation;
+    }
+
+    fn noise(&mut self, scale: f32) -> f32 {
+        if scale > 0.0 {
+            self.rng.gen_range(-scale..=scale)
+        } else {
+            0.0
+        }
+    }
+
+    /// Integrates one step of wheel distances and the turning rate measured by the gyro in rad/s.
+    pub fn update(
+        &mut self,
+        left_distance: f32,
+        right_distance: f32,
+        wheel_base: f32,
+        gyro: f32,
+        dt: f32,
+    ) {
+        let left = left_distance * (1.0 + self.noise(self.config.encoder_noise));
+        let right = right_distance * (1.0 + self.noise(self.config.encoder_noise));
+        let gyro = gyro + self.noise(self.config.gyro_noise);
+
+        let weight = self.config.gyro_weight.clamp(0.0, 1.0);
+        let encoder_turn = (left - right) / wheel_base;
+        let turn = encoder_turn * (1.0 - weight) + gyro * dt * weight;
+
+        // Drive along the average heading of the step
+        let heading = self.orientation + turn / 2.0;
+        self.position += Vec2::from_angle(heading) * (left + right) / 2.0;
+        self.orientation += turn;
+    }
+
+    pub fn estimate(&self) -> PoseEstimate {
+        PoseEstimate {
+            x: self.position.x,
+            y: self.position.y,
+            orientation: self.orientation.to_degrees(),
+        }
+    }
+}
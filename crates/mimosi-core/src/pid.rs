@@ -0,0 +1,148 @@
+use rhai::{CustomType, TypeBuilder};
+
+/// A PID controller with a limited output and protection against integral windup.
+#[derive(Clone, Debug, CustomType)]
+#[rhai_type(name = "PID", extra = Self::build_extra)]
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub integral_limit: f32, // Largest contribution of the integral term to the output, 0 for no limit
+    pub output_limit: f32,   // Largest output in either direction, 0 for no limit
+    #[rhai_type(readonly)]
+    pub integral: f32, // Accumulated error over time
+    #[rhai_type(readonly)]
+    pub output: f32, // Output of the last update
+    #[rhai_type(skip)]
+    pub previous_error: Option<f32>,
+}
+
+impl Pid {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_limit: 0.0,
+            output_limit: 0.0,
+            integral: 0.0,
+            output: 0.0,
+            previous_error: None,
+        }
+    }
+
+    /// Feeds the error of this tick into the controller and returns the new output.
+    ///
+    /// The first update after a reset has no derivative term, so the output doesn't jump.
+    pub fn update(&mut self, error: f32, dt: f32) -> f32 {
+        if dt <= 0.0 {
+            return self.output;
+        }
+        let derivative = self.previous_error.map_or(0.0, |p| (error - p) / dt);
+        self.previous_error = Some(error);
+
+        let mut integral = self.integral + error * dt;
+        if self.integral_limit > 0.0 && self.ki != 0.0 {
+            let max = (self.integral_limit / self.ki).abs();
+            integral = integral.clamp(-max, max);
+        }
+
+        let unlimited = self.kp * error + self.ki * integral + self.kd * derivative;
+        let output = if self.output_limit > 0.0 {
+            unlimited.clamp(-self.output_limit, self.output_limit)
+        } else {
+            unlimited
+        };
+
+        // While the output is saturated, only integrate errors that pull it back into range
+        if output == unlimited || (error * self.ki).signum() != unlimited.signum() {
+            self.integral = integral;
+        }
+        self.output = output;
+        output
+    }
+
+    /// Forgets the accumulated error and the previous error, e.g. before a new maneuver.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.output = 0.0;
+        self.previous_error = None;
+    }
+
+    fn build_extra(builder: &mut TypeBuilder<Self>) {
+        builder
+            .with_fn("new_pid", Self::new)
+            .with_fn("update", Self::update)
+            .with_fn("reset", Self::reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proportional_term_scales_the_error() {
+        let mut pid = Pid::new(2.0, 0.0, 0.0);
+        assert_eq!(pid.update(1.5, 0.1), 3.0);
+        assert_eq!(pid.update(-0.5, 0.1), -1.0);
+    }
+
+    #[test]
+    fn integral_term_accumulates_the_error_over_time() {
+        let mut pid = Pid::new(0.0, 2.0, 0.0);
+        pid.update(1.0, 0.5);
+        assert_eq!(pid.integral, 0.5);
+        assert_eq!(pid.update(1.0, 0.5), 2.0);
+        assert_eq!(pid.integral, 1.0);
+    }
+
+    #[test]
+    fn derivative_term_starts_after_the_first_update() {
+        let mut pid = Pid::new(0.0, 0.0, 0.5);
+        assert_eq!(pid.update(1.0, 0.1), 0.0);
+        assert_eq!(pid.update(3.0, 0.1), 10.0);
+    }
+
+    #[test]
+    fn integral_limit_clamps_the_integral_contribution() {
+        let mut pid = Pid::new(0.0, 2.0, 0.0);
+        pid.integral_limit = 1.0;
+        for _ in 0..10 {
+            pid.update(1.0, 1.0);
+        }
+        assert_eq!(pid.integral, 0.5);
+        assert_eq!(pid.output, 1.0);
+    }
+
+    #[test]
+    fn saturated_output_stops_integrating_until_the_error_pulls_back() {
+        let mut pid = Pid::new(1.0, 1.0, 0.0);
+        pid.output_limit = 1.0;
+        assert_eq!(pid.update(2.0, 1.0), 1.0);
+        assert_eq!(pid.integral, 0.0);
+        // An error of the other sign drives the output back into range, so it is integrated
+        pid.update(-0.5, 1.0);
+        assert_eq!(pid.integral, -0.5);
+    }
+
+    #[test]
+    fn reset_forgets_the_integral_and_the_previous_error() {
+        let mut pid = Pid::new(1.0, 1.0, 1.0);
+        pid.update(1.0, 0.1);
+        pid.update(2.0, 0.1);
+        pid.reset();
+        assert_eq!(pid.integral, 0.0);
+        assert_eq!(pid.output, 0.0);
+        assert_eq!(pid.previous_error, None);
+        // Without a previous error there is no derivative kick
+        assert_eq!(pid.update(1.0, 1.0), 2.0);
+    }
+
+    #[test]
+    fn non_positive_time_step_keeps_the_last_output() {
+        let mut pid = Pid::new(1.0, 0.0, 0.0);
+        pid.update(0.5, 0.1);
+        assert_eq!(pid.update(4.0, 0.0), 0.5);
+    }
+}
@@ -0,0 +1,137 @@
+use mazeparser::{
+    grid::{self, Cell, Grid, EAST, NORTH, SOUTH, WEST},
+    path,
+};
+use rhai::{Array, Dynamic, Engine, INT};
+
+fn direction(name: &str) -> Option<u8> {
+    match name {
+        "N" => Some(NORTH),
+        "E" => Some(EAST),
+        "S" => Some(SOUTH),
+        "W" => Some(WEST),
+        _ => None,
+    }
+}
+
+fn direction_name(direction: u8) -> &'static str {
+    match direction {
+        NORTH => "N",
+        EAST => "E",
+        SOUTH => "S",
+        _ => "W",
+    }
+}
+
+fn cell(grid: &Grid, x: INT, y: INT) -> Option<Cell> {
+    let cell = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+    grid.contains(cell).then_some(cell)
+}
+
+fn set_wall(grid: &mut Grid, x: INT, y: INT, side: &str, present: bool) {
+    if let (Some(cell), Some(side)) = (cell(grid, x, y), direction(side)) {
+        grid.set_wall(cell, side, present);
+    }
+}
+
+fn has_wall(grid: &mut Grid, x: INT, y: INT, side: &str) -> bool {
+    match (cell(grid, x, y), direction(side)) {
+        (Some(cell), Some(side)) => grid.has_wall(cell, side),
+        _ => true,
+    }
+}
+
+/// The shortest path as an array of `[x, y]` cells, or `()` if the goal can't be reached.
+fn shortest_path(grid: &mut Grid, x: INT, y: INT, goal_x: INT, goal_y: INT) -> Dynamic {
+    let (Some(start), Some(goal)) = (cell(grid, x, y), cell(grid, goal_x, goal_y)) else {
+        return Dynamic::UNIT;
+    };
+    match grid.shortest_path(start, &[goal]) {
+        Some(path) => path
+            .into_iter()
+            .map(|(x, y)| Dynamic::from_array(vec![(x as INT).into(), (y as INT).into()]))
+            .collect::<Array>()
+            .into(),
+        None => Dynamic::UNIT,
+    }
+}
+
+/// The direction of the first step towards the goal, or `()` if there is none.
+fn next_move(grid: &mut Grid, x: INT, y: INT, goal_x: INT, goal_y: INT) -> Dynamic {
+    let (Some(start), Some(goal)) = (cell(grid, x, y), cell(grid, goal_x, goal_y)) else {
+        return Dynamic::UNIT;
+    };
+    let Some(next) = grid
+        .shortest_path(start, &[goal])
+        .and_then(|path| path.get(1).copied())
+    else {
+        return Dynamic::UNIT;
+    };
+    grid::DIRECTIONS
+        .into_iter()
+        .find(|&d| grid::step(start, d, grid.width, grid.height) == Some(next))
+        .map_or(Dynamic::UNIT, |d| direction_name(d).into())
+}
+
+/// The path with diagonals as an array of `[x1, y1, x2, y2]` segments in cell units,
+/// or `()` if the goal can't be reached.
+fn diagonal_path(grid: &mut Grid, x: INT, y: INT, goal_x: INT, goal_y: INT) -> Dynamic {
+    let (Some(start), Some(goal)) = (cell(grid, x, y), cell(grid, goal_x, goal_y)) else {
+        return Dynamic::UNIT;
+    };
+    match grid.shortest_path(start, &[goal]) {
+        Some(cells) => path::diagonal_path(&cells)
+            .into_iter()
+            .map(|s| {
+                Dynamic::from_array(vec![
+                    s.start.x.into(),
+                    s.start.y.into(),
+                    s.end.x.into(),
+                    s.end.y.into(),
+                ])
+            })
+            .collect::<Array>()
+            .into(),
+        None => Dynamic::UNIT,
+    }
+}
+
+/// Estimated seconds to drive the diagonal path, or `()` if the goal can't be reached.
+fn estimate_time(
+    grid: &mut Grid,
+    x: INT,
+    y: INT,
+    goal_x: INT,
+    goal_y: INT,
+    speed: f32,
+    acceleration: f32,
+) -> Dynamic {
+    let (Some(start), Some(goal)) = (cell(grid, x, y), cell(grid, goal_x, goal_y)) else {
+        return Dynamic::UNIT;
+    };
+    grid.shortest_path(start, &[goal])
+        .map_or(Dynamic::UNIT, |cells| {
+            path::estimate_time(&path::diagonal_path(&cells), speed, acceleration).into()
+        })
+}
+
+/// Registers the `Grid` type scripts use as their wall map, together with the path planner.
+pub fn register_grid(engine: &mut Engine, planner: bool) {
+    engine
+        .register_type_with_name::<Grid>("Grid")
+        .register_fn("grid", |width: INT, height: INT| {
+            Grid::new(width.max(0) as usize, height.max(0) as usize)
+        })
+        .register_get("width", |g: &mut Grid| g.width as INT)
+        .register_get("height", |g: &mut Grid| g.height as INT)
+        .register_fn("set_wall", set_wall)
+        .register_fn("has_wall", has_wall);
+
+    if planner {
+        engine
+            .register_fn("shortest_path", shortest_path)
+            .register_fn("next_move", next_move)
+            .register_fn("diagonal_path", diagonal_path)
+            .register_fn("estimate_time", estimate_time);
+    }
+}
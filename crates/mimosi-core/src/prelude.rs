@@ -0,0 +1,48 @@
+//! The stable API of mimosi-core, for tools and bindings built on top of it.
+//!
+//! What is reachable from here only changes in a breaking way with a new major version.
+//! Such a change adds a new version of the prelude next to `v1`, so code using `v1` keeps working until then.
+//! The modules in `internal`, behind the feature of the same name, can change with any release.
+
+pub mod v1 {
+    pub use crate::{
+        channels::{ChannelValue, Channels},
+        clock::{Clock, ClockConfig},
+        console::{Console, LogLine},
+        disturbance::{Disturbance, DisturbanceConfig, DisturbanceKind},
+        drift::{Drift, DriftConfig},
+        drive::{DifferentialDrive, DriveModel},
+        encoder::{Encoder, EncoderConfig},
+        engine::{ApiCategory, EngineConfig, MouseData, SensorInfo, Sensors},
+        error::Error,
+        events::{Event, Marker, TimedEvent},
+        external::{Command, Controller},
+        kidnap::{KidnapConfig, Kidnapper, Kidnapping},
+        manual::{CommandTrack, DriveCommand},
+        maze::{
+            Layer, Material, Maze, Rectangle, StartDirection, Wall, CELL_SIZE_METERS,
+            CLASSIC_CELL_SIZE,
+        },
+        motor::{DriverConfig, MotorConfig},
+        mouse::{Micromouse, MouseConfig, Sensor},
+        odometry::{Odometry, OdometryConfig, PoseEstimate},
+        ray::Ray,
+        replay::{Frame, Recorder, ReplayFile, SensorFrame},
+        run_state::RunState,
+        scenario::{CollisionMode, Scenario, ScriptErrorPolicy, SimulationConfig},
+        score::{Score, ScoredRun, ScoringRules},
+        sensor::{Pose, RaySensor, SensorModel, SensorReading},
+        simulation::{CallTimings, Phase, Simulation, Snapshot, Timings},
+        trail::{Trail, TrailPoint},
+        turn::{Turn, TurnConfig},
+        wall_index::WallIndex,
+    };
+
+    #[cfg(feature = "wasm")]
+    pub use crate::wasm::WasmController;
+
+    /// The random number generator passed to a [`SensorModel`].
+    pub use rand::RngCore;
+}
+
+pub use v1::*;
@@ -1,7 +1,12 @@
-use notan::math::Vec2;
+use glam::Vec2;
 
 use crate::maze::Wall;
 
+/// How far behind the origin a wall may start and still be hit, so rounding errors don't
+/// let a sensor mounted flush with a wall see through it.
+const BEHIND_ORIGIN: f32 = 1e-3;
+
+/// A half-line to find the walls in the way of, e.g. of a sensor.
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
     pub origin: Vec2,
@@ -9,6 +14,10 @@ pub struct Ray {
 }
 
 impl Ray {
+    /// Where the ray enters the wall.
+    ///
+    /// Edges the ray leaves the wall through don't count, so a ray starting inside a wall
+    /// ignores it and one starting on its surface only hits it when pointing into it.
     fn intersect(&self, wall: &Wall) -> Option<Vec2> {
         let edges = [
             (wall.p1, wall.p2),
@@ -16,8 +25,10 @@ impl Ray {
             (wall.p3, wall.p4),
             (wall.p4, wall.p1),
         ];
+        let center = (wall.p1 + wall.p2 + wall.p3 + wall.p4) / 4.0;
+        let min_t = -BEHIND_ORIGIN / self.direction.length();
 
-        let mut found = None;
+        let mut nearest: Option<f32> = None;
 
         for (p1, p2) in edges {
             let wall_dir = p2 - p1;
@@ -30,21 +41,24 @@ impl Ray {
             if denom.abs() < f32::EPSILON {
                 continue;
             }
+            let entering = denom * (center - p1).dot(perp_wall_dir) > 0.0;
 
             let t1 = ray_to_wall_start.dot(perp_wall_dir) / denom;
             let t2 = ray_to_wall_start.dot(self.direction.perp()) / denom;
 
-            if t1 >= 0.0 && (0.0..=1.0).contains(&t2) {
-                found = Some(Vec2 {
-                    x: self.origin.x + t1 * self.direction.x,
-                    y: self.origin.y + t1 * self.direction.y,
-                });
+            if entering && t1 >= min_t && (0.0..=1.0).contains(&t2) {
+                let t1 = t1.max(0.0);
+                nearest = Some(nearest.map_or(t1, |t| t.min(t1)));
             }
         }
-        found
+        nearest.map(|t| self.origin + t * self.direction)
     }
 
-    pub fn find_nearest_intersection(&self, walls: &[Wall]) -> Option<(Vec2, f32)> {
+    /// The nearest point where the ray enters one of the walls, and its distance from the origin.
+    pub fn find_nearest_intersection<'a>(
+        &self,
+        walls: impl IntoIterator<Item = &'a Wall>,
+    ) -> Option<(Vec2, f32)> {
         let mut nearest_intersection: Option<Vec2> = None;
         let mut nearest_distance = f32::MAX;
 
@@ -60,6 +74,6 @@ impl Ray {
             }
         }
 
-        nearest_intersection.map(|i| (i, nearest_distance))
+        nearest_intersection.map(|i| (i, nearest_distance.sqrt()))
     }
 }
@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "internal")]
+use crate::events::Event;
+use crate::{
+    channels::ChannelValue,
+    error::{from_toml, read, write, Error},
+    events::TimedEvent,
+    helper::Vec2Def,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SensorFrame {
+    pub name: String,
+    pub value: f32,
+    #[serde(with = "Vec2Def")]
+    pub origin: Vec2, // Where the sensor is mounted in the world
+    #[serde(with = "Vec2Def")]
+    pub hit: Vec2, // The closest point the sensor detected
+}
+
+/// The state of a run at a single point in time.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Frame {
+    pub time: f32,
+    #[serde(with = "Vec2Def")]
+    pub position: Vec2,
+    pub orientation: f32,
+    pub left_power: f32,
+    pub right_power: f32,
+    pub sensors: Vec<SensorFrame>,
+    pub collided: bool,
+    pub finished: bool,
+    #[serde(default)]
+    pub calibrating: bool, // If the frame was recorded before the run started
+    #[serde(default)]
+    pub channels: Vec<ChannelValue>, // Values the script logged with `log_value`
+    #[serde(default)]
+    pub layer: usize, // Layer of the maze the mouse was on
+    #[serde(default)]
+    pub in_start: bool, // If the mouse was in the start cell
+    #[serde(default)]
+    pub in_goal: bool, // If the mouse was in a cell of the finish
+    #[serde(default)]
+    pub wall_touches: usize, // Touches since the start of the simulation
+}
+
+/// Captures a frame for every physics step of a run.
+#[derive(Debug, Default, Clone)]
+pub struct Recorder {
+    pub frames: Vec<Frame>,
+}
+
+impl Recorder {
+    pub fn record(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.last().map_or(0.0, |f| f.time)
+    }
+
+    /// The last frame at or before the given time.
+    pub fn frame_at(&self, time: f32) -> Option<&Frame> {
+        let index = self.frames.partition_point(|f| f.time <= time);
+        self.frames.get(index.saturating_sub(1))
+    }
+
+    /// Index of the frame shown at the given time.
+    pub fn index_at(&self, time: f32) -> usize {
+        self.frames
+            .partition_point(|f| f.time <= time)
+            .saturating_sub(1)
+    }
+}
+
+/// A recorded run together with everything needed to show it without the script.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayFile {
+    pub maze: String,  // The maze in the text format
+    pub mouse: String, // The mouse config
+    #[serde(default)]
+    pub tag: Option<String>, // Name of the experiment
+    #[serde(default)]
+    pub seed: u64, // Seed of the run, to reproduce its noise with `--seed`
+    pub events: Vec<TimedEvent>,
+    pub frames: Vec<Frame>,
+}
+
+impl ReplayFile {
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let replay = toml::to_string(self).map_err(|e| Error::format(path.display(), e))?;
+        write(path, replay)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let replay = read(path)?;
+        from_toml(&path.display().to_string(), &replay)
+    }
+}
+
+/// Which events the player can jump to.
+#[cfg(feature = "internal")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFilter {
+    Any,
+    Crash,
+    Finish,
+    CellChange,
+    Marker,
+}
+
+#[cfg(feature = "internal")]
+impl EventFilter {
+    pub fn matches(&self, event: &Event) -> bool {
+        match self {
+            EventFilter::Any => true,
+            EventFilter::Crash => matches!(
+                event,
+                Event::Crash | Event::WallTouch | Event::ScriptError { .. }
+            ),
+            EventFilter::Finish => matches!(event, Event::Finish),
+            EventFilter::CellChange => matches!(event, Event::CellChange { .. }),
+            EventFilter::Marker => matches!(event, Event::Marker { .. }),
+        }
+    }
+}
+
+/// Plays back recorded frames at an arbitrary speed, independent of the recording rate.
+#[cfg(feature = "internal")]
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub time: f32,
+    pub speed: f32,
+    pub playing: bool,
+}
+
+#[cfg(feature = "internal")]
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            speed: 1.0,
+            playing: false,
+        }
+    }
+}
+
+#[cfg(feature = "internal")]
+impl Player {
+    pub fn advance(&mut self, dt: f32, recorder: &Recorder) {
+        if self.playing {
+            self.seek(self.time + dt * self.speed, recorder);
+            if self.time >= recorder.duration() || self.time <= 0.0 {
+                self.playing = false;
+            }
+        }
+    }
+
+    pub fn seek(&mut self, time: f32, recorder: &Recorder) {
+        self.time = time.clamp(0.0, recorder.duration());
+    }
+
+    /// Moves the given number of recorded frames forward or backward.
+    pub fn step(&mut self, frames: isize, recorder: &Recorder) {
+        let index = recorder.index_at(self.time) as isize + frames;
+        let index = index.clamp(0, recorder.frames.len().saturating_sub(1) as isize) as usize;
+        if let Some(frame) = recorder.frames.get(index) {
+            self.time = frame.time;
+        }
+    }
+
+    /// Jumps to the next event after the current time matching the filter.
+    pub fn next_event(&mut self, events: &[TimedEvent], filter: EventFilter, recorder: &Recorder) {
+        if let Some(event) = events
+            .iter()
+            .find(|e| e.time > self.time && filter.matches(&e.event))
+        {
+            self.seek(event.time, recorder);
+        }
+    }
+
+    /// Jumps to the last event before the current time matching the filter.
+    pub fn previous_event(
+        &mut self,
+        events: &[TimedEvent],
+        filter: EventFilter,
+        recorder: &Recorder,
+    ) {
+        if let Some(event) = events
+            .iter()
+            .rev()
+            .find(|e| e.time < self.time && filter.matches(&e.event))
+        {
+            self.seek(event.time, recorder);
+        }
+    }
+}
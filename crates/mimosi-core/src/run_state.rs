@@ -0,0 +1,27 @@
+use rhai::{CustomType, TypeBuilder, INT};
+use serde::Serialize;
+
+/// Where the run stands, the same for the window, the telemetry, the results and the script.
+///
+/// Values that don't apply, like the runs left without scoring rules, are negative.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, CustomType)]
+pub struct RunState {
+    #[rhai_type(readonly)]
+    pub phase: String, // One of calibration, run, crashed, finished or timed_out
+    #[rhai_type(readonly)]
+    pub run_time: f32, // Seconds since the timed run started
+    #[rhai_type(readonly)]
+    pub time_left: f32, // Seconds until the time limit of the scenario
+    #[rhai_type(readonly)]
+    pub wall_touches: INT,
+    #[rhai_type(readonly)]
+    pub penalty: f32, // Seconds the wall touches add to the score of the current run
+    #[rhai_type(readonly)]
+    pub runs: INT, // Scored runs that reached the finish
+    #[rhai_type(readonly)]
+    pub runs_left: INT,
+    #[rhai_type(readonly)]
+    pub maze_time_left: f32,
+    #[rhai_type(readonly)]
+    pub best_score: f32,
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disturbance::DisturbanceConfig,
+    drift::DriftConfig,
+    engine::{ApiCategory, EngineConfig},
+    kidnap::KidnapConfig,
+    score::ScoringRules,
+};
+
+/// What happens when the mouse touches a wall.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CollisionMode {
+    /// The run ends
+    #[default]
+    Crash,
+    /// The mouse is pushed out of the wall and slides along it
+    Slide {
+        friction: f32, // Fraction of the wheel speed lost per second of contact
+    },
+}
+
+/// What happens when the script fails with an error while it runs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptErrorPolicy {
+    /// The simulation pauses and shows the error, headless runs stop with it
+    #[default]
+    Pause,
+    /// The run ends as if the mouse crashed, like a reset firmware would in a contest
+    Crash,
+    /// The failed call is skipped and the mouse keeps driving with the last commands
+    Skip,
+}
+
+/// How often the physics and the controller run.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct SimulationConfig {
+    /// Physics steps per second, instead of the time step given on the command line
+    #[serde(default)]
+    pub physics_hz: Option<f32>,
+    /// Calls of the script or controller per second, like the period of a control loop. Every physics step if not set
+    #[serde(default)]
+    pub controller_hz: Option<f32>,
+}
+
+impl SimulationConfig {
+    /// Simulated seconds per physics step.
+    pub fn physics_dt(&self, default: f32) -> f32 {
+        self.physics_hz.map_or(default, |hz| 1.0 / hz)
+    }
+}
+
+/// Settings of a simulation run that are neither part of the maze nor the mouse.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Scenario {
+    /// Added to the seed of every random number generator of the scenario and the mouse,
+    /// so a single number picks the noise of a run
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub disturbance: Option<DisturbanceConfig>,
+    #[serde(default)]
+    pub drift: Option<DriftConfig>,
+    /// Seed for the noise of the sensors
+    #[serde(default)]
+    pub sensor_seed: u64,
+    /// Opt-in script functions to make available
+    #[serde(default)]
+    pub enabled_api: Vec<ApiCategory>,
+    #[serde(default)]
+    pub disabled_api: Vec<ApiCategory>,
+    #[serde(default)]
+    pub collision: CollisionMode,
+    /// Simulated seconds after which the run is stopped
+    #[serde(default)]
+    pub time_limit: Option<f32>,
+    /// Competition rules for timing and scoring the runs
+    #[serde(default)]
+    pub scoring: Option<ScoringRules>,
+    /// Longest time in seconds the mouse may calibrate in the start cell before the run starts
+    #[serde(default)]
+    pub calibration_time: Option<f32>,
+    /// Allows mazes without a reachable finish, scoring exploration by coverage instead
+    #[serde(default)]
+    pub allow_unreachable_goal: bool,
+    /// Moves the mouse to random cells during the run, to test its re-localization
+    #[serde(default)]
+    pub kidnap: Option<KidnapConfig>,
+    #[serde(default)]
+    pub simulation: SimulationConfig,
+    #[serde(default)]
+    pub on_script_error: ScriptErrorPolicy,
+}
+
+impl Scenario {
+    /// A copy of the scenario with all random number generators seeded differently.
+    pub fn with_seed(&self, offset: u64) -> Self {
+        let mut scenario = self.clone();
+        scenario.seed = scenario.seed.wrapping_add(offset);
+        scenario
+    }
+
+    /// The scenario with the seed of the run added to the seeds of its random number generators.
+    pub(crate) fn seeded(mut self) -> Self {
+        let seed = self.seed;
+        self.sensor_seed = self.sensor_seed.wrapping_add(seed);
+        if let Some(disturbance) = &mut self.disturbance {
+            disturbance.seed = disturbance.seed.wrapping_add(seed);
+        }
+        if let Some(drift) = &mut self.drift {
+            drift.seed = drift.seed.wrapping_add(seed);
+        }
+        if let Some(kidnap) = &mut self.kidnap {
+            kidnap.seed = kidnap.seed.wrapping_add(seed);
+        }
+        self
+    }
+
+    pub fn engine_config(&self) -> EngineConfig {
+        EngineConfig {
+            enabled: self.enabled_api.iter().copied().collect(),
+            disabled: self.disabled_api.iter().copied().collect(),
+        }
+    }
+}
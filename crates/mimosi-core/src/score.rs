@@ -0,0 +1,160 @@
+use std::fmt::Display;
+
+use mazeparser::grid::Cell;
+use serde::{Deserialize, Serialize};
+
+use crate::maze::Maze;
+
+/// Competition rules for timing and scoring the runs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct ScoringRules {
+    pub maze_time: f32, // Seconds the mouse has for all its runs, from first leaving the start
+    pub max_runs: usize, // Runs that count, later ones are ignored
+    pub maze_time_factor: f32, // Part of the maze time used so far that is added to a run
+    pub touch_penalty: f32, // Seconds added per wall touch during a run
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            maze_time: 600.0,
+            max_runs: 5,
+            maze_time_factor: 1.0 / 30.0,
+            touch_penalty: 3.0,
+        }
+    }
+}
+
+/// A run from the start to the finish.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoredRun {
+    pub run_time: f32,
+    pub maze_time: f32, // Maze time used when the run started
+    pub touches: usize,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RunState {
+    AtStart,
+    Running { start: f32, touches: usize },
+    Returning, // Reached the finish and has to get back to the start for the next run
+}
+
+/// Times the runs like a competition and keeps the best score.
+///
+/// A run starts when the mouse leaves the start cell and ends when it reaches the finish.
+/// It has to return to the start cell before the next run.
+#[derive(Debug, Clone)]
+pub struct Score {
+    pub rules: ScoringRules,
+    pub runs: Vec<ScoredRun>,
+    state: RunState,
+    maze_start: Option<f32>,
+    time: f32,
+}
+
+impl Score {
+    pub fn new(rules: ScoringRules) -> Self {
+        Self {
+            rules,
+            runs: Vec::new(),
+            state: RunState::AtStart,
+            maze_start: None,
+            time: 0.0,
+        }
+    }
+
+    /// Seconds of the maze time used so far.
+    pub fn maze_time(&self) -> f32 {
+        self.maze_start.map_or(0.0, |start| self.time - start)
+    }
+
+    /// Whether the maze time or the allowed runs are used up.
+    pub fn is_over(&self) -> bool {
+        self.maze_time() >= self.rules.maze_time || self.runs.len() >= self.rules.max_runs
+    }
+
+    /// Seconds since the current run started, if the mouse is on a run.
+    pub fn current_run(&self) -> Option<f32> {
+        match self.state {
+            RunState::Running { start, .. } => Some(self.time - start),
+            _ => None,
+        }
+    }
+
+    /// Seconds the wall touches of the current run add to its score so far.
+    pub fn penalty(&self, wall_touches: usize) -> f32 {
+        match self.state {
+            RunState::Running { touches, .. } => {
+                (wall_touches - touches) as f32 * self.rules.touch_penalty
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn best(&self) -> Option<&ScoredRun> {
+        self.runs.iter().min_by(|a, b| a.score.total_cmp(&b.score))
+    }
+
+    pub fn update(&mut self, time: f32, maze: &Maze, cell: Option<Cell>, wall_touches: usize) {
+        self.time = time;
+        if self.is_over() {
+            return;
+        }
+        let at_start = cell == Some(maze.start_cell);
+        let at_finish = cell.is_some_and(|cell| maze.finish_cells.contains(&cell));
+        self.state = match self.state {
+            RunState::AtStart if !at_start => {
+                self.maze_start.get_or_insert(time);
+                RunState::Running {
+                    start: time,
+                    touches: wall_touches,
+                }
+            }
+            RunState::Running { start, touches } if at_finish => {
+                let run_time = time - start;
+                let maze_time = start - self.maze_start.unwrap_or(start);
+                let touches = wall_touches - touches;
+                self.runs.push(ScoredRun {
+                    run_time,
+                    maze_time,
+                    touches,
+                    score: run_time
+                        + maze_time * self.rules.maze_time_factor
+                        + touches as f32 * self.rules.touch_penalty,
+                });
+                RunState::Returning
+            }
+            RunState::Returning if at_start => RunState::AtStart,
+            state => state,
+        };
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Maze time: {:.2}s of {:.0}s",
+            self.maze_time().min(self.rules.maze_time),
+            self.rules.maze_time
+        )?;
+        for (i, run) in self.runs.iter().enumerate() {
+            writeln!(
+                f,
+                "Run {}: {:.2}s, {} touches, started after {:.2}s of maze time, score {:.2}",
+                i + 1,
+                run.run_time,
+                run.touches,
+                run.maze_time,
+                run.score
+            )?;
+        }
+        match self.best() {
+            Some(best) => writeln!(f, "Score: {:.2}", best.score),
+            None => writeln!(f, "Score: no run reached the finish"),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use glam::Vec2;
+use rand::{Rng, RngCore};
+
+use crate::{maze::Maze, mouse::Sensor, ray::Ray};
+
+/// Where a sensor is located in the world and where it is looking.
+#[derive(Debug, Clone, Copy)]
+pub struct Pose {
+    pub position: Vec2,
+    pub orientation: f32, // Orientation angle in radians
+    pub layer: usize,     // The sensor only sees the walls of this layer
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SensorReading {
+    pub value: f32,
+    pub closest_point: Vec2,
+}
+
+/// A sample of the standard normal distribution, using the Box-Muller transform.
+pub(crate) fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+/// Applies the noise, resolution and range limit of the sensor to a perfect reading.
+///
+/// `units_per_mm` converts the mm of the sensor config into the units of the reading,
+/// which is a distance in the same units as the positions.
+pub fn perturb(sensor: &Sensor, value: f32, units_per_mm: f32, rng: &mut impl Rng) -> f32 {
+    let mut value = value;
+    if sensor.noise_stddev > 0.0 {
+        value += gaussian(rng) * sensor.noise_stddev * units_per_mm;
+    }
+    if let Some(resolution) = sensor.resolution_mm.filter(|r| *r > 0.0) {
+        let step = resolution * units_per_mm;
+        value = (value / step).round() * step;
+    }
+    // Noise doesn't push a reading past what the sensor can measure
+    if let Some(max_range) = sensor.max_range {
+        value = value.min(max_range * units_per_mm);
+    }
+    value.max(0.0)
+}
+
+/// Computes the value of a sensor from its pose in the maze.
+///
+/// Implement this to add sensors that don't measure distances along a ray.
+/// Randomness should come from `rng`, which is seeded by the scenario, so runs stay reproducible.
+pub trait SensorModel {
+    fn measure(
+        &self,
+        sensor: &Sensor,
+        pose: Pose,
+        maze: &Maze,
+        rng: &mut dyn RngCore,
+    ) -> Option<SensorReading>;
+}
+
+/// The default sensor model, measuring the distance to the next wall along a ray.
+///
+/// Sensors with a `field_of_view` measure the closest wall in a cone of rays instead, like a time-of-flight sensor.
+/// Walls beyond the `max_range` aren't seen.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RaySensor;
+
+impl SensorModel for RaySensor {
+    fn measure(
+        &self,
+        sensor: &Sensor,
+        pose: Pose,
+        maze: &Maze,
+        _rng: &mut dyn RngCore,
+    ) -> Option<SensorReading> {
+        let (walls, index) = maze.layer_walls(pose.layer);
+        let units_per_mm = 1.0 / (maze.meters_per_unit() * 1000.0);
+        let range = saturated(sensor, units_per_mm);
+        let field_of_view = sensor.field_of_view.to_radians();
+        let rays = if field_of_view > 0.0 {
+            sensor.cone_rays.max(2)
+        } else {
+            1
+        };
+        (0..rays)
+            .filter_map(|i| {
+                let offset = if rays > 1 {
+                    (i as f32 / (rays - 1) as f32 - 0.5) * field_of_view
+                } else {
+                    0.0
+                };
+                let r = Ray {
+                    origin: pose.position,
+                    direction: Vec2::from_angle(pose.orientation + offset),
+                };
+                index.cast(&r, walls)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, distance)| *distance <= range)
+            .map(|(closest_point, value)| SensorReading {
+                value,
+                closest_point,
+            })
+    }
+}
+
+/// The reading of a sensor that sees no wall: its `max_range` if it has one, infinity otherwise.
+pub fn saturated(sensor: &Sensor, units_per_mm: f32) -> f32 {
+    sensor
+        .max_range
+        .map_or(f32::INFINITY, |range| range * units_per_mm)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// A wall across the corridor three cells right of the start, whose near side is at x = 150.
+    const MAZE: &str = "SP: 0,0\nSD: R\nFI: 1,0;2,1\n.C3: 0-1\n";
+    /// From the center of the start cell, the wall is 125 units or 450 mm away.
+    const ORIGIN: Vec2 = Vec2::new(25.0, 25.0);
+
+    fn sensor(config: &str) -> Sensor {
+        toml::from_str(&format!(
+            "position_offset = {{ x = 0.0, y = 0.0 }}\nangle = 0.0\n{config}"
+        ))
+        .unwrap()
+    }
+
+    fn measure(sensor: &Sensor) -> Option<SensorReading> {
+        let maze = Maze::parse(MAZE).unwrap();
+        let pose = Pose {
+            position: ORIGIN,
+            orientation: 0.0,
+            layer: 0,
+        };
+        RaySensor.measure(sensor, pose, &maze, &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn reads_the_distance_to_the_wall() {
+        let reading = measure(&sensor("")).unwrap();
+        assert!((reading.value - 125.0).abs() < 1e-3, "{}", reading.value);
+        assert!(reading.closest_point.distance(Vec2::new(150.0, 25.0)) < 1e-3);
+    }
+
+    /// Converts mm into the units of the classic maze, where a cell of 180 mm is 50 units wide.
+    const UNITS_PER_MM: f32 = 50.0 / 180.0;
+
+    #[test]
+    fn resolution_rounds_to_steps_in_mm() {
+        let sensor = sensor("resolution_mm = 40.0");
+        let mut rng = StdRng::seed_from_u64(0);
+        let value = perturb(&sensor, 450.0 * UNITS_PER_MM, UNITS_PER_MM, &mut rng);
+        assert!((value / UNITS_PER_MM - 440.0).abs() < 1e-3, "{value}");
+    }
+
+    #[test]
+    fn max_range_caps_noisy_readings_in_mm() {
+        let sensor = sensor("noise_stddev = 50.0\nmax_range = 400.0");
+        let mut rng = StdRng::seed_from_u64(0);
+        let readings: Vec<_> = (0..200)
+            .map(|_| perturb(&sensor, 390.0 * UNITS_PER_MM, UNITS_PER_MM, &mut rng) / UNITS_PER_MM)
+            .collect();
+        assert!(readings.iter().all(|mm| *mm <= 400.0 + 1e-3));
+        assert!(readings.iter().any(|mm| *mm < 390.0));
+    }
+
+    #[test]
+    fn noise_has_the_standard_deviation_in_mm() {
+        let sensor = sensor("noise_stddev = 2.0");
+        let mut rng = StdRng::seed_from_u64(0);
+        let errors: Vec<_> = (0..5000)
+            .map(|_| {
+                perturb(&sensor, 100.0, UNITS_PER_MM, &mut rng) / UNITS_PER_MM
+                    - 100.0 / UNITS_PER_MM
+            })
+            .collect();
+        let variance = errors.iter().map(|e| e * e).sum::<f32>() / errors.len() as f32;
+        assert!((variance.sqrt() - 2.0).abs() < 0.1, "{}", variance.sqrt());
+    }
+
+    #[test]
+    fn walls_beyond_the_max_range_are_not_seen() {
+        assert!(measure(&sensor("max_range = 400.0")).is_none());
+        let reading = measure(&sensor("max_range = 500.0")).unwrap();
+        assert!((reading.value - 125.0).abs() < 1e-3, "{}", reading.value);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    fs::OpenOptions,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use memmap2::MmapMut;
+
+use crate::{
+    engine::MouseData,
+    error::Error,
+    external::{Command, Controller, Message, TIMEOUT},
+    mouse::Micromouse,
+};
+
+/// First eight bytes of the file once it is ready, "MIMOSI01" read as a little endian integer.
+const MAGIC: u64 = u64::from_le_bytes(*b"MIMOSI01");
+/// Bytes of messages each direction can hold before the writer has to wait for the reader.
+const CAPACITY: usize = 64 * 1024;
+/// Size of the file header: the magic number and the capacity.
+const HEADER: usize = 64;
+/// Offsets within a ring. The counters are in their own cache lines, so both sides don't fight over them.
+const WRITTEN: usize = 0;
+const READ: usize = 64;
+const DATA: usize = 128;
+/// Offsets of the rings from the simulator to the controller and back.
+const TO_CONTROLLER: usize = HEADER;
+const FROM_CONTROLLER: usize = HEADER + DATA + CAPACITY;
+const SIZE: usize = FROM_CONTROLLER + DATA + CAPACITY;
+/// How often to check for new data before yielding the CPU to other threads.
+const SPINS: u32 = 10_000;
+/// How long to wait for a controller to open the file, which gives the user time to start it.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A controller in another process on the same machine, which exchanges the same JSON lines as
+/// [`ExternalController`](crate::external::ExternalController) through a memory mapped file.
+///
+/// The file holds two single producer, single consumer ring buffers, one per direction.
+/// Each has a counter of the bytes written and one of the bytes read, so neither side needs a lock.
+pub struct SharedMemoryController {
+    base: *mut u8,
+    _map: MmapMut, // Keeps the memory `base` points to mapped
+    line: Vec<u8>,
+}
+
+/// Polls until `ready` returns something, spinning first and then yielding, or gives up after `timeout`.
+fn wait<T>(timeout: Duration, mut ready: impl FnMut() -> Option<T>) -> Option<T> {
+    let deadline = Instant::now() + timeout;
+    let mut spins = 0;
+    loop {
+        if let Some(value) = ready() {
+            return Some(value);
+        }
+        if spins < SPINS {
+            spins += 1;
+            std::hint::spin_loop();
+        } else if Instant::now() > deadline {
+            return None;
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+impl SharedMemoryController {
+    /// Creates the file, greets the controller with the dimensions of the mouse and waits until it has read them.
+    pub fn create(path: &Path, mouse: &Micromouse) -> Result<Self, Error> {
+        let error = |e| Error::io(path, e);
+        // A new file every time, so a controller still holding the one of the last run doesn't mix them up
+        if path.exists() {
+            std::fs::remove_file(path).map_err(error)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(error)?;
+        file.set_len(SIZE as u64).map_err(error)?;
+        // SAFETY: the file was just created for this controller, the other process only accesses it
+        // through the atomic counters and the parts of the rings they hand over
+        let mut map = unsafe { MmapMut::map_mut(&file) }.map_err(error)?;
+        let mut controller = Self {
+            base: map.as_mut_ptr(),
+            _map: map,
+            line: Vec::new(),
+        };
+
+        controller
+            .counter(8)
+            .store(CAPACITY as u64, Ordering::Relaxed);
+        // Written last, so a controller that sees it finds everything else set up
+        controller.counter(0).store(MAGIC, Ordering::Release);
+        controller
+            .send(&Message::hello(mouse))
+            .map_err(Error::Controller)?;
+
+        // Sleeps instead of spinning, starting the controller can take a while
+        let connected = controller.counter(TO_CONTROLLER + READ);
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+        while connected.load(Ordering::Acquire) == 0 {
+            if Instant::now() > deadline {
+                return Err(Error::Controller(format!(
+                    "No controller connected to {} within {} s",
+                    path.display(),
+                    CONNECT_TIMEOUT.as_secs()
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(controller)
+    }
+
+    fn counter(&self, offset: usize) -> &AtomicU64 {
+        // SAFETY: all offsets are inside the mapping and multiples of 8, which is page aligned
+        unsafe { &*(self.base.add(offset) as *const AtomicU64) }
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), String> {
+        let line = message.to_line()?;
+        let bytes = line.as_bytes();
+        if bytes.len() > CAPACITY {
+            return Err(format!(
+                "Message of {} bytes doesn't fit into the shared memory",
+                bytes.len()
+            ));
+        }
+
+        let written = self
+            .counter(TO_CONTROLLER + WRITTEN)
+            .load(Ordering::Relaxed);
+        let end = written + bytes.len() as u64;
+        let read = self.counter(TO_CONTROLLER + READ);
+        wait(TIMEOUT, || {
+            (end - read.load(Ordering::Acquire) <= CAPACITY as u64).then_some(())
+        })
+        .ok_or("Controller stopped reading")?;
+
+        // The message may wrap around the end of the ring
+        let start = written as usize % CAPACITY;
+        let first = bytes.len().min(CAPACITY - start);
+        // SAFETY: the controller is done with these bytes, it has read up to at most one capacity before `end`
+        unsafe {
+            let data = self.base.add(TO_CONTROLLER + DATA);
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), data.add(start), first);
+            std::ptr::copy_nonoverlapping(bytes[first..].as_ptr(), data, bytes.len() - first);
+        }
+        self.counter(TO_CONTROLLER + WRITTEN)
+            .store(end, Ordering::Release);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Command, String> {
+        self.line.clear();
+        let mut read = self.counter(FROM_CONTROLLER + READ).load(Ordering::Relaxed);
+        loop {
+            let available = wait(TIMEOUT, || {
+                let available = self
+                    .counter(FROM_CONTROLLER + WRITTEN)
+                    .load(Ordering::Acquire);
+                (available > read).then_some(available)
+            })
+            .ok_or("Controller didn't answer")?;
+
+            while read < available {
+                // SAFETY: the controller is done with the bytes up to `available`
+                let byte = unsafe {
+                    *self
+                        .base
+                        .add(FROM_CONTROLLER + DATA + read as usize % CAPACITY)
+                };
+                read += 1;
+                if byte == b'\n' {
+                    self.counter(FROM_CONTROLLER + READ)
+                        .store(read, Ordering::Release);
+                    return serde_json::from_slice(&self.line)
+                        .map_err(|e| format!("Invalid command from controller: {e}"));
+                }
+                self.line.push(byte);
+            }
+            self.counter(FROM_CONTROLLER + READ)
+                .store(read, Ordering::Release);
+        }
+    }
+}
+
+impl Controller for SharedMemoryController {
+    /// Writes the state of the mouse into the shared memory and waits for the wheel powers to use for the next step.
+    fn exchange(&mut self, time: f32, mouse: &MouseData) -> Result<Command, String> {
+        self.send(&Message::tick(time, mouse))?;
+        self.receive()
+    }
+}
@@ -0,0 +1,955 @@
+use glam::{vec2, Vec2};
+use mazeparser::grid::Cell;
+use rand::{rngs::StdRng, SeedableRng};
+use rhai::{Engine, EvalAltResult, Scope, AST, INT};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    channels::Channels,
+    clock::Clock,
+    console::{Console, LogLine},
+    disturbance::Disturbance,
+    drift::Drift,
+    drive::{DifferentialDrive, DriveModel},
+    engine::{build_engine, MouseData},
+    error::Error,
+    events::{Event, Marker, TimedEvent},
+    external::Controller,
+    helper::{DOWN, LEFT, RIGHT, UP},
+    kidnap::Kidnapper,
+    manual::CommandTrack,
+    maze::{Maze, StartDirection, Wall},
+    mouse::{Micromouse, MouseConfig},
+    replay::{Frame, Recorder, SensorFrame},
+    run_state::RunState,
+    scenario::{CollisionMode, Scenario, ScriptErrorPolicy},
+    score::Score,
+    sensor::{perturb, saturated, Pose, RaySensor, SensorModel},
+    trail::{Trail, TrailPoint},
+};
+
+// Function to check if two line segments intersect
+pub fn lines_intersect(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> bool {
+    fn orientation(a: Vec2, b: Vec2, c: Vec2) -> i32 {
+        let val = (b.y - a.y) * (c.x - b.x) - (b.x - a.x) * (c.y - b.y);
+        if val == 0.0 {
+            return 0;
+        }
+        if val > 0.0 {
+            1
+        } else {
+            -1
+        }
+    }
+
+    let o1 = orientation(p1, p2, q1);
+    let o2 = orientation(p1, p2, q2);
+    let o3 = orientation(q1, q2, p1);
+    let o4 = orientation(q1, q2, p2);
+
+    if o1 != o2 && o3 != o4 {
+        return true;
+    }
+
+    false
+}
+
+fn polygon_wall_collision(corners: &[Vec2], wall: &Wall) -> bool {
+    // Check each edge of the polygon against the wall
+    for i in 0..corners.len() {
+        let (p1, p2) = (corners[i], corners[(i + 1) % corners.len()]);
+        if lines_intersect(p1, p2, wall.p1, wall.p2)
+            || lines_intersect(p1, p2, wall.p2, wall.p3)
+            || lines_intersect(p1, p2, wall.p3, wall.p4)
+            || lines_intersect(p1, p2, wall.p4, wall.p1)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Upper limit of physics steps per rendered frame, so a slow frame can't snowball.
+///
+/// When it is reached, the simulation runs slower than the time scale instead.
+#[cfg(feature = "internal")]
+pub const MAX_STEPS_PER_FRAME: usize = 5000;
+
+/// What the simulation is doing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// The mouse may move within the start cell to calibrate its sensors, at most until the given time
+    Calibration { until: f32 },
+    /// The timed run
+    Run,
+}
+
+/// If the chassis of the mouse overlaps a wall of the maze.
+pub fn touches_wall(mouse: &Micromouse, maze: &Maze) -> bool {
+    let rotation = Vec2::from_angle(mouse.orientation);
+    let corners: Vec<Vec2> = mouse
+        .outline()
+        .iter()
+        .map(|corner| mouse.position + corner.rotate(rotation))
+        .collect();
+
+    // Only the walls around the mouse can touch it
+    let min = corners
+        .iter()
+        .copied()
+        .reduce(Vec2::min)
+        .unwrap_or_default();
+    let max = corners
+        .iter()
+        .copied()
+        .reduce(Vec2::max)
+        .unwrap_or_default();
+    let (walls, index) = maze.layer_walls(mouse.layer);
+    index
+        .near(min, max)
+        .any(|i| polygon_wall_collision(&corners, &walls[i]))
+}
+
+fn start_orientation(maze: &Maze) -> f32 {
+    match maze.start_direction {
+        StartDirection::Up => UP,
+        StartDirection::Right => RIGHT,
+        StartDirection::Down => DOWN,
+        StartDirection::Left => LEFT,
+    }
+}
+
+/// Timestamp the controller clock puts on a sample taken at the time, the time itself without a clock.
+///
+/// Takes the clock alone, so it can be used while the sensors of the mouse are borrowed.
+fn stamp(clock: &mut Option<Clock>, time: f32) -> f32 {
+    clock.as_mut().map_or(time, |clock| clock.stamp(time))
+}
+
+/// Time spent in the parts of the physics steps, collected while profiling is enabled.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timings {
+    pub script: Duration,
+    pub raycast: Duration,
+}
+
+/// How long the calls of the script or controller took over the whole run.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CallTimings {
+    pub calls: u64,
+    pub total: Duration,
+    pub slowest: Duration,
+}
+
+impl CallTimings {
+    pub fn record(&mut self, elapsed: Duration) {
+        self.calls += 1;
+        self.total += elapsed;
+        self.slowest = self.slowest.max(elapsed);
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total.div_f64(self.calls.max(1) as f64)
+    }
+}
+
+/// Everything about a simulation that changes while it runs, taken with [`Simulation::snapshot`].
+///
+/// This includes the variables of the script and the state of the random number generators,
+/// so a restored simulation continues exactly like it did after the snapshot was taken.
+#[derive(Clone)]
+pub struct Snapshot<'a> {
+    pub time: f32, // Simulated time the snapshot was taken at
+    scope: Scope<'a>,
+    mouse: Micromouse,
+    collided: bool,
+    finished: bool,
+    disturbance: Option<Disturbance>,
+    drift: Option<Drift>,
+    sensor_rng: StdRng,
+    tick: u64,
+    phase: Phase,
+    run_start: f32,
+    touching: bool,
+    wall_touches: usize,
+    score: Option<Score>,
+    kidnapper: Option<Kidnapper>,
+    events: Vec<TimedEvent>,
+    visits: HashMap<Cell, usize>,
+    current_cell: Option<Cell>,
+    recorder: Recorder,
+    trail: Trail,
+    last_control: Option<f32>,
+    console: VecDeque<LogLine>,
+    channels: Channels,
+}
+
+pub struct Simulation {
+    pub engine: Engine,
+    pub mouse: Micromouse,
+    pub collided: bool,
+    pub finished: bool,
+    pub maze: Maze,
+    pub ast: AST,
+    pub default_sensor_model: Box<dyn SensorModel>,
+    pub sensor_models: HashMap<String, Box<dyn SensorModel>>,
+    pub drive_model: Box<dyn DriveModel>,
+    pub disturbance: Option<Disturbance>,
+    pub drift: Option<Drift>,
+    pub sensor_rng: StdRng, // Noise of the sensor readings
+    pub seed: u64,          // Seed of the run, the same one reproduces the same noise
+    pub time: f32,          // Simulated time in seconds
+    pub tick: u64,          // Physics steps done
+    pub time_limit: Option<f32>,
+    pub phase: Phase,
+    pub run_start: f32, // When the timed run started
+    pub collision: CollisionMode,
+    pub on_script_error: ScriptErrorPolicy,
+    pub touching: bool, // If the mouse is sliding along a wall
+    pub wall_touches: usize,
+    pub score: Option<Score>, // Competition scoring, if the scenario has rules for it
+    pub kidnapper: Option<Kidnapper>,
+    pub events: Vec<TimedEvent>,
+    pub visits: HashMap<Cell, usize>, // How often the mouse entered each cell
+    pub current_cell: Option<Cell>,
+    pub recorder: Recorder,
+    pub trail: Trail, // Where the mouse drove recently
+    pub reachable_cells: usize,
+    pub profiling: bool,
+    pub timings: Timings,
+    pub calls: CallTimings, // Measured for every call, also without profiling
+    /// Recorded commands that drive the mouse instead of the script
+    pub track: Option<CommandTrack>,
+    /// A controller connected over TCP or loaded from WebAssembly that drives the mouse instead of the script
+    pub external: Option<Box<dyn Controller>>,
+    /// Simulated seconds between two calls of the script or controller, every step if not set
+    pub controller_period: Option<f32>,
+    /// When the script or controller was called last
+    pub last_control: Option<f32>,
+    /// What the script logged
+    pub console: Console,
+    /// Values the script logged for the telemetry
+    pub channels: Channels,
+}
+
+impl Simulation {
+    /// Sets up the simulation, or fails with the position of the first problem in the script.
+    pub fn new(
+        script: String,
+        maze: Maze,
+        mouse_config: MouseConfig,
+        scenario: Scenario,
+    ) -> Result<Self, Error> {
+        if !scenario.allow_unreachable_goal && !maze.is_finish_reachable() {
+            return Err(Error::UnreachableFinish);
+        }
+        let mut engine = build_engine(&scenario.engine_config());
+        let console = Console::attach(&mut engine);
+        let channels = Channels::attach(&mut engine);
+        let ast = engine.compile(script).map_err(Error::script)?;
+        let seed = scenario.seed;
+        let scenario = scenario.seeded();
+        let mouse_config = mouse_config.with_seed(seed);
+        Ok(Self {
+            seed,
+            mouse: Micromouse::new(mouse_config, maze.start, start_orientation(&maze)),
+            collided: false,
+            finished: false,
+            reachable_cells: maze.reachable_cells(),
+            trail: Trail::new(maze.cell_size / 30.0),
+            maze,
+            engine,
+            ast,
+            default_sensor_model: Box::new(RaySensor),
+            sensor_models: HashMap::new(),
+            drive_model: Box::new(DifferentialDrive),
+            disturbance: scenario.disturbance.map(Disturbance::new),
+            drift: scenario.drift.map(Drift::new),
+            sensor_rng: StdRng::seed_from_u64(scenario.sensor_seed),
+            time: 0.0,
+            tick: 0,
+            time_limit: scenario.time_limit,
+            phase: scenario
+                .calibration_time
+                .map_or(Phase::Run, |until| Phase::Calibration { until }),
+            run_start: 0.0,
+            collision: scenario.collision,
+            on_script_error: scenario.on_script_error,
+            touching: false,
+            wall_touches: 0,
+            score: scenario.scoring.map(Score::new),
+            kidnapper: scenario.kidnap.map(Kidnapper::new),
+            events: Vec::new(),
+            visits: HashMap::new(),
+            current_cell: None,
+            recorder: Recorder::default(),
+            profiling: false,
+            timings: Timings::default(),
+            calls: CallTimings::default(),
+            track: None,
+            external: None,
+            controller_period: scenario.simulation.controller_hz.map(|hz| 1.0 / hz),
+            last_control: None,
+            console,
+            channels,
+        })
+    }
+
+    /// Replaces the model used to compute the value of the sensor with the given name.
+    pub fn set_sensor_model(&mut self, name: impl Into<String>, model: impl SensorModel + 'static) {
+        self.sensor_models.insert(name.into(), Box::new(model));
+    }
+
+    /// If the next step calls the script or controller, which only happens at the controller rate.
+    pub fn control_due(&self, dt: f32) -> bool {
+        match (self.controller_period, self.last_control) {
+            (Some(period), Some(last)) => self.time - last >= period - dt / 2.0,
+            _ => true,
+        }
+    }
+
+    /// Runs the script once and advances the simulation with the wheel powers it set.
+    pub fn step(&mut self, scope: &mut Scope, dt: f32) -> Result<(), Box<EvalAltResult>> {
+        if let Some(track) = &self.track {
+            let (left_power, right_power) = track.powers_at(self.time);
+            self.mouse.set_left_power(left_power);
+            self.mouse.set_right_power(right_power);
+            self.update(dt);
+            return Ok(());
+        }
+
+        // Between the calls of the control loop the mouse keeps driving with the last powers
+        if !self.control_due(dt) {
+            self.update(dt);
+            return Ok(());
+        }
+        let control_dt = self.last_control.map_or(dt, |last| self.time - last);
+        self.last_control = Some(self.time);
+
+        let mut data = self.mouse.get_data(
+            &self.maze,
+            self.time,
+            control_dt,
+            self.collided,
+            self.is_calibrating(),
+        );
+        data.run = self.run_state();
+        if let Some(external) = &mut self.external {
+            let start = Instant::now();
+            let command = external.exchange(self.time, &data)?;
+            self.record_call(start.elapsed());
+            self.mouse.set_left_power(command.left_power);
+            self.mouse.set_right_power(command.right_power);
+            self.mouse.left_brake = command.left_brake;
+            self.mouse.right_brake = command.right_brake;
+            for message in command.log.iter().flat_map(|log| log.lines()) {
+                self.console.push(self.time, message.to_string());
+            }
+            if let Some(cell) = command.localized {
+                self.check_localization(cell);
+            }
+            if command.calibrated {
+                self.start_run();
+            }
+            self.update(dt);
+            return Ok(());
+        }
+
+        scope.set_value("mouse", data);
+        let len = scope.len();
+        self.channels.set_time(self.time);
+        let start = Instant::now();
+        let result = self.engine.run_ast_with_scope(scope, &self.ast);
+        self.record_call(start.elapsed());
+        self.console.collect(self.time);
+        if let Err(error) = result {
+            scope.rewind(len);
+            return self.script_failed(error, dt);
+        }
+        if let Some(mouse_data) = scope.get_value::<MouseData>("mouse") {
+            let calibrated = mouse_data.calibrated;
+            if let Some(cell) = mouse_data.localized {
+                self.check_localization(cell);
+            }
+            self.mouse.update_from_data(mouse_data);
+            if calibrated {
+                self.start_run();
+            }
+        }
+        // Drop the variables the script declared, otherwise the scope grows with every step
+        scope.rewind(len);
+        self.update(dt);
+        Ok(())
+    }
+
+    fn record_call(&mut self, elapsed: Duration) {
+        self.calls.record(elapsed);
+        if self.profiling {
+            self.timings.script += elapsed;
+        }
+    }
+
+    /// Handles an error of the script as the scenario wants, returning it if the caller has to.
+    fn script_failed(
+        &mut self,
+        error: Box<EvalAltResult>,
+        dt: f32,
+    ) -> Result<(), Box<EvalAltResult>> {
+        // Stopped by the watchdog, which isn't a mistake in the script
+        let stopped = matches!(
+            *error,
+            EvalAltResult::ErrorTerminated(..) | EvalAltResult::ErrorTooManyOperations(..)
+        );
+        if stopped || self.on_script_error == ScriptErrorPolicy::Pause {
+            return Err(error);
+        }
+        let message = error.to_string();
+        self.console
+            .push(self.time, format!("Script error: {message}"));
+        self.push_event(Event::ScriptError { message });
+        match self.on_script_error {
+            ScriptErrorPolicy::Crash => self.crash(),
+            _ => self.update(dt),
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let external_force = self
+            .disturbance
+            .as_mut()
+            .map_or(Vec2::ZERO, |d| d.sample(dt));
+        let (position, orientation, velocity) = (
+            self.mouse.position,
+            self.mouse.orientation,
+            self.mouse.velocity(),
+        );
+        self.drive_model
+            .update(&mut self.mouse, dt, self.maze.friction, external_force);
+        if let CollisionMode::Slide { friction } = self.collision {
+            self.slide(position, orientation, friction, dt);
+        }
+
+        if let Some(drift) = &mut self.drift {
+            drift.update(dt);
+            self.mouse.temperature = drift.temperature;
+        }
+
+        let gyro_bias = self.drift.as_ref().map_or(0.0, Drift::gyro_bias);
+        self.mouse.update_imu(orientation, velocity, gyro_bias, dt);
+
+        if let Some(odometry) = &mut self.mouse.odometry {
+            odometry.update(
+                self.mouse.left_velocity * dt,
+                self.mouse.right_velocity * dt,
+                self.mouse.wheel_base,
+                self.mouse.gyro_z.to_radians(),
+                dt,
+            );
+        }
+
+        self.time += dt;
+        self.mouse.encoder_timestamp = stamp(&mut self.mouse.clock, self.time);
+        // The update that only initializes the sensors isn't a step
+        if dt > 0.0 {
+            self.tick += 1;
+        }
+        self.update_sensors();
+        self.update_status();
+        self.recorder.record(self.frame());
+        self.trail.record(TrailPoint {
+            position: self.mouse.position,
+            speed: self.mouse.velocity().length(),
+            layer: self.mouse.layer,
+        });
+        if let Some(score) = &mut self.score {
+            if self.phase == Phase::Run {
+                score.update(self.time, &self.maze, self.current_cell, self.wall_touches);
+            }
+        }
+
+        let run_time = self.run_time();
+        let pose = match (&mut self.kidnapper, self.phase) {
+            (Some(kidnapper), Phase::Run) => {
+                kidnapper.update(run_time, &self.maze, self.current_cell, self.finished)
+            }
+            _ => None,
+        };
+        if let Some((position, orientation)) = pose {
+            self.mouse.layer = 0;
+            self.set_pose(position, orientation);
+            self.push_event(Event::Kidnap {
+                position,
+                orientation,
+            });
+        }
+
+        if let Phase::Calibration { until } = self.phase {
+            if self.time >= until || self.current_cell != Some(self.maze.start_cell) {
+                self.start_run();
+            }
+        }
+    }
+
+    pub fn is_calibrating(&self) -> bool {
+        matches!(self.phase, Phase::Calibration { .. })
+    }
+
+    /// Ends the calibration and puts the mouse back to the start for the timed run.
+    pub fn start_run(&mut self) {
+        if !self.is_calibrating() {
+            return;
+        }
+        self.phase = Phase::Run;
+        self.mouse.layer = 0;
+        self.set_pose(self.maze.start, start_orientation(&self.maze));
+        // Count the start cell again once the run moves the mouse
+        self.visits.clear();
+        self.trail.clear();
+        self.current_cell = None;
+        self.run_start = self.time;
+        self.push_event(Event::RunStart);
+    }
+
+    /// Seconds since the timed run started.
+    pub fn run_time(&self) -> f32 {
+        self.time - self.run_start
+    }
+
+    /// Where the run stands, in one place for everything that shows or records it.
+    pub fn run_state(&self) -> RunState {
+        let phase = if self.collided {
+            "crashed"
+        } else if self.finished {
+            "finished"
+        } else if self.timed_out() {
+            "timed_out"
+        } else if self.is_calibrating() {
+            "calibration"
+        } else {
+            "run"
+        };
+        let score = self.score.as_ref();
+        RunState {
+            phase: phase.to_string(),
+            run_time: self.run_time(),
+            time_left: self
+                .time_limit
+                .map_or(-1.0, |limit| (limit - self.run_time()).max(0.0)),
+            wall_touches: self.wall_touches as INT,
+            penalty: score.map_or(0.0, |s| s.penalty(self.wall_touches)),
+            runs: score.map_or(0, |s| s.runs.len() as INT),
+            runs_left: score.map_or(-1, |s| s.rules.max_runs.saturating_sub(s.runs.len()) as INT),
+            maze_time_left: score.map_or(-1.0, |s| (s.rules.maze_time - s.maze_time()).max(0.0)),
+            best_score: score.and_then(Score::best).map_or(-1.0, |run| run.score),
+        }
+    }
+
+    /// Pushes the mouse out of the walls it drove into, keeping the movement along them.
+    fn slide(&mut self, position: Vec2, orientation: f32, friction: f32, dt: f32) {
+        let touching = self.check_collisions();
+        if touching {
+            // The walls are axis aligned, so keeping only one axis of the movement slides along them
+            let target = self.mouse.position;
+            let candidates = [
+                (vec2(target.x, position.y), self.mouse.orientation),
+                (vec2(position.x, target.y), self.mouse.orientation),
+                (position, self.mouse.orientation),
+                (position, orientation),
+            ];
+            for (candidate, candidate_orientation) in candidates {
+                self.mouse.position = candidate;
+                self.mouse.orientation = candidate_orientation;
+                if !self.check_collisions() {
+                    break;
+                }
+            }
+
+            let slowdown = (friction * dt).min(1.0);
+            self.mouse.left_velocity -= self.mouse.left_velocity * slowdown;
+            self.mouse.right_velocity -= self.mouse.right_velocity * slowdown;
+            self.mouse.lateral_velocity = 0.0;
+        }
+        if touching && !self.touching {
+            self.wall_touches += 1;
+            self.push_event(Event::WallTouch);
+        }
+        self.touching = touching;
+    }
+
+    /// Places the mouse at a new pose and records it as an event.
+    pub fn teleport(&mut self, position: Vec2, orientation: f32) {
+        self.set_pose(position, orientation);
+        self.push_event(Event::Teleport {
+            position,
+            orientation,
+        });
+    }
+
+    /// Saves the state of the simulation and the script, to go back to it with `restore`.
+    ///
+    /// A controller connected from outside keeps its own state, which isn't part of the snapshot.
+    pub fn snapshot<'a>(&self, scope: &Scope<'a>) -> Snapshot<'a> {
+        Snapshot {
+            time: self.time,
+            scope: scope.clone(),
+            mouse: self.mouse.clone(),
+            collided: self.collided,
+            finished: self.finished,
+            disturbance: self.disturbance.clone(),
+            drift: self.drift.clone(),
+            sensor_rng: self.sensor_rng.clone(),
+            tick: self.tick,
+            phase: self.phase,
+            run_start: self.run_start,
+            touching: self.touching,
+            wall_touches: self.wall_touches,
+            score: self.score.clone(),
+            kidnapper: self.kidnapper.clone(),
+            events: self.events.clone(),
+            visits: self.visits.clone(),
+            current_cell: self.current_cell,
+            recorder: self.recorder.clone(),
+            trail: self.trail.clone(),
+            last_control: self.last_control,
+            console: self.console.lines.clone(),
+            channels: self.channels.save(),
+        }
+    }
+
+    /// Puts the simulation and the script back to the state of the snapshot.
+    pub fn restore<'a>(&mut self, snapshot: &Snapshot<'a>, scope: &mut Scope<'a>) {
+        let snapshot = snapshot.clone();
+        *scope = snapshot.scope;
+        self.time = snapshot.time;
+        self.mouse = snapshot.mouse;
+        self.collided = snapshot.collided;
+        self.finished = snapshot.finished;
+        self.disturbance = snapshot.disturbance;
+        self.drift = snapshot.drift;
+        self.sensor_rng = snapshot.sensor_rng;
+        self.tick = snapshot.tick;
+        self.phase = snapshot.phase;
+        self.run_start = snapshot.run_start;
+        self.touching = snapshot.touching;
+        self.wall_touches = snapshot.wall_touches;
+        self.score = snapshot.score;
+        self.kidnapper = snapshot.kidnapper;
+        self.events = snapshot.events;
+        self.visits = snapshot.visits;
+        self.current_cell = snapshot.current_cell;
+        self.recorder = snapshot.recorder;
+        self.trail = snapshot.trail;
+        self.last_control = snapshot.last_control;
+        self.console.lines = snapshot.console;
+        self.channels.restore(&snapshot.channels);
+    }
+
+    /// Compares the cell the script reported with the one the mouse is in after a kidnapping.
+    fn check_localization(&mut self, cell: Cell) {
+        let run_time = self.run_time();
+        if let Some(after) = self
+            .kidnapper
+            .as_mut()
+            .and_then(|kidnapper| kidnapper.report(run_time, cell, self.current_cell))
+        {
+            self.push_event(Event::Relocalized { after });
+        }
+    }
+
+    /// Ends the run like hitting a wall does, which counts as a wall touch.
+    fn crash(&mut self) {
+        self.collided = true;
+        self.wall_touches += 1;
+        self.push_event(Event::Crash);
+    }
+
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push(TimedEvent {
+            time: self.time,
+            event,
+        });
+    }
+
+    /// Places the mouse at a new pose, bringing it to a stop and updating the sensors immediately.
+    pub fn set_pose(&mut self, position: Vec2, orientation: f32) {
+        self.mouse.position = position;
+        self.mouse.orientation = orientation;
+        self.mouse.left_velocity = 0.0;
+        self.mouse.right_velocity = 0.0;
+        self.mouse.lateral_velocity = 0.0;
+        if let Some(odometry) = &mut self.mouse.odometry {
+            odometry.reset(position, orientation);
+        }
+        self.collided = false;
+        self.finished = false;
+        self.update_sensors();
+        self.collided = self.check_collisions();
+        self.current_cell = self.maze.grid_cell_at(position);
+    }
+
+    fn update_sensors(&mut self) {
+        let start = self.profiling.then(Instant::now);
+        let ir_scale = self.drift.as_ref().map_or(1.0, Drift::ir_scale);
+        let units_per_mm = 1.0 / (self.maze.meters_per_unit() * 1000.0);
+        // In a fixed order, so the same seed gives every sensor the same noise
+        let mut sensors: Vec<_> = self.mouse.sensors.iter_mut().collect();
+        sensors.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (name, sensor) in sensors {
+            let pose = Pose {
+                position: self.mouse.position
+                    + sensor
+                        .position_offset
+                        .rotate(Vec2::from_angle(self.mouse.orientation)),
+                orientation: self.mouse.orientation + sensor.angle,
+                layer: self.mouse.layer,
+            };
+            let model = self
+                .sensor_models
+                .get(name)
+                .unwrap_or(&self.default_sensor_model);
+            if let Some(reading) = model.measure(sensor, pose, &self.maze, &mut self.sensor_rng) {
+                sensor.value = perturb(
+                    sensor,
+                    reading.value * ir_scale,
+                    units_per_mm,
+                    &mut self.sensor_rng,
+                );
+                sensor.closest_point = reading.closest_point;
+            } else {
+                // Nothing in sight, e.g. on a bridge, so the old value would be wrong
+                sensor.value = saturated(sensor, units_per_mm);
+                let reach = if sensor.value.is_finite() {
+                    sensor.value
+                } else {
+                    0.0
+                };
+                sensor.closest_point = pose.position + Vec2::from_angle(pose.orientation) * reach;
+            }
+            sensor.timestamp = stamp(&mut self.mouse.clock, self.time);
+        }
+        if let Some(start) = start {
+            self.timings.raycast += start.elapsed();
+        }
+    }
+
+    /// Whether the time limit of the scenario ran out.
+    pub fn timed_out(&self) -> bool {
+        self.time_limit
+            .is_some_and(|limit| self.run_time() >= limit)
+    }
+
+    /// Distance in meters the mouse drove since the run started.
+    pub fn path_length(&self) -> f32 {
+        let frames = &self.recorder.frames;
+        let frames = &frames[frames.partition_point(|f| f.calibrating)..];
+        let distance = frames
+            .windows(2)
+            .map(|pair| pair[0].position.distance(pair[1].position))
+            .fold(0.0, |sum, d| sum + d);
+        distance * self.maze.meters_per_unit()
+    }
+
+    /// Fraction of the reachable cells the mouse has visited so far.
+    pub fn coverage(&self) -> f32 {
+        self.visits.len() as f32 / self.reachable_cells.max(1) as f32
+    }
+
+    fn update_status(&mut self) {
+        let cell = self.maze.grid_cell_at(self.mouse.position);
+        if cell != self.current_cell {
+            let before = self.on_markers();
+            if let (Some(from), Some(to)) = (self.current_cell, cell) {
+                let layer = self.maze.layer_after(self.mouse.layer, from, to);
+                if layer != self.mouse.layer {
+                    self.mouse.layer = layer;
+                    self.push_event(Event::LayerChange { layer });
+                }
+            }
+            if let Some(cell) = cell {
+                *self.visits.entry(cell).or_default() += 1;
+                self.push_event(Event::CellChange { cell });
+            }
+            self.current_cell = cell;
+            let after = self.on_markers();
+            for (marker, was, is) in [
+                (Marker::Start, before.0, after.0),
+                (Marker::Goal, before.1, after.1),
+            ] {
+                if was != is {
+                    self.push_event(Event::Marker {
+                        marker,
+                        entered: is,
+                        tick: self.tick,
+                    });
+                }
+            }
+        }
+
+        if self.collision == CollisionMode::Crash && !self.collided && self.check_collisions() {
+            self.crash();
+        }
+
+        // The finish is on the floor, not on a bridge above it
+        if !self.finished
+            && self.mouse.layer == 0
+            && self.mouse.position.x >= self.maze.finish.p1.x
+            && self.mouse.position.y >= self.maze.finish.p1.y
+            && self.mouse.position.x <= self.maze.finish.p3.x
+            && self.mouse.position.y <= self.maze.finish.p3.y
+        {
+            self.finished = true;
+            self.push_event(Event::Finish);
+        }
+    }
+
+    /// If the mouse is in the start cell and in the goal area, like a sensor looking for markers on the floor would see it.
+    pub fn on_markers(&self) -> (bool, bool) {
+        match self.current_cell.filter(|_| self.mouse.layer == 0) {
+            Some(cell) => (
+                cell == self.maze.start_cell,
+                self.maze.finish_cells.contains(&cell),
+            ),
+            None => (false, false),
+        }
+    }
+
+    /// Tick and run time of the first time in the timed run the mouse entered or left a marker.
+    pub fn first_crossing(&self, marker: Marker, entered: bool) -> Option<(u64, f32)> {
+        self.events
+            .iter()
+            .filter(|e| e.time >= self.run_start)
+            .find_map(|e| match e.event {
+                Event::Marker {
+                    marker: m,
+                    entered: en,
+                    tick,
+                } if m == marker && en == entered => Some((tick, e.time - self.run_start)),
+                _ => None,
+            })
+    }
+
+    fn check_collisions(&self) -> bool {
+        touches_wall(&self.mouse, &self.maze)
+    }
+
+    /// Captures the current state for the replay.
+    pub fn frame(&self) -> Frame {
+        let mut sensors: Vec<_> = self
+            .mouse
+            .sensors
+            .iter()
+            .map(|(name, sensor)| SensorFrame {
+                name: name.clone(),
+                value: sensor.value,
+                origin: self.mouse.position
+                    + sensor
+                        .position_offset
+                        .rotate(Vec2::from_angle(self.mouse.orientation)),
+                hit: sensor.closest_point,
+            })
+            .collect();
+        sensors.sort_by(|a, b| a.name.cmp(&b.name));
+        let (in_start, in_goal) = self.on_markers();
+        Frame {
+            time: self.time,
+            position: self.mouse.position,
+            orientation: self.mouse.orientation,
+            left_power: self.mouse.left_power,
+            right_power: self.mouse.right_power,
+            sensors,
+            collided: self.collided,
+            finished: self.finished,
+            calibrating: self.is_calibrating(),
+            channels: self.channels.values(),
+            layer: self.mouse.layer,
+            in_start,
+            in_goal,
+            wall_touches: self.wall_touches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, RngCore};
+
+    use super::*;
+    use crate::{mouse::Sensor, sensor::SensorReading};
+
+    const MAZE: &str = "SP: 0,0\nSD: R\nFI: 2,0;3,1\n.C3: 0-1\n.R1: 1-2\n";
+    const MOUSE: &str = r#"
+        wheel_base = 25.0
+        wheel_radius = 5.0
+        encoder_resolution = 360
+        max_speed = 300.0
+        wheel_friction = 0.8
+        mass = 1.0
+        width = 15.0
+        length = 25.0
+
+        [sensors.FRONT]
+        angle = 0.0
+        position_offset = { x = 12.5, y = 0.0 }
+
+        [sensors.SIDE]
+        angle = 90.0
+        position_offset = { x = 0.0, y = 7.5 }
+    "#;
+
+    /// Reads a random distance, to see which generator the simulation passes in.
+    struct RandomSensor;
+
+    impl SensorModel for RandomSensor {
+        fn measure(
+            &self,
+            _sensor: &Sensor,
+            pose: Pose,
+            _maze: &Maze,
+            rng: &mut dyn RngCore,
+        ) -> Option<SensorReading> {
+            Some(SensorReading {
+                value: rng.gen_range(1.0..100.0),
+                closest_point: pose.position,
+            })
+        }
+    }
+
+    fn simulation(sensor_seed: u64) -> Simulation {
+        let scenario = Scenario {
+            sensor_seed,
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(
+            String::new(),
+            Maze::parse(MAZE).unwrap(),
+            toml::from_str(MOUSE).unwrap(),
+            scenario,
+        )
+        .unwrap();
+        sim.set_sensor_model("FRONT", RandomSensor);
+        sim.update(0.0);
+        sim
+    }
+
+    #[test]
+    fn custom_sensor_model_replaces_only_its_sensor() {
+        let sim = simulation(0);
+        let front = &sim.mouse.sensors["FRONT"];
+        assert_eq!(front.closest_point, sim.mouse.position + vec2(12.5, 0.0));
+        assert!((1.0..100.0).contains(&front.value));
+        // The other sensor still casts its ray, which sees no wall in the open maze
+        assert_eq!(sim.mouse.sensors["SIDE"].value, f32::INFINITY);
+    }
+
+    #[test]
+    fn custom_sensor_model_uses_the_seeded_rng() {
+        let value = |seed| simulation(seed).mouse.sensors["FRONT"].value;
+        assert_eq!(value(1), value(1));
+        assert_ne!(value(1), value(2));
+    }
+}
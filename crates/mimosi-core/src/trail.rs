@@ -0,0 +1,47 @@
+use std::collections::VecDeque;
+
+use glam::Vec2;
+
+/// Most points a trail keeps, the oldest are dropped first.
+const MAX_POINTS: usize = 4000;
+
+/// A position the mouse drove through.
+#[derive(Debug, Clone, Copy)]
+pub struct TrailPoint {
+    pub position: Vec2,
+    pub speed: f32, // Pixels per second
+    pub layer: usize,
+}
+
+/// The recent path of the mouse, to see how smoothly it drove.
+#[derive(Debug, Clone)]
+pub struct Trail {
+    pub points: VecDeque<TrailPoint>,
+    spacing: f32, // Smallest distance between two points
+}
+
+impl Trail {
+    pub fn new(spacing: f32) -> Self {
+        Self {
+            points: VecDeque::new(),
+            spacing,
+        }
+    }
+
+    /// Adds a point once the mouse moved far enough from the last one.
+    pub fn record(&mut self, point: TrailPoint) {
+        if let Some(last) = self.points.back() {
+            if last.layer == point.layer && last.position.distance(point.position) < self.spacing {
+                return;
+            }
+        }
+        self.points.push_back(point);
+        if self.points.len() > MAX_POINTS {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
@@ -0,0 +1,128 @@
+use glam::{vec2, Vec2};
+use rhai::{CustomType, TypeBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::MouseData;
+
+/// Tuning of the turn primitives, set in the `[turns]` section of the mouse config.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct TurnConfig {
+    pub speed: f32,         // Forward speed during smooth turns
+    pub radius: f32,        // Radius of smooth turns
+    pub angular_speed: f32, // Rotation speed of turns in place, in degrees per second
+}
+
+impl Default for TurnConfig {
+    fn default() -> Self {
+        Self {
+            speed: 100.0,
+            radius: 25.0,
+            angular_speed: 180.0,
+        }
+    }
+}
+
+/// A turn with constant wheel speeds, e.g. a 90° turn in place or a smooth 45° turn onto a diagonal.
+#[derive(Clone, Debug, CustomType)]
+#[rhai_type(extra = Self::build_extra)]
+pub struct Turn {
+    #[rhai_type(skip)]
+    pub angle: f32, // Signed angle in radians, positive turns right
+    #[rhai_type(skip)]
+    pub speed: f32,
+    #[rhai_type(skip)]
+    pub angular_speed: f32, // Signed, in radians per second
+    #[rhai_type(skip)]
+    pub wheel_base: f32,
+    #[rhai_type(readonly)]
+    pub duration: f32,
+    #[rhai_type(readonly)]
+    pub elapsed: f32,
+}
+
+impl Turn {
+    fn new(angle: f32, speed: f32, angular_speed: f32, wheel_base: f32) -> Self {
+        let angular_speed = angular_speed.abs().copysign(angle);
+        Self {
+            angle,
+            speed,
+            angular_speed,
+            wheel_base,
+            duration: if angular_speed == 0.0 {
+                0.0
+            } else {
+                angle / angular_speed
+            },
+            elapsed: 0.0,
+        }
+    }
+
+    /// Rotates on the spot by `angle` degrees.
+    pub fn in_place(mouse: &mut MouseData, angle: f32) -> Self {
+        Self::new(
+            angle.to_radians(),
+            0.0,
+            mouse.turns.angular_speed.to_radians(),
+            mouse.wheel_base,
+        )
+    }
+
+    /// Drives an arc with the configured radius, turning by `angle` degrees.
+    pub fn smooth(mouse: &mut MouseData, angle: f32) -> Self {
+        let TurnConfig { speed, radius, .. } = mouse.turns;
+        Self::new(angle.to_radians(), speed, speed / radius, mouse.wheel_base)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn left_speed(&self) -> f32 {
+        if self.finished() {
+            0.0
+        } else {
+            self.speed + self.angular_speed * self.wheel_base / 2.0
+        }
+    }
+
+    pub fn right_speed(&self) -> f32 {
+        if self.finished() {
+            0.0
+        } else {
+            self.speed - self.angular_speed * self.wheel_base / 2.0
+        }
+    }
+
+    /// Advances the turn by `dt` seconds. Returns true once the turn is done.
+    pub fn step(&mut self, dt: f32) -> bool {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        self.finished()
+    }
+
+    /// Points along the rest of the turn, starting at the given pose.
+    pub fn remaining_arc(&self, position: Vec2, orientation: f32, segments: usize) -> Vec<Vec2> {
+        let remaining = self.duration - self.elapsed;
+        let dt = remaining / segments as f32;
+        let mut points = vec![position];
+        let mut position = position;
+        let mut orientation = orientation;
+        for _ in 0..segments {
+            orientation += self.angular_speed * dt;
+            position += vec2(orientation.cos(), orientation.sin()) * self.speed * dt;
+            points.push(position);
+        }
+        points
+    }
+
+    fn build_extra(builder: &mut TypeBuilder<Self>) {
+        builder
+            .with_fn("turn_in_place", Self::in_place)
+            .with_fn("smooth_turn", Self::smooth)
+            .with_fn("step", Self::step)
+            .with_get("angle", |t: &mut Self| t.angle.to_degrees())
+            .with_get("left_speed", |t: &mut Self| t.left_speed())
+            .with_get("right_speed", |t: &mut Self| t.right_speed())
+            .with_get("finished", |t: &mut Self| t.finished());
+    }
+}
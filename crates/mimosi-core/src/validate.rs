@@ -0,0 +1,185 @@
+use std::fmt::Display;
+
+use glam::Vec2;
+
+use crate::{
+    maze::Maze,
+    replay::Frame,
+    simulation::{lines_intersect, touches_wall, Simulation},
+};
+
+/// A setup that makes the mouse behave in ways that are hard to explain from the outside.
+#[derive(Debug, Clone)]
+pub enum SetupWarning {
+    /// The sensor is mounted outside the chassis
+    SensorOutsideChassis { sensor: String },
+    /// The sensor starts inside a wall, so its ray ignores that wall
+    SensorInWall { sensor: String },
+    /// The chassis overlaps a wall at the start, so the mouse crashes right away
+    ChassisInWall,
+    /// The body of the mouse isn't a convex polygon
+    BodyNotConvex,
+}
+
+impl Display for SetupWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetupWarning::SensorOutsideChassis { sensor } => {
+                write!(f, "Sensor {sensor} is mounted outside the chassis")
+            }
+            SetupWarning::SensorInWall { sensor } => write!(
+                f,
+                "Sensor {sensor} starts inside a wall, so it doesn't see that wall"
+            ),
+            SetupWarning::ChassisInWall => write!(
+                f,
+                "The mouse overlaps a wall at the start and crashes right away"
+            ),
+            SetupWarning::BodyNotConvex => write!(
+                f,
+                "The body of the mouse isn't a convex polygon, so the check for sensors outside of it is unreliable"
+            ),
+        }
+    }
+}
+
+/// Checks the mouse at its start position for sensors and a chassis that don't fit.
+pub fn check_setup(sim: &Simulation) -> Vec<SetupWarning> {
+    let mouse = &sim.mouse;
+    let maze = &sim.maze;
+    let mut warnings = Vec::new();
+    let mut sensors: Vec<_> = mouse.sensors.iter().collect();
+    sensors.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, sensor) in sensors {
+        if !mouse.covers(sensor.position_offset) {
+            warnings.push(SetupWarning::SensorOutsideChassis {
+                sensor: name.clone(),
+            });
+        }
+        let position = mouse.position
+            + sensor
+                .position_offset
+                .rotate(Vec2::from_angle(mouse.orientation));
+        let in_wall = maze
+            .index
+            .near(position, position)
+            .any(|i| maze.walls[i].contains(position));
+        if in_wall {
+            warnings.push(SetupWarning::SensorInWall {
+                sensor: name.clone(),
+            });
+        }
+    }
+    if touches_wall(mouse, maze) {
+        warnings.push(SetupWarning::ChassisInWall);
+    }
+    if !is_convex(mouse.outline()) {
+        warnings.push(SetupWarning::BodyNotConvex);
+    }
+    warnings
+}
+
+/// If the corners, in counterclockwise order, form a convex polygon.
+fn is_convex(corners: &[Vec2]) -> bool {
+    let n = corners.len();
+    n >= 3
+        && (0..n).all(|i| {
+            let (a, b, c) = (corners[i], corners[(i + 1) % n], corners[(i + 2) % n]);
+            (b - a).perp_dot(c - b) >= 0.0
+        })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AnomalyKind {
+    /// The mouse moved through a wall between two frames
+    Tunneling,
+    /// The mouse moved further than its top speed allows
+    Teleport { distance: f32 },
+    /// The speed changed faster than the mouse can accelerate
+    Acceleration { acceleration: f32 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Anomaly {
+    pub time: f32,
+    pub kind: AnomalyKind,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.3}s: ", self.time)?;
+        match self.kind {
+            AnomalyKind::Tunneling => write!(f, "Drove through a wall"),
+            AnomalyKind::Teleport { distance } => {
+                write!(f, "Jumped {distance:.1} units in a single step")
+            }
+            AnomalyKind::Acceleration { acceleration } => {
+                write!(f, "Accelerated with {acceleration:.1} units/s²")
+            }
+        }
+    }
+}
+
+fn crosses_wall(maze: &Maze, layer: usize, from: Vec2, to: Vec2) -> bool {
+    let (walls, index) = maze.layer_walls(layer);
+    let near = index.near(from.min(to), from.max(to));
+    near.map(|i| &walls[i]).any(|wall| {
+        lines_intersect(from, to, wall.p1, wall.p2)
+            || lines_intersect(from, to, wall.p2, wall.p3)
+            || lines_intersect(from, to, wall.p3, wall.p4)
+            || lines_intersect(from, to, wall.p4, wall.p1)
+    })
+}
+
+/// Looks for movements in a recorded run that the physics shouldn't allow.
+pub fn check_replay(
+    frames: &[Frame],
+    maze: &Maze,
+    max_speed: f32,
+    max_acceleration: f32,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    let mut previous_speed: Option<f32> = None;
+    for pair in frames.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        let dt = to.time - from.time;
+        if dt <= 0.0 {
+            continue;
+        }
+        let time = to.time;
+        let distance = from.position.distance(to.position);
+
+        // Leave some room for slip caused by disturbances
+        if distance > max_speed * dt * 1.5 + 1.0 {
+            anomalies.push(Anomaly {
+                time,
+                kind: AnomalyKind::Teleport { distance },
+            });
+            previous_speed = None;
+            continue;
+        }
+
+        // Driving over a ramp onto another layer crosses the walls of only one of them
+        if crosses_wall(maze, from.layer, from.position, to.position)
+            && crosses_wall(maze, to.layer, from.position, to.position)
+        {
+            anomalies.push(Anomaly {
+                time,
+                kind: AnomalyKind::Tunneling,
+            });
+        }
+
+        let speed = distance / dt;
+        if let Some(previous_speed) = previous_speed {
+            let acceleration = (speed - previous_speed).abs() / dt;
+            if acceleration > max_acceleration {
+                anomalies.push(Anomaly {
+                    time,
+                    kind: AnomalyKind::Acceleration { acceleration },
+                });
+            }
+        }
+        previous_speed = Some(speed);
+    }
+    anomalies
+}
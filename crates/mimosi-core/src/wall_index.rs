@@ -0,0 +1,131 @@
+use glam::{vec2, Vec2};
+
+use crate::{maze::Wall, ray::Ray};
+
+/// Sorts the walls into a grid of buckets, so queries only look at the walls close by.
+///
+/// A wall is stored in every bucket its bounding box overlaps,
+/// so long walls can be returned more than once.
+#[derive(Debug, Default)]
+pub struct WallIndex {
+    bucket_size: f32,
+    width: usize,
+    height: usize,
+    buckets: Vec<Vec<usize>>, // Indices into the walls of the maze
+}
+
+impl WallIndex {
+    pub fn new(walls: &[Wall], bucket_size: f32) -> Self {
+        let (_, max) = bounds(walls.iter().flat_map(|w| [w.p1, w.p2, w.p3, w.p4]));
+        // Walls outside the positive quadrant end up in the first buckets
+        let width = (max.x.max(0.0) / bucket_size).floor() as usize + 1;
+        let height = (max.y.max(0.0) / bucket_size).floor() as usize + 1;
+        let mut index = Self {
+            bucket_size,
+            width,
+            height,
+            buckets: vec![Vec::new(); width * height],
+        };
+        for (i, wall) in walls.iter().enumerate() {
+            let (min, max) = bounds([wall.p1, wall.p2, wall.p3, wall.p4]);
+            // Pad the box a little, so hits right on the border of a bucket aren't missed
+            let padding = Vec2::splat(bucket_size * 1e-3);
+            let (x1, y1) = index.bucket_of(min - padding);
+            let (x2, y2) = index.bucket_of(max + padding);
+            for y in y1..=y2 {
+                for x in x1..=x2 {
+                    index.buckets[y * width + x].push(i);
+                }
+            }
+        }
+        index
+    }
+
+    fn bucket_of(&self, position: Vec2) -> (usize, usize) {
+        let cell = (position / self.bucket_size).floor().max(Vec2::ZERO);
+        (
+            (cell.x as usize).min(self.width - 1),
+            (cell.y as usize).min(self.height - 1),
+        )
+    }
+
+    /// Indices of the walls whose bounding boxes may overlap the given box.
+    pub fn near(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let (x1, y1) = self.bucket_of(min);
+        let (x2, y2) = self.bucket_of(max);
+        (y1..=y2)
+            .flat_map(move |y| (x1..=x2).map(move |x| y * self.width + x))
+            .flat_map(|bucket| self.buckets[bucket].iter().copied())
+    }
+
+    /// Finds the nearest wall along the ray, like `Ray::find_nearest_intersection`,
+    /// but only tests the walls of the buckets the ray passes through.
+    pub fn cast(&self, ray: &Ray, walls: &[Wall]) -> Option<(Vec2, f32)> {
+        let extent = vec2(self.width as f32, self.height as f32) * self.bucket_size;
+        let inside = ray.origin.cmpge(Vec2::ZERO).all() && ray.origin.cmplt(extent).all();
+        if !inside || ray.direction == Vec2::ZERO {
+            return ray.find_nearest_intersection(walls);
+        }
+
+        // Walk the buckets in the order the ray passes through them
+        let direction = ray.direction;
+        let (mut x, mut y) = self.bucket_of(ray.origin);
+        let step = |d: f32| if d > 0.0 { 1 } else { -1 };
+        let boundary =
+            |cell: usize, d: f32| (cell as f32 + (d > 0.0) as u8 as f32) * self.bucket_size;
+        let mut t_max = vec2(
+            if direction.x != 0.0 {
+                (boundary(x, direction.x) - ray.origin.x) / direction.x
+            } else {
+                f32::INFINITY
+            },
+            if direction.y != 0.0 {
+                (boundary(y, direction.y) - ray.origin.y) / direction.y
+            } else {
+                f32::INFINITY
+            },
+        );
+        let t_delta = Vec2::splat(self.bucket_size) / direction.abs();
+
+        let mut nearest: Option<(Vec2, f32)> = None;
+        loop {
+            let candidates = self.buckets[y * self.width + x].iter().map(|&i| &walls[i]);
+            if let Some(hit) = ray.find_nearest_intersection(candidates) {
+                if nearest.is_none_or(|(_, distance)| hit.1 < distance) {
+                    nearest = Some(hit);
+                }
+            }
+            // A hit before the ray leaves the bucket can't be beaten by walls further on
+            let t_exit = t_max.min_element();
+            if let Some((point, _)) = nearest {
+                let t_hit = (point - ray.origin).dot(direction) / direction.length_squared();
+                if t_hit <= t_exit {
+                    return nearest;
+                }
+            }
+
+            if t_max.x < t_max.y {
+                let next = x as i64 + step(direction.x);
+                if next < 0 || next >= self.width as i64 {
+                    return nearest;
+                }
+                x = next as usize;
+                t_max.x += t_delta.x;
+            } else {
+                let next = y as i64 + step(direction.y);
+                if next < 0 || next >= self.height as i64 {
+                    return nearest;
+                }
+                y = next as usize;
+                t_max.y += t_delta.y;
+            }
+        }
+    }
+}
+
+fn bounds(points: impl IntoIterator<Item = Vec2>) -> (Vec2, Vec2) {
+    points.into_iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), p| (min.min(p), max.max(p)),
+    )
+}
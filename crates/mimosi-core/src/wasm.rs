@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use wasmtime::{Caller, Config, Engine, Extern, Linker, Module, Store, Trap, TypedFunc};
+
+use crate::{
+    engine::MouseData,
+    error::Error,
+    external::{Command, Controller},
+    mouse::Micromouse,
+};
+
+/// Name of the module the host functions are imported from.
+const HOST_MODULE: &str = "mimosi";
+/// Fuel for a single call of `init` or `step`, roughly the number of instructions it may run.
+const FUEL_PER_CALL: u64 = 100_000_000;
+
+/// What the module can read during a step, and the commands it set so far.
+#[derive(Default)]
+struct Host {
+    wheel_base: f32,
+    encoder_resolution: usize,
+    time: f32,
+    delta_time: f32,
+    calibrating: bool,
+    crashed: bool,
+    sensors: Vec<f32>, // Ordered by the names of the sensors
+    left_encoder: i64,
+    right_encoder: i64,
+    gyro_z: f32,
+    accel_x: f32,
+    accel_y: f32,
+    temperature: f32,
+    command: Command,
+}
+
+/// Firmware compiled to WebAssembly that drives the mouse instead of the script.
+///
+/// The module exports `step`, which is called every physics step, and optionally `init`,
+/// which is called once after loading. It reads the state of the mouse and sets the
+/// wheel powers through the functions it imports from the `mimosi` module.
+pub struct WasmController {
+    store: Store<Host>,
+    step: TypedFunc<(), ()>,
+}
+
+impl WasmController {
+    /// Loads a module from a `.wasm` file or its text format.
+    pub fn load(path: &Path, mouse: &Micromouse) -> Result<Self, Error> {
+        let error = |e: wasmtime::Error| Error::Controller(format!("{}: {e:#}", path.display()));
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(error)?;
+        let module = Module::from_file(&engine, path).map_err(error)?;
+        let mut linker = Linker::new(&engine);
+        link_host(&mut linker).map_err(error)?;
+
+        let mut store = Store::new(
+            &engine,
+            Host {
+                wheel_base: mouse.wheel_base,
+                encoder_resolution: mouse.encoder_resolution,
+                sensors: vec![0.0; mouse.sensors.len()],
+                ..Default::default()
+            },
+        );
+        let instance = linker.instantiate(&mut store, &module).map_err(error)?;
+        let step = instance
+            .get_typed_func::<(), ()>(&mut store, "step")
+            .map_err(error)?;
+        if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "init") {
+            call(&mut store, &init, "init").map_err(Error::Controller)?;
+        }
+        Ok(Self { store, step })
+    }
+}
+
+impl Controller for WasmController {
+    /// Calls `step` of the module. The wheel powers and brakes stay as they were set until they are changed.
+    fn exchange(&mut self, time: f32, mouse: &MouseData) -> Result<Command, String> {
+        let host = self.store.data_mut();
+        let mut sensors: Vec<_> = mouse.sensors.0.iter().collect();
+        sensors.sort_by(|a, b| a.0.cmp(b.0));
+        host.sensors = sensors
+            .into_iter()
+            .map(|(_, sensor)| sensor.value)
+            .collect();
+        host.time = time;
+        host.delta_time = mouse.delta_time;
+        host.calibrating = mouse.calibrating;
+        host.crashed = mouse.crashed;
+        host.left_encoder = mouse.left_encoder;
+        host.right_encoder = mouse.right_encoder;
+        host.gyro_z = mouse.gyro_z;
+        host.accel_x = mouse.accel_x;
+        host.accel_y = mouse.accel_y;
+        host.temperature = mouse.temperature;
+
+        call(&mut self.store, &self.step, "step")?;
+        // Only the powers and brakes carry over to the next step
+        let command = &mut self.store.data_mut().command;
+        let next = Command {
+            calibrated: false,
+            localized: None,
+            log: None,
+            ..command.clone()
+        };
+        Ok(std::mem::replace(command, next))
+    }
+}
+
+/// Calls an export with a fresh budget of fuel, so a module stuck in a loop can't hang the simulation.
+fn call(store: &mut Store<Host>, func: &TypedFunc<(), ()>, name: &str) -> Result<(), String> {
+    store
+        .set_fuel(FUEL_PER_CALL)
+        .map_err(|e| format!("{e:#}"))?;
+    func.call(&mut *store, ()).map_err(|e| {
+        if e.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) {
+            format!("`{name}` ran out of fuel after about {FUEL_PER_CALL} instructions")
+        } else {
+            format!("{e:#}")
+        }
+    })
+}
+
+/// Defines the functions the module can import.
+fn link_host(linker: &mut Linker<Host>) -> wasmtime::Result<()> {
+    linker
+        .func_wrap(HOST_MODULE, "wheel_base", |c: Caller<'_, Host>| {
+            c.data().wheel_base
+        })?
+        .func_wrap(HOST_MODULE, "encoder_resolution", |c: Caller<'_, Host>| {
+            c.data().encoder_resolution as i64
+        })?
+        .func_wrap(HOST_MODULE, "time", |c: Caller<'_, Host>| c.data().time)?
+        .func_wrap(HOST_MODULE, "delta_time", |c: Caller<'_, Host>| {
+            c.data().delta_time
+        })?
+        .func_wrap(HOST_MODULE, "calibrating", |c: Caller<'_, Host>| {
+            c.data().calibrating as i32
+        })?
+        .func_wrap(HOST_MODULE, "crashed", |c: Caller<'_, Host>| {
+            c.data().crashed as i32
+        })?
+        .func_wrap(HOST_MODULE, "sensor_count", |c: Caller<'_, Host>| {
+            c.data().sensors.len() as i32
+        })?
+        // NaN for sensors that don't exist
+        .func_wrap(HOST_MODULE, "sensor", |c: Caller<'_, Host>, index: i32| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|i| c.data().sensors.get(i).copied())
+                .unwrap_or(f32::NAN)
+        })?
+        .func_wrap(HOST_MODULE, "left_encoder", |c: Caller<'_, Host>| {
+            c.data().left_encoder
+        })?
+        .func_wrap(HOST_MODULE, "right_encoder", |c: Caller<'_, Host>| {
+            c.data().right_encoder
+        })?
+        .func_wrap(HOST_MODULE, "gyro_z", |c: Caller<'_, Host>| c.data().gyro_z)?
+        .func_wrap(HOST_MODULE, "accel_x", |c: Caller<'_, Host>| {
+            c.data().accel_x
+        })?
+        .func_wrap(HOST_MODULE, "accel_y", |c: Caller<'_, Host>| {
+            c.data().accel_y
+        })?
+        .func_wrap(HOST_MODULE, "temperature", |c: Caller<'_, Host>| {
+            c.data().temperature
+        })?
+        .func_wrap(
+            HOST_MODULE,
+            "set_power",
+            |mut c: Caller<'_, Host>, left: f32, right: f32| {
+                let command = &mut c.data_mut().command;
+                command.left_power = left;
+                command.right_power = right;
+            },
+        )?
+        .func_wrap(
+            HOST_MODULE,
+            "set_brake",
+            |mut c: Caller<'_, Host>, left: i32, right: i32| {
+                let command = &mut c.data_mut().command;
+                command.left_brake = left != 0;
+                command.right_brake = right != 0;
+            },
+        )?
+        .func_wrap(
+            HOST_MODULE,
+            "finish_calibration",
+            |mut c: Caller<'_, Host>| {
+                c.data_mut().command.calibrated = true;
+            },
+        )?
+        .func_wrap(
+            HOST_MODULE,
+            "localize",
+            |mut c: Caller<'_, Host>, x: i32, y: i32| {
+                c.data_mut().command.localized =
+                    usize::try_from(x).ok().zip(usize::try_from(y).ok());
+            },
+        )?
+        // Logs the UTF-8 text at `ptr` in the exported memory
+        .func_wrap(
+            HOST_MODULE,
+            "log",
+            |mut c: Caller<'_, Host>, ptr: i32, len: i32| {
+                let Some(Extern::Memory(memory)) = c.get_export("memory") else {
+                    return;
+                };
+                let (Ok(ptr), Ok(len)) = (usize::try_from(ptr), usize::try_from(len)) else {
+                    return;
+                };
+                let Some(bytes) = memory.data(&c).get(ptr..ptr.saturating_add(len)) else {
+                    return;
+                };
+                let message = String::from_utf8_lossy(bytes).into_owned();
+                let log = c.data_mut().command.log.get_or_insert_with(String::new);
+                if !log.is_empty() {
+                    log.push('\n');
+                }
+                log.push_str(&message);
+            },
+        )?;
+    Ok(())
+}
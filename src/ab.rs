@@ -0,0 +1,177 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use rayon::prelude::*;
+
+use mimosi_core::internal::error::Error;
+
+use crate::{
+    bench::{self, Outcome, Setup, Stats},
+    report::{OutputFormat, Report, Value},
+};
+
+/// Significance level below which a difference is reported as real.
+const ALPHA: f64 = 0.05;
+
+/// Which of two runs under the same conditions went better.
+///
+/// Finishing beats not finishing, between two finished runs the faster one wins.
+fn compare(a: &Outcome, b: &Outcome) -> Ordering {
+    match (a.finish_time, b.finish_time) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Two sided p-value of the sign test: how likely a split at least this uneven is
+/// if both controllers were equally good.
+fn sign_test(a_wins: usize, b_wins: usize) -> f64 {
+    let n = a_wins + b_wins;
+    if n == 0 {
+        return 1.0;
+    }
+    // Sums the binomial probabilities of the tail in log space, so large counts don't underflow
+    let mut log_choose = 0.0;
+    let mut tail = 0.0;
+    for i in 0..=a_wins.min(b_wins) {
+        if i > 0 {
+            log_choose += ((n - i + 1) as f64 / i as f64).ln();
+        }
+        tail += (log_choose - n as f64 * std::f64::consts::LN_2).exp();
+    }
+    (2.0 * tail).min(1.0)
+}
+
+/// How two controllers did against each other on a set of paired runs.
+struct Comparison {
+    pairs: usize,
+    a_finished: usize,
+    b_finished: usize,
+    a_wins: usize,
+    b_wins: usize,
+    delta: Option<Stats>, // Finish time of B minus A, where both finished
+    p_value: f64,
+}
+
+impl Comparison {
+    fn new<'a>(pairs: impl Iterator<Item = &'a (Outcome, Outcome)>) -> Self {
+        let pairs: Vec<_> = pairs.collect();
+        let wins = |order| pairs.iter().filter(|(a, b)| compare(a, b) == order).count();
+        let (a_wins, b_wins) = (wins(Ordering::Greater), wins(Ordering::Less));
+        let deltas: Vec<f32> = pairs
+            .iter()
+            .filter_map(|(a, b)| Some(b.finish_time? - a.finish_time?))
+            .collect();
+        Self {
+            pairs: pairs.len(),
+            a_finished: pairs
+                .iter()
+                .filter(|(a, _)| a.finish_time.is_some())
+                .count(),
+            b_finished: pairs
+                .iter()
+                .filter(|(_, b)| b.finish_time.is_some())
+                .count(),
+            a_wins,
+            b_wins,
+            delta: Stats::new(&deltas),
+            p_value: sign_test(a_wins, b_wins),
+        }
+    }
+
+    fn row(&self, name: String) -> Vec<Value> {
+        let decided = self.a_wins + self.b_wins;
+        vec![
+            name.into(),
+            self.pairs.into(),
+            self.a_finished.into(),
+            self.b_finished.into(),
+            self.a_wins.into(),
+            self.b_wins.into(),
+            (self.pairs - decided).into(),
+            if decided > 0 {
+                Value::number(self.b_wins as f32 / decided as f32 * 100.0, 1)
+            } else {
+                Value::Missing
+            },
+            self.delta
+                .map_or(Value::Missing, |s| Value::number(s.median, 3)),
+            self.delta
+                .map_or(Value::Missing, |s| Value::number(s.mean, 3)),
+            Value::number(self.p_value as f32, 4),
+        ]
+    }
+}
+
+/// Runs two controllers on every maze with the same seeds and compares them pair by pair.
+///
+/// Both see identical conditions in each pair, so the differences come from the controllers alone.
+/// Times are in seconds, deltas are B minus A, so negative deltas mean B is faster.
+pub fn ab(
+    mazes: &[(PathBuf, String)],
+    (a, b): (&(PathBuf, String), &(PathBuf, String)),
+    setup: &Setup,
+    seeds: u64,
+    duration: f32,
+    dt: f32,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let runs: Vec<_> = (0..mazes.len())
+        .flat_map(|maze| (0..seeds).map(move |seed| (maze, seed)))
+        .collect();
+    // Every run builds its own simulation, which isn't shared between threads
+    let outcomes = runs
+        .par_iter()
+        .map(|&(maze, seed)| {
+            let run = |script| {
+                bench::run(
+                    &mazes[maze].1,
+                    &Setup { script, ..*setup },
+                    seed,
+                    duration,
+                    dt,
+                )
+            };
+            Ok((run(&a.1)?, run(&b.1)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut report = Report::new(&[
+        "maze",
+        "pairs",
+        "a_finished",
+        "b_finished",
+        "a_wins",
+        "b_wins",
+        "ties",
+        "b_win_rate",
+        "delta_median",
+        "delta_mean",
+        "p_value",
+    ]);
+    report.header(format!("A: {}", a.0.display()));
+    report.header(format!("B: {}", b.0.display()));
+    for (i, (path, _)) in mazes.iter().enumerate() {
+        let pairs = runs
+            .iter()
+            .zip(&outcomes)
+            .filter(|((maze, _), _)| *maze == i)
+            .map(|(_, pair)| pair);
+        report.push(Comparison::new(pairs).row(path.display().to_string()));
+    }
+    let total = Comparison::new(outcomes.iter());
+    if mazes.len() > 1 {
+        report.push(total.row(String::from("total")));
+    }
+
+    let p_value = total.p_value;
+    report.footer(if p_value >= ALPHA {
+        format!("No significant difference between A and B (p = {p_value:.4})")
+    } else if total.b_wins > total.a_wins {
+        format!("B is better than A (p = {p_value:.4})")
+    } else {
+        format!("A is better than B (p = {p_value:.4})")
+    });
+    report.print(output)
+}
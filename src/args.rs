@@ -1,6 +1,11 @@
-use std::path::PathBuf;
+use std::{net::IpAddr, path::PathBuf};
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use mazeparser::WallRun;
+
+use crate::{
+    convert::MazeFormat, export::ImageFormat, report::OutputFormat, telemetry::TelemetryFormat,
+};
 
 #[derive(Parser)]
 pub struct Args {
@@ -13,12 +18,478 @@ pub enum Command {
     ExampleMouse,
     ExampleMaze,
     ExampleScript,
-    Simulate {
+    /// Print statistics about a maze
+    Info {
+        maze: Option<PathBuf>,
+        /// Top speed used for time estimates, in cells per second
+        #[arg(long, default_value_t = 1.0)]
+        speed: f32,
+        /// Acceleration used for time estimates, in cells per second²
+        #[arg(long, default_value_t = 2.0)]
+        acceleration: f32,
+        #[arg(long, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+    /// Check a maze for mistakes the parser accepts, like an unreachable finish or gaps in the outer walls
+    Validate {
+        maze: PathBuf,
+        /// Reject numbers with a decimal comma instead of reading them like a decimal point, e.g. for the mazes of a competition
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Open a maze in an editor to toggle walls and move the start and finish by clicking
+    Edit {
+        /// Maze to edit, created when it doesn't exist yet
+        maze: PathBuf,
+        /// Cells per side of a new maze
+        #[arg(long, default_value_t = 16)]
+        size: usize,
+    },
+    /// Generate a starter script for a mouse
+    NewScript {
+        #[arg(long)]
+        mouse: Option<PathBuf>,
+    },
+    /// Run a script on a maze in the window, which is what happens without a command
+    Simulate(Box<SimulateArgs>),
+    /// Drive the mouse with the arrow keys instead of a script, to try out a maze and how the mouse handles
+    Drive {
+        maze: Option<PathBuf>,
+        #[arg(long)]
+        mouse: Option<PathBuf>,
+        #[arg(long)]
+        scenario: Option<PathBuf>,
+        /// Where to save the commands recorded while driving
+        #[arg(long, default_value = "track.toml")]
+        track_output: PathBuf,
+    },
+    /// Run the script on mazes without rendering and report the outcomes
+    Bench(BenchArgs),
+    /// Run every script on every maze in parallel and print a table of the outcomes
+    Batch(BatchArgs),
+    /// Run two scripts on the same mazes and seeds and compare them pair by pair
+    Ab(AbArgs),
+    /// Give every script all the runs the scoring rules allow on every maze and rank them
+    Tournament(TournamentArgs),
+    /// Check recorded telemetry for movements the physics shouldn't allow
+    CheckReplay {
+        file: PathBuf,
         #[arg(long)]
         maze: Option<PathBuf>,
         #[arg(long)]
         mouse: Option<PathBuf>,
+        /// Highest plausible acceleration. Defaults to the limit of the mouse or 10 times its top speed
+        #[arg(long)]
+        max_acceleration: Option<f32>,
+    },
+    /// Play back a saved replay without running the script
+    Replay {
+        file: PathBuf,
+    },
+    /// Turn saved replays into a single HTML page to share
+    ReplayReport {
+        #[arg(required = true)]
+        replays: Vec<PathBuf>,
+        #[arg(long, default_value = "report.html")]
+        html: PathBuf,
+    },
+    /// Convert a recorded command track into a starter script
+    TrackToScript {
+        file: PathBuf,
+    },
+    /// Draw a maze, and optionally a recorded run on it, as an image
+    Export {
+        /// Defaults to the maze saved in the replay
+        maze: Option<PathBuf>,
+        /// Saved replay or telemetry of any format to draw the path of
+        #[arg(long)]
+        replay: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t)]
+        format: ImageFormat,
+        /// Defaults to the maze or replay with the extension of the format
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Pixels per cell
+        #[arg(long, default_value_t = 24.0)]
+        cell: f32,
+    },
+    /// Convert a maze between the text format, the binary `.maz` format and JSON
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Format of the input, defaults to the one of its extension
+        #[arg(long, value_enum)]
+        from: Option<MazeFormat>,
+        /// Format of the output, defaults to the one of its extension
+        #[arg(long, value_enum)]
+        to: Option<MazeFormat>,
+    },
+    /// Convert telemetry of any format to CSV
+    DecodeTelemetry {
+        file: PathBuf,
+    },
+    /// List the runs saved by bench
+    History {
+        #[arg(default_value = "history.toml")]
+        file: PathBuf,
+        /// Only show runs of this experiment
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+    /// Fetch mazes of past competitions to bench scripts on, find duplicates and patch mazes
+    Mazes {
+        #[command(subcommand)]
+        command: MazesCommand,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum MazesCommand {
+    /// List the maze sets that can be fetched
+    List,
+    /// Download a maze set and convert it to the native format
+    Fetch {
+        set: String,
+        /// Where to save the set, in a directory named after it
+        #[arg(long, default_value = "mazes")]
+        dir: PathBuf,
+        /// Replace mazes that changed since they were last fetched
+        #[arg(long)]
+        update: bool,
+    },
+    /// List mazes that repeat others, also when rotated or mirrored
+    Duplicates {
+        /// Maze files or directories of mazes
+        #[arg(required = true)]
+        mazes: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+    /// Add and remove walls or change settings of a maze from the command line
+    Edit {
+        maze: PathBuf,
+        /// Wall to add, e.g. `R3:2-5` on row line 3 from x 2 to 5 or `C7:1-2` on column line 7. Can be given multiple times
+        #[arg(long)]
+        add_wall: Vec<WallRun>,
+        /// Wall to remove, in the same format. Can be given multiple times
+        #[arg(long)]
+        remove_wall: Vec<WallRun>,
+        /// Line of the text format to replace, e.g. `FR:0.8` or `SP:0,15`. Can be given multiple times
         #[arg(long)]
-        script: Option<PathBuf>,
+        set: Vec<String>,
+        /// Layer to add and remove the walls on, 1 for the upper layer of a bridge
+        #[arg(long, default_value_t = 0)]
+        layer: usize,
+        /// Where to write the patched maze. Defaults to the input, or a `.maze` next to it for `.maz` files
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Write the maze even if it has problems `validate` would report
+        #[arg(long)]
+        force: bool,
     },
 }
+
+#[derive(Parser, Clone)]
+pub struct SimulateArgs {
+    #[arg(long)]
+    pub maze: Option<PathBuf>,
+    #[arg(long)]
+    pub mouse: Option<PathBuf>,
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Seed of the random number generators instead of the one of the scenario
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Drive with a recorded command track instead of the script
+    #[arg(long)]
+    pub track: Option<PathBuf>,
+    /// Wait for a controller on this address that drives the mouse over TCP instead of the script
+    #[arg(long)]
+    pub controller: Option<String>,
+    /// Drive with firmware compiled to WebAssembly instead of the script
+    #[arg(long, conflicts_with = "controller")]
+    pub wasm: Option<PathBuf>,
+    /// Create this file and wait for a controller on the same machine to drive the mouse through it
+    #[arg(long, conflicts_with_all = ["controller", "wasm"])]
+    pub shared_memory: Option<PathBuf>,
+    /// Where to save the commands recorded while driving by hand
+    #[arg(long, default_value = "track.toml")]
+    pub track_output: PathBuf,
+    /// How much simulated time passes per real second
+    #[arg(long, default_value_t = 1.0)]
+    pub time_scale: f32,
+    /// Time scale at which the renderer starts to decimate
+    #[arg(long, default_value_t = 4.0)]
+    pub decimation_threshold: f32,
+    /// Simulated seconds per physics step, independent of the frame rate
+    #[arg(long, default_value_t = 0.001)]
+    pub physics_dt: f32,
+    /// Maximum number of sensor rays drawn while decimating
+    #[arg(long, default_value_t = 4)]
+    pub max_sensor_rays: usize,
+    /// Where to save the replay of the run
+    #[arg(long, default_value = "replay.toml")]
+    pub replay_output: PathBuf,
+    /// Where to export the mouse config with the values tuned in the inspector
+    #[arg(long, default_value = "mouse.tuned.toml")]
+    pub tuned_mouse: PathBuf,
+    /// Where to save the summary shown at the end of a run
+    #[arg(long, default_value = "summary.png")]
+    pub summary: PathBuf,
+    /// Where to save the coverage heatmap
+    #[arg(long, default_value = "heatmap.svg")]
+    pub heatmap: PathBuf,
+    /// Size of the side panel relative to the default, on top of the scaling of the display
+    #[arg(long, default_value_t = 1.0)]
+    pub ui_scale: f32,
+    /// Show the performance overlay from the start
+    #[arg(long)]
+    pub perf: bool,
+    /// Start driving by hand with the arrow keys, with the sensor readings shown
+    #[arg(long)]
+    pub manual: bool,
+    /// Where to save the telemetry of the run when exiting
+    #[arg(long)]
+    pub telemetry: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t)]
+    pub telemetry_format: TelemetryFormat,
+    /// Stream the telemetry of every physics step as JSON to WebSocket clients on this port
+    #[arg(long)]
+    pub telemetry_port: Option<u16>,
+    /// Address the telemetry stream listens on, only this machine can connect by default
+    #[arg(long, default_value = "127.0.0.1")]
+    pub telemetry_address: IpAddr,
+    /// Name of the experiment, saved with the telemetry and replays
+    #[arg(long)]
+    pub tag: Option<String>,
+    #[command(flatten)]
+    pub start: StartArgs,
+    /// Pause when the simulated time reaches this many seconds, to step through what happens next
+    #[arg(long)]
+    pub pause_at: Option<f32>,
+    /// Where to save detailed results of the run when exiting, as CSV if it ends in .csv and JSON otherwise
+    #[arg(long)]
+    pub results: Option<PathBuf>,
+    /// Race these scripts against the main one on the same maze, drawn as ghosts
+    #[arg(long)]
+    pub race: Vec<PathBuf>,
+    /// Play back these replays next to the mouse, e.g. to compare a new tune against the best run so far
+    #[arg(long)]
+    pub ghost: Vec<PathBuf>,
+    /// Show the hints of this tutorial one after another, each once its trigger happens
+    #[arg(long)]
+    pub tutorial: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone)]
+pub struct BenchArgs {
+    pub mazes: Vec<PathBuf>,
+    #[arg(long)]
+    pub mouse: Option<PathBuf>,
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Drive with a recorded command track instead of the script
+    #[arg(long)]
+    pub track: Option<PathBuf>,
+    /// Wait for a controller on this address that drives the mouse over TCP instead of the script
+    #[arg(long)]
+    pub controller: Option<String>,
+    /// Drive with firmware compiled to WebAssembly instead of the script
+    #[arg(long, conflicts_with = "controller")]
+    pub wasm: Option<PathBuf>,
+    /// Create this file and wait for a controller on the same machine to drive the mouse through it
+    #[arg(long, conflicts_with_all = ["controller", "wasm"])]
+    pub shared_memory: Option<PathBuf>,
+    /// Seed of the first run instead of the one of the scenario, the others count up from it
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// How many runs with different random seeds to do per maze
+    #[arg(long, default_value_t = 1)]
+    pub seeds: u64,
+    /// Simulated seconds after which a run is stopped
+    #[arg(long, default_value_t = 300.0)]
+    pub duration: f32,
+    /// Simulated seconds per physics step
+    #[arg(long, default_value_t = 0.001)]
+    pub dt: f32,
+    /// Name of the experiment, saved with the results
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// File the outcome of every run is appended to
+    #[arg(long)]
+    pub history: Option<PathBuf>,
+    #[command(flatten)]
+    pub watchdog: WatchdogArgs,
+    #[command(flatten)]
+    pub start: StartArgs,
+    /// Keep the simulated time in step with the wall clock, for controllers that expect sensor readings in real time
+    #[arg(long)]
+    pub realtime: bool,
+    /// How many milliseconds a paced run may fall behind the wall clock before it skips ahead
+    #[arg(long, default_value_t = 5.0, requires = "realtime")]
+    pub tolerance: f32,
+    #[arg(long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Limits for headless runs, so a runaway script can't stall the others.
+#[derive(clap::Args, Clone)]
+pub struct WatchdogArgs {
+    /// Real seconds after which a run is stopped
+    #[arg(long)]
+    pub timeout: Option<f32>,
+    /// Most script operations allowed in a single step
+    #[arg(long)]
+    pub max_operations: Option<u64>,
+}
+
+/// The direction the mouse faces at the start, north is up.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Heading {
+    #[value(name = "N", alias = "n")]
+    North,
+    #[value(name = "E", alias = "e")]
+    East,
+    #[value(name = "S", alias = "s")]
+    South,
+    #[value(name = "W", alias = "w")]
+    West,
+}
+
+/// Overrides for the start of the maze, to begin a run deep inside it.
+#[derive(clap::Args, Clone, Default)]
+pub struct StartArgs {
+    /// Start in this cell instead of the one of the maze, e.g. `3,4`
+    #[arg(long, value_parser = parse_cell)]
+    pub start_cell: Option<(usize, usize)>,
+    /// Face this way at the start instead of the direction of the maze
+    #[arg(long, value_enum)]
+    pub start_heading: Option<Heading>,
+}
+
+fn parse_cell(s: &str) -> Result<(usize, usize), String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("Expected a cell as x,y, got {s}"))?;
+    let parse = |v: &str| v.trim().parse().map_err(|e| format!("{v}: {e}"));
+    Ok((parse(x)?, parse(y)?))
+}
+
+#[derive(Parser, Clone)]
+pub struct BatchArgs {
+    /// Maze files or directories containing them
+    #[arg(required = true)]
+    pub mazes: Vec<PathBuf>,
+    /// Scripts to run, can be given multiple times
+    #[arg(long = "script")]
+    pub scripts: Vec<PathBuf>,
+    #[arg(long)]
+    pub mouse: Option<PathBuf>,
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Seed of the random number generators instead of the one of the scenario
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Simulated seconds after which a run is stopped
+    #[arg(long, default_value_t = 300.0)]
+    pub duration: f32,
+    /// Simulated seconds per physics step
+    #[arg(long, default_value_t = 0.001)]
+    pub dt: f32,
+    /// How many runs to do at the same time. Defaults to the number of CPUs
+    #[arg(long)]
+    pub jobs: Option<usize>,
+    #[command(flatten)]
+    pub watchdog: WatchdogArgs,
+    #[arg(long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+    /// Also write the results with the trajectory of every run to this HTML file
+    #[arg(long)]
+    pub html: Option<PathBuf>,
+    /// Write detailed results of every run to this file, as CSV if it ends in .csv and JSON otherwise
+    #[arg(long)]
+    pub results: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone)]
+pub struct TournamentArgs {
+    /// Maze files or directories containing them
+    #[arg(required = true)]
+    pub mazes: Vec<PathBuf>,
+    /// Scripts of the entrants, can be given multiple times
+    #[arg(long = "script", required = true)]
+    pub scripts: Vec<PathBuf>,
+    #[arg(long)]
+    pub mouse: Option<PathBuf>,
+    /// Scoring rules are taken from its `[scoring]` section, the defaults are used without one
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Seed of the random number generators instead of the one of the scenario
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Simulated seconds after which a mouse is stopped. Defaults to the maze time of the scoring rules
+    #[arg(long)]
+    pub duration: Option<f32>,
+    /// Simulated seconds per physics step
+    #[arg(long, default_value_t = 0.001)]
+    pub dt: f32,
+    /// How many runs to do at the same time. Defaults to the number of CPUs
+    #[arg(long)]
+    pub jobs: Option<usize>,
+    #[command(flatten)]
+    pub watchdog: WatchdogArgs,
+    #[arg(long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+    /// Also write the leaderboard with the trajectory of every entrant on every maze to this HTML file
+    #[arg(long)]
+    pub html: Option<PathBuf>,
+    /// Write detailed results of every entrant on every maze to this file, as CSV if it ends in .csv and JSON otherwise
+    #[arg(long)]
+    pub results: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone)]
+pub struct AbArgs {
+    #[arg(long)]
+    pub a: PathBuf,
+    #[arg(long)]
+    pub b: PathBuf,
+    /// Maze files or directories containing them, can be given multiple times
+    #[arg(long, required = true)]
+    pub mazes: Vec<PathBuf>,
+    #[arg(long)]
+    pub mouse: Option<PathBuf>,
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Seed of the first run instead of the one of the scenario, the others count up from it
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// How many runs with different random seeds to do per maze and script
+    #[arg(long, default_value_t = 20)]
+    pub seeds: u64,
+    /// Simulated seconds after which a run is stopped
+    #[arg(long, default_value_t = 300.0)]
+    pub duration: f32,
+    /// Simulated seconds per physics step
+    #[arg(long, default_value_t = 0.001)]
+    pub dt: f32,
+    /// How many runs to do at the same time. Defaults to the number of CPUs
+    #[arg(long)]
+    pub jobs: Option<usize>,
+    #[command(flatten)]
+    pub watchdog: WatchdogArgs,
+    #[arg(long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+impl Default for SimulateArgs {
+    fn default() -> Self {
+        Self::parse_from(["simulate"])
+    }
+}
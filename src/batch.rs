@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use mimosi_core::internal::{error::Error, maze::Maze, replay::Frame};
+
+use crate::{
+    bench::{self, Outcome, Setup},
+    html::{self, RunView},
+    report::{OutputFormat, Report, Value},
+    results::{self, RunResult},
+};
+
+/// Extensions of the files picked up from maze directories.
+const MAZE_EXTENSIONS: &[&str] = &["maze", "maz", "txt"];
+
+/// Expands directories into the maze files they contain, sorted by name.
+pub fn maze_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+            .and_then(|entries| entries.map(|entry| entry.map(|e| e.path())).collect())
+            .map_err(|e| Error::io(path, e))?;
+        entries.retain(|p| {
+            p.is_file()
+                && p.extension().is_some_and(|ext| {
+                    MAZE_EXTENSIONS
+                        .iter()
+                        .any(|known| ext.eq_ignore_ascii_case(known))
+                })
+        });
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// Files a batch writes besides printing the table.
+#[derive(Clone, Copy, Default)]
+pub struct Exports<'a> {
+    pub html: Option<&'a Path>, // The table with the trajectory of every run
+    pub results: Option<&'a Path>, // Detailed results of every run, as JSON or CSV
+}
+
+/// The outcome of a run with its frames, or why it failed.
+type Run = Result<(Outcome, Vec<Frame>), String>;
+
+pub fn name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Runs every script on every maze in parallel and prints a table of the outcomes.
+/// The script of the setup is replaced by each of the scripts.
+/// Times are in seconds and path lengths in meters.
+///
+/// Failing runs and runs stopped by the watchdog are reported in the table instead of stopping the others.
+pub fn batch(
+    mazes: &[(PathBuf, String)],
+    scripts: &[(PathBuf, String)],
+    setup: &Setup,
+    duration: f32,
+    dt: f32,
+    output: OutputFormat,
+    Exports { html, results }: Exports,
+) -> Result<(), Error> {
+    let combinations: Vec<_> = scripts
+        .iter()
+        .flat_map(|script| {
+            mazes
+                .iter()
+                .enumerate()
+                .map(move |(i, maze)| (i, maze, script))
+        })
+        .collect();
+
+    // Every run builds its own simulation, which isn't shared between threads
+    let runs: Vec<(Run, RunResult)> = combinations
+        .par_iter()
+        .map(|(_, (maze_path, maze), (script_path, script))| {
+            let setup = Setup { script, ..*setup };
+            let names = (name(maze_path), name(script_path));
+            match bench::simulate(maze, &setup, 0, duration, dt) {
+                Ok((outcome, sim)) => {
+                    let result = RunResult::new(&sim, names.0, names.1);
+                    // Only keep the frames if they are needed for the HTML report
+                    let frames = if html.is_some() {
+                        sim.recorder.frames
+                    } else {
+                        Vec::new()
+                    };
+                    (Ok((outcome, frames)), result)
+                }
+                Err(e) => {
+                    let e = e.to_string();
+                    (Err(e.clone()), RunResult::failed(names.0, names.1, e))
+                }
+            }
+        })
+        .collect();
+    if let Some(path) = results {
+        let results: Vec<RunResult> = runs.iter().map(|(_, result)| result.clone()).collect();
+        results::write_results(path, &results)?;
+    }
+    let results: Vec<_> = runs.into_iter().map(|(run, _)| run).collect();
+    let outcomes: Vec<Result<Outcome, String>> = results
+        .iter()
+        .map(|result| {
+            result
+                .as_ref()
+                .map(|(outcome, _)| *outcome)
+                .map_err(Clone::clone)
+        })
+        .collect();
+
+    let mut report = Report::new(&[
+        "maze",
+        "script",
+        "finish_time",
+        "left_start",
+        "entered_goal",
+        "crashed",
+        "path_length",
+        "stopped",
+        "error",
+    ]);
+    let mut finished = 0;
+    for ((_, (maze, _), (script, _)), outcome) in combinations.iter().zip(&outcomes) {
+        let mut row: Vec<Value> = vec![name(maze).into(), name(script).into()];
+        match outcome {
+            Ok(outcome) => {
+                finished += outcome.finish_time.is_some() as usize;
+                row.extend([
+                    outcome
+                        .finish_time
+                        .map_or(Value::Missing, |t| Value::number(t, 2)),
+                    outcome
+                        .left_start
+                        .map_or(Value::Missing, |(_, t)| Value::number(t, 3)),
+                    outcome
+                        .entered_goal
+                        .map_or(Value::Missing, |(_, t)| Value::number(t, 3)),
+                    outcome.crashed.into(),
+                    Value::number(outcome.path_length, 2),
+                    outcome.stopped.map(|reason| reason.to_string()).into(),
+                    Value::Missing,
+                ]);
+            }
+            Err(e) => row.extend([
+                Value::Missing,
+                Value::Missing,
+                Value::Missing,
+                Value::Missing,
+                Value::Missing,
+                Value::Missing,
+                e.clone().into(),
+            ]),
+        }
+        report.push(row);
+    }
+    report.footer(format!("Finished: {finished}/{}", outcomes.len()));
+
+    if let Some(html) = html {
+        let parsed = mazes
+            .iter()
+            .map(|(path, maze)| Maze::parse(maze).map_err(|e| Error::maze(&name(path), e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let runs: Vec<RunView> = combinations
+            .iter()
+            .zip(&results)
+            .filter_map(|((index, (maze, _), (script, _)), result)| {
+                Some(RunView {
+                    title: format!("{} / {}", name(maze), name(script)),
+                    maze: &parsed[*index],
+                    frames: &result.as_ref().ok()?.1,
+                })
+            })
+            .collect();
+        html::save(html, "Batch Report", &report, &runs)?;
+    }
+    report.print(output)
+}
@@ -0,0 +1,392 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rhai::{Dynamic, EvalAltResult, Scope};
+
+use mimosi_core::internal::{
+    error::{from_toml, Error},
+    events::Marker,
+    external::ExternalController,
+    kidnap::Kidnapper,
+    manual::CommandTrack,
+    maze::Maze,
+    mouse::MouseConfig,
+    scenario::Scenario,
+    shared_memory::SharedMemoryController,
+    simulation::Simulation,
+    validate,
+    wasm::WasmController,
+};
+
+use crate::{
+    args::WatchdogArgs,
+    history::{self, Entry},
+    pacer::{Pacer, Pacing},
+    report::{OutputFormat, Report, Value},
+};
+
+/// Limits that keep a single run from stalling all the others.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub wall_clock: Option<Duration>, // Real time a run may take
+    pub max_operations: Option<u64>,  // Script operations per step
+}
+
+impl Limits {
+    pub fn from_args(
+        WatchdogArgs {
+            timeout,
+            max_operations,
+        }: WatchdogArgs,
+    ) -> Result<Self, Error> {
+        let wall_clock = timeout
+            .map(Duration::try_from_secs_f32)
+            .transpose()
+            .map_err(|e| Error::Invalid(format!("Invalid timeout: {e}")))?;
+        Ok(Self {
+            wall_clock,
+            max_operations,
+        })
+    }
+}
+
+/// Why the watchdog stopped a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stopped {
+    WallClock,
+    Operations,
+}
+
+impl std::fmt::Display for Stopped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stopped::WallClock => write!(f, "took too long"),
+            Stopped::Operations => write!(f, "too many script operations"),
+        }
+    }
+}
+
+/// Everything except the maze that defines a run.
+#[derive(Clone, Copy)]
+pub struct Setup<'a> {
+    pub mouse: &'a str,
+    pub script: &'a str,
+    pub scenario: &'a Scenario,
+    pub track: Option<&'a CommandTrack>,
+    pub controller: Option<&'a str>, // Address to wait for an external controller on
+    pub wasm: Option<&'a Path>,      // Module of a WebAssembly controller
+    pub shared_memory: Option<&'a Path>, // File to wait for a controller on the same machine on
+    pub tag: Option<&'a str>,        // Name of the experiment the runs belong to
+    pub echo: bool,                  // Print what the script logs
+    pub limits: Limits,
+    pub realtime: Option<Duration>, // Keep to the wall clock within this tolerance
+    pub all_runs: bool, // Keep going after the finish until the scoring rules allow no more runs
+}
+
+impl<'a> Setup<'a> {
+    /// A quiet setup for running scripts against each other, which fill in `script` per run.
+    pub fn from_args(
+        mouse: &'a str,
+        scenario: &'a Scenario,
+        watchdog: WatchdogArgs,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            mouse,
+            script: "",
+            scenario,
+            track: None,
+            controller: None,
+            wasm: None,
+            shared_memory: None,
+            tag: None,
+            echo: false,
+            limits: Limits::from_args(watchdog)?,
+            realtime: None,
+            all_runs: false,
+        })
+    }
+}
+
+/// The result of a single headless run.
+#[derive(Debug, Clone, Copy)]
+pub struct Outcome {
+    pub finish_time: Option<f32>,
+    pub crashed: bool,
+    pub wall_touches: usize,
+    pub coverage: f32,
+    pub path_length: f32, // m
+    pub stopped: Option<Stopped>,
+    pub kidnappings: usize,
+    pub relocalized: usize, // Kidnappings after which the script found the mouse again
+    pub left_start: Option<(u64, f32)>, // Tick and run time the mouse first left the start cell
+    pub entered_goal: Option<(u64, f32)>, // Tick and run time the mouse first entered the goal area
+    pub pacing: Option<Pacing>, // How well the run kept to the wall clock, if it was paced
+}
+
+/// Runs the script without rendering until the mouse finishes, crashes or the time runs out.
+pub fn run(maze: &str, setup: &Setup, seed: u64, duration: f32, dt: f32) -> Result<Outcome, Error> {
+    simulate(maze, setup, seed, duration, dt).map(|(outcome, _)| outcome)
+}
+
+/// Like `run`, but also returns the simulation as it was at the end of the run.
+pub fn simulate(
+    maze: &str,
+    setup: &Setup,
+    seed: u64,
+    duration: f32,
+    dt: f32,
+) -> Result<(Outcome, Simulation), Error> {
+    let maze = Maze::parse(maze).map_err(|e| Error::maze("maze", e))?;
+    let mouse_config: MouseConfig = from_toml("mouse config", setup.mouse)?;
+    let mut sim = Simulation::new(
+        setup.script.to_string(),
+        maze,
+        mouse_config,
+        setup.scenario.with_seed(seed),
+    )?;
+    // The setup is the same for every seed, so warn only once
+    if setup.echo && seed == 0 {
+        for warning in validate::check_setup(&sim) {
+            eprintln!("Warning: {warning}");
+        }
+    }
+    sim.track = setup.track.cloned();
+    if let Some(address) = setup.controller {
+        eprintln!("Waiting for a controller on {address}");
+        sim.external = Some(Box::new(ExternalController::listen(address, &sim.mouse)?));
+    }
+    if let Some(path) = setup.wasm {
+        sim.external = Some(Box::new(WasmController::load(path, &sim.mouse)?));
+    }
+    if let Some(path) = setup.shared_memory {
+        eprintln!("Waiting for a controller on {}", path.display());
+        sim.external = Some(Box::new(SharedMemoryController::create(path, &sim.mouse)?));
+    }
+    sim.console.echo = setup.echo;
+    sim.update(0.0);
+
+    if let Some(max_operations) = setup.limits.max_operations {
+        sim.engine.set_max_operations(max_operations);
+    }
+    let deadline = setup.limits.wall_clock.map(|limit| Instant::now() + limit);
+    if let Some(deadline) = deadline {
+        // Also stops scripts that never return from a single step
+        sim.engine.on_progress(move |operations| {
+            (operations % 1024 == 0 && Instant::now() >= deadline).then_some(Dynamic::UNIT)
+        });
+    }
+
+    let mut scope = Scope::new();
+    scope.push_dynamic("state", Dynamic::from_map(Default::default()));
+    let mut pacer = setup.realtime.map(Pacer::new);
+    let mut stopped = None;
+    let done = |sim: &Simulation| match &sim.score {
+        Some(score) if setup.all_runs => score.is_over(),
+        _ => sim.finished,
+    };
+    while sim.run_time() < duration && !done(&sim) && !sim.collided && !sim.timed_out() {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            stopped = Some(Stopped::WallClock);
+            break;
+        }
+        match sim.step(&mut scope, dt).map_err(|e| *e) {
+            Ok(()) => (),
+            Err(EvalAltResult::ErrorTerminated(..)) => {
+                stopped = Some(Stopped::WallClock);
+                break;
+            }
+            Err(EvalAltResult::ErrorTooManyOperations(..)) => {
+                stopped = Some(Stopped::Operations);
+                break;
+            }
+            Err(e) => return Err(Error::runtime(Box::new(e))),
+        }
+        if let Some(pacer) = &mut pacer {
+            pacer.pace(sim.time);
+        }
+    }
+
+    let outcome = Outcome {
+        finish_time: sim.finished.then_some(sim.run_time()),
+        crashed: sim.collided,
+        wall_touches: sim.wall_touches,
+        coverage: sim.coverage(),
+        path_length: sim.path_length(),
+        stopped,
+        kidnappings: sim.kidnapper.as_ref().map_or(0, |k| k.kidnappings.len()),
+        relocalized: sim.kidnapper.as_ref().map_or(0, Kidnapper::relocalized),
+        left_start: sim.first_crossing(Marker::Start, false),
+        entered_goal: sim.first_crossing(Marker::Goal, true),
+        pacing: pacer.as_ref().map(Pacer::pacing),
+    };
+    Ok((outcome, sim))
+}
+
+/// Two-sided 95% quantiles of Student's t-distribution for 1 to 30 degrees of freedom.
+const T_95: [f32; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// The t quantile for a 95% confidence interval, rounded towards wider intervals beyond the table.
+fn t_quantile(degrees_of_freedom: usize) -> f32 {
+    match degrees_of_freedom {
+        0 => f32::NAN,
+        1..=30 => T_95[degrees_of_freedom - 1],
+        31..=40 => 2.042,
+        41..=60 => 2.021,
+        61..=120 => 2.000,
+        _ => 1.980,
+    }
+}
+
+/// Summary statistics of a set of values.
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub mean: f32,
+    pub median: f32,
+    pub worst: f32,
+    /// Half width of the 95% confidence interval of the mean
+    pub confidence: f32,
+}
+
+impl Stats {
+    /// `None` if there are no values. Larger values are considered worse.
+    pub fn new(values: &[f32]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let n = sorted.len() as f32;
+        let mean = sorted.iter().sum::<f32>() / n;
+        let median = if sorted.len() % 2 == 0 {
+            (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0
+        } else {
+            sorted[sorted.len() / 2]
+        };
+        // A single run says nothing about the spread
+        let confidence = if sorted.len() > 1 {
+            let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / (n - 1.0);
+            t_quantile(sorted.len() - 1) * variance.sqrt() / n.sqrt()
+        } else {
+            0.0
+        };
+        Some(Self {
+            mean,
+            median,
+            worst: sorted[sorted.len() - 1],
+            confidence,
+        })
+    }
+}
+
+/// Runs every maze once per seed and prints the aggregated outcomes.
+/// Times are in seconds and the coverage in percent.
+/// With a history file, every run is also appended to it.
+pub fn bench(
+    mazes: &[(PathBuf, String)],
+    setup: &Setup,
+    seeds: u64,
+    duration: f32,
+    dt: f32,
+    history: Option<&Path>,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let mut report = Report::new(&[
+        "maze",
+        "runs",
+        "finished",
+        "crashed",
+        "wall_touches",
+        "stopped",
+        "time_mean",
+        "time_confidence",
+        "time_median",
+        "time_worst",
+        "coverage_mean",
+        "coverage_confidence",
+        "coverage_median",
+        "coverage_worst",
+    ]);
+    if let Some(tag) = setup.tag {
+        report.header(format!("Experiment: {tag}"));
+    }
+    let (mut kidnappings, mut relocalized) = (0, 0);
+    let mut pacing: Option<Pacing> = None;
+    for (path, maze) in mazes {
+        let outcomes = (0..seeds)
+            .map(|seed| run(maze, setup, seed, duration, dt))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(history) = history {
+            let entries = outcomes
+                .iter()
+                .enumerate()
+                .map(|(seed, outcome)| {
+                    Entry::new(
+                        setup.tag.map(String::from),
+                        path.display().to_string(),
+                        setup.scenario.seed.wrapping_add(seed as u64),
+                        outcome,
+                    )
+                })
+                .collect();
+            history::append(history, entries)?;
+        }
+
+        let finish_times: Vec<f32> = outcomes.iter().filter_map(|o| o.finish_time).collect();
+        let crashes = outcomes.iter().filter(|o| o.crashed).count();
+        let touches: usize = outcomes.iter().map(|o| o.wall_touches).sum();
+        let stopped = outcomes.iter().filter(|o| o.stopped.is_some()).count();
+        kidnappings += outcomes.iter().map(|o| o.kidnappings).sum::<usize>();
+        relocalized += outcomes.iter().map(|o| o.relocalized).sum::<usize>();
+        for run in outcomes.iter().filter_map(|o| o.pacing) {
+            let total = pacing.get_or_insert_with(Pacing::default);
+            total.jitter = total.jitter.max(run.jitter);
+            total.latest = total.latest.max(run.latest);
+            total.resyncs += run.resyncs;
+        }
+        let coverage: Vec<f32> = outcomes.iter().map(|o| o.coverage * 100.0).collect();
+        let time = Stats::new(&finish_times);
+        let worst_coverage = coverage.iter().copied().reduce(f32::min);
+        let coverage = Stats::new(&coverage);
+
+        let number = |value: Option<f32>, decimals| {
+            value.map_or(Value::Missing, |v| Value::number(v, decimals))
+        };
+        report.push(vec![
+            path.display().to_string().into(),
+            outcomes.len().into(),
+            finish_times.len().into(),
+            crashes.into(),
+            touches.into(),
+            stopped.into(),
+            number(time.map(|s| s.mean), 2),
+            number(time.map(|s| s.confidence), 2),
+            number(time.map(|s| s.median), 2),
+            number(time.map(|s| s.worst), 2),
+            number(coverage.map(|s| s.mean), 1),
+            number(coverage.map(|s| s.confidence), 1),
+            number(coverage.map(|s| s.median), 1),
+            number(worst_coverage, 1),
+        ]);
+    }
+    if kidnappings > 0 {
+        report.footer(format!(
+            "Re-localized after {relocalized} of {kidnappings} kidnappings"
+        ));
+    }
+    if let Some(pacing) = pacing {
+        report.footer(format!(
+            "Real time: jitter up to {:.3} ms, steps up to {:.3} ms late, fell behind {} times",
+            pacing.jitter * 1000.0,
+            pacing.latest * 1000.0,
+            pacing.resyncs
+        ));
+    }
+    report.print(output)
+}
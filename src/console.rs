@@ -0,0 +1,24 @@
+use mimosi_core::internal::console::Console;
+use notan::egui::{self, Context};
+
+/// Shows what the script logged in a window.
+pub fn show(console: &mut Console, ctx: &Context) {
+    let mut clear = false;
+    egui::Window::new("Console")
+        .default_width(400.0)
+        .default_height(200.0)
+        .show(ctx, |ui| {
+            clear = ui.button("Clear").clicked();
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for line in &console.lines {
+                        ui.monospace(format!("[{:.3}s] {}", line.time, line.message));
+                    }
+                });
+        });
+    if clear {
+        console.clear();
+    }
+}
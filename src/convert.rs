@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use mazeparser::{Maze, StartDirection};
+use mimosi_core::internal::error::{self, Error};
+
+/// Ways a maze can be stored in a file.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MazeFormat {
+    /// The text format of mimosi
+    Maze,
+    /// The classic binary format with one byte per cell
+    Maz,
+    /// The parsed maze as JSON, for other tools
+    Json,
+}
+
+impl MazeFormat {
+    /// The format a file extension stands for, `.txt` being the text format.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "maze" | "txt" => Some(MazeFormat::Maze),
+            "maz" => Some(MazeFormat::Maz),
+            "json" => Some(MazeFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+fn format_of(path: &Path, format: Option<MazeFormat>) -> Result<MazeFormat, Error> {
+    format
+        .or_else(|| MazeFormat::from_path(path))
+        .ok_or_else(|| {
+            Error::format(
+                path.display(),
+                "Unknown maze format, pass it with --from or --to",
+            )
+        })
+}
+
+fn read(path: &Path, format: MazeFormat) -> Result<Maze, Error> {
+    let name = path.display().to_string();
+    match format {
+        MazeFormat::Maze => error::read(path)?
+            .parse()
+            .map_err(|e| Error::maze(&name, e)),
+        MazeFormat::Maz => {
+            let bytes = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+            Maze::from_maz(&bytes).map_err(|e| Error::format(&name, e))
+        }
+        MazeFormat::Json => {
+            serde_json::from_str(&error::read(path)?).map_err(|e| Error::json(&name, e))
+        }
+    }
+}
+
+/// What a `.maz` file loses of the maze, as readers assume a classic start and finish.
+fn maz_losses(maze: &Maze) -> Vec<&'static str> {
+    let classic = Maze::from_maz(&[0; 256]).expect("An empty maze has the right size");
+    let mut losses = Vec::new();
+    if maze.start_cell() != classic.start_cell() || maze.start_direction != StartDirection::Up {
+        losses.push("the start");
+    }
+    if maze.finish_cells() != classic.finish_cells() {
+        losses.push("the finish");
+    }
+    if maze.name.is_some() || maze.author.is_some() {
+        losses.push("the name and author");
+    }
+    if maze.cell_size.is_some() {
+        losses.push("the cell size");
+    }
+    losses
+}
+
+/// Reads a maze in one format and writes it in another, each given or taken from the extension of the file.
+pub fn convert(
+    input: &Path,
+    output: &Path,
+    from: Option<MazeFormat>,
+    to: Option<MazeFormat>,
+) -> Result<(), Error> {
+    let maze = read(input, format_of(input, from)?)?;
+    let bytes = match format_of(output, to)? {
+        MazeFormat::Maze => maze.to_string().into_bytes(),
+        MazeFormat::Maz => {
+            let bytes = maze
+                .to_maz()
+                .map_err(|e| Error::format(output.display(), e))?;
+            let losses = maz_losses(&maze);
+            if !losses.is_empty() {
+                eprintln!(
+                    "Warning: The .maz format has no room for {}, readers assume the classic ones",
+                    losses.join(", ")
+                );
+            }
+            bytes
+        }
+        MazeFormat::Json => {
+            let json = serde_json::to_string_pretty(&maze)
+                .map_err(|e| Error::format(output.display(), e))?;
+            (json + "\n").into_bytes()
+        }
+    };
+    error::write(output, bytes)
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use mazeparser::grid::Grid;
+use mimosi_core::internal::error::{self, Error};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::report::{OutputFormat, Report};
+
+/// Lists the files of the maze archive collected by micromouseonline, which has the mazes of past competitions.
+const ARCHIVE: &str = "https://api.github.com/repos/micromouseonline/mazefiles/contents";
+/// Name of the file with the checksums of the fetched mazes, in the format of `sha256sum`.
+///
+/// The checksums are trusted on first use: they are taken from the first download, so they only
+/// detect mazes that change in the archive later on, not a first download that was already wrong.
+const CHECKSUMS: &str = "SHA256SUMS";
+
+/// A collection of mazes that can be fetched from the archive.
+pub struct MazeSet {
+    pub name: &'static str,
+    pub description: &'static str,
+    directory: &'static str, // Directory of the archive the mazes are in
+    filter: &'static str,    // Only mazes with this in their file name
+}
+
+pub const SETS: &[MazeSet] = &[
+    MazeSet {
+        name: "classic",
+        description: "All classic 16x16 competition mazes",
+        directory: "classic",
+        filter: "",
+    },
+    MazeSet {
+        name: "apec",
+        description: "Mazes of the APEC micromouse contest",
+        directory: "classic",
+        filter: "apec",
+    },
+    MazeSet {
+        name: "japan",
+        description: "Mazes of the All Japan micromouse contest",
+        directory: "classic",
+        filter: "japan",
+    },
+    MazeSet {
+        name: "halfsize",
+        description: "32x32 mazes of half size competitions",
+        directory: "halfsize",
+        filter: "",
+    },
+];
+
+/// A file in a directory listing of the archive.
+#[derive(Deserialize)]
+struct ArchiveFile {
+    name: String,
+    download_url: Option<String>,
+}
+
+fn get(url: &str) -> Result<String, Error> {
+    let error = |e: &dyn std::fmt::Display| Error::Network {
+        address: url.to_string(),
+        message: e.to_string(),
+    };
+    ureq::get(url)
+        .call()
+        .map_err(|e| error(&e))?
+        .into_string()
+        .map_err(|e| error(&e))
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Hash of the walls of a maze, which all its rotated and mirrored copies share.
+pub fn canonical_hash(maze: &mazeparser::Maze) -> String {
+    let grid = Grid::from_maze(maze).canonical();
+    let mut bytes = format!("{}x{}\n", grid.width, grid.height).into_bytes();
+    bytes.extend_from_slice(grid.cell_walls());
+    sha256(&bytes)
+}
+
+/// A maze with the same walls as an earlier one, maybe rotated or mirrored.
+pub struct Duplicate {
+    pub maze: usize,
+    pub original: usize,
+    pub relation: &'static str, // How the original was changed into the duplicate
+}
+
+/// Finds the mazes that repeat an earlier one. Only the walls are compared, not the start or finish.
+pub fn find_duplicates(mazes: &[mazeparser::Maze]) -> Vec<Duplicate> {
+    let mut seen = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (i, maze) in mazes.iter().enumerate() {
+        match seen.entry(canonical_hash(maze)) {
+            Entry::Occupied(entry) => {
+                let original = *entry.get();
+                let grid = Grid::from_maze(maze);
+                let relation = Grid::from_maze(&mazes[original])
+                    .symmetries()
+                    .into_iter()
+                    .find(|(_, symmetry)| *symmetry == grid)
+                    .map_or("unchanged", |(name, _)| name);
+                duplicates.push(Duplicate {
+                    maze: i,
+                    original,
+                    relation,
+                });
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(i);
+            }
+        }
+    }
+    duplicates
+}
+
+/// Prints the mazes that are duplicates of others in the list, so a benchmark suite can leave them out.
+pub fn print_duplicates(mazes: &[(PathBuf, String)], output: OutputFormat) -> Result<(), Error> {
+    let parsed = mazes
+        .iter()
+        .map(|(path, text)| {
+            mazeparser::Maze::from_str(text)
+                .map_err(|e| Error::maze(&path.display().to_string(), e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let duplicates = find_duplicates(&parsed);
+
+    let mut report = Report::new(&["maze", "duplicate_of", "relation"]);
+    for duplicate in &duplicates {
+        report.push(vec![
+            mazes[duplicate.maze].0.display().to_string().into(),
+            mazes[duplicate.original].0.display().to_string().into(),
+            duplicate.relation.into(),
+        ]);
+    }
+    report.footer(format!(
+        "{} of {} mazes are duplicates",
+        duplicates.len(),
+        mazes.len()
+    ));
+    report.print(output)
+}
+
+/// Warns about mazes of a fetched set that repeat others, which would bias a benchmark on it.
+fn warn_duplicates<'a>(dir: &Path, names: impl Iterator<Item = &'a String>) {
+    let (names, mazes): (Vec<_>, Vec<_>) = names
+        .filter_map(|name| {
+            let text = std::fs::read_to_string(dir.join(name)).ok()?;
+            Some((name, mazeparser::Maze::from_str(&text).ok()?))
+        })
+        .unzip();
+    for duplicate in find_duplicates(&mazes) {
+        eprintln!(
+            "Warning: {} is the same maze as {}, {}",
+            names[duplicate.maze], names[duplicate.original], duplicate.relation
+        );
+    }
+}
+
+fn read_checksums(path: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once("  "))
+        .map(|(hash, name)| (name.to_string(), hash.to_string()))
+        .collect()
+}
+
+pub fn print_sets() -> Result<(), Error> {
+    let mut report = Report::new(&["set", "description"]);
+    for set in SETS {
+        report.push(vec![set.name.into(), set.description.into()]);
+    }
+    report.print(OutputFormat::Table)
+}
+
+/// Downloads a maze set into a subdirectory of `dir`, converted to the native format.
+///
+/// The checksums of the converted mazes are saved next to them. Mazes that changed since
+/// they were last fetched are only replaced with `update`, so a corpus stays the same between runs.
+/// Mazes that weren't fetched before are taken as they are, there are no known checksums to compare them to.
+pub fn fetch(name: &str, dir: &Path, update: bool) -> Result<(), Error> {
+    let set = SETS.iter().find(|set| set.name == name).ok_or_else(|| {
+        let names: Vec<&str> = SETS.iter().map(|set| set.name).collect();
+        Error::Invalid(format!(
+            "Unknown maze set {name}, expected one of: {}",
+            names.join(", ")
+        ))
+    })?;
+    let target = dir.join(set.name);
+    std::fs::create_dir_all(&target).map_err(|e| Error::io(&target, e))?;
+    let checksums_path = target.join(CHECKSUMS);
+    let mut checksums = read_checksums(&checksums_path);
+
+    let url = format!("{ARCHIVE}/{}", set.directory);
+    let files: Vec<ArchiveFile> =
+        serde_json::from_str(&get(&url)?).map_err(|e| Error::json(&url, e))?;
+    let files = files.into_iter().filter(|file| {
+        let name = file.name.to_lowercase();
+        name.ends_with(".txt") && name.contains(set.filter)
+    });
+
+    let (mut fetched, mut changed, mut failed) = (0, Vec::new(), 0);
+    for file in files {
+        let Some(url) = &file.download_url else {
+            continue;
+        };
+        let maze = match get(url)
+            .and_then(|text| mazeparser::Maze::from_ascii(&text).map_err(|e| Error::format(url, e)))
+        {
+            Ok(maze) => maze.to_string(),
+            Err(e) => {
+                eprintln!("Skipping {}: {e}", file.name);
+                failed += 1;
+                continue;
+            }
+        };
+        let name = format!("{}.maze", file.name.trim_end_matches(".txt"));
+        let hash = sha256(maze.as_bytes());
+        if checksums.get(&name).is_some_and(|known| *known != hash) && !update {
+            changed.push(name);
+            continue;
+        }
+        error::write(&target.join(&name), &maze)?;
+        checksums.insert(name, hash);
+        fetched += 1;
+    }
+
+    let lines: String = checksums
+        .iter()
+        .map(|(name, hash)| format!("{hash}  {name}\n"))
+        .collect();
+    error::write(&checksums_path, lines)?;
+    warn_duplicates(&target, checksums.keys());
+    println!(
+        "Fetched {fetched} mazes of {} into {}{}",
+        set.name,
+        target.display(),
+        if failed > 0 {
+            format!(", {failed} failed")
+        } else {
+            String::new()
+        }
+    );
+    for name in &changed {
+        eprintln!("{name} changed since it was last fetched and was kept");
+    }
+    if changed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Found {
+            count: changed.len(),
+            what: "changed mazes, pass --update to replace them",
+        })
+    }
+}
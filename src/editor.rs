@@ -0,0 +1,403 @@
+use std::path::PathBuf;
+
+use mazeparser::{
+    grid::{Cell, Grid, EAST, NORTH, SOUTH, WEST},
+    Maze, StartDirection,
+};
+use mimosi_core::internal::error::Error;
+use notan::draw::*;
+use notan::egui::{self, *};
+use notan::math::{vec2, Vec2};
+use notan::prelude::*;
+
+use crate::lint::{self, Problem};
+
+/// Space around the maze, in pixels.
+const MARGIN: f32 = 20.0;
+/// How close to the line between two cells a click toggles its wall, in cells.
+const EDGE_DISTANCE: f32 = 0.25;
+
+/// A side of a cell, which is the same wall as the opposite side of its neighbor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Edge {
+    cell: Cell,
+    direction: u8,
+}
+
+/// What is being dragged to another cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Handle {
+    Start,
+    Finish { grabbed: Cell }, // Cell of the finish the drag started in
+}
+
+#[derive(AppState)]
+pub struct Editor {
+    path: PathBuf,
+    maze: Maze, // The walls of the floor are only up to date after `sync`
+    grid: Grid, // Walls of the floor, which are the ones being edited
+    problems: Vec<Problem>,
+    changed: bool, // If there are edits that aren't saved yet
+    message: Option<String>,
+    drag: Option<Handle>,
+    cursor: Vec2, // Position of the pointer, in cells
+    scale: f32,   // Pixels per cell
+    panel_width: f32,
+    pointer_over_ui: bool,
+}
+
+/// An empty square maze with only the outer walls, starting in the bottom left corner facing north.
+fn new_maze(size: usize) -> Result<Maze, Error> {
+    if size < 2 {
+        return Err(Error::Invalid(format!(
+            "A maze needs at least 2x2 cells, not {size}x{size}"
+        )));
+    }
+    let (from, to) = if size % 2 == 0 {
+        (size / 2 - 1, size / 2 + 1)
+    } else {
+        (size / 2, size / 2 + 1)
+    };
+    format!(
+        "BOUNDS: {size}x{size}\nSP: 0,{}\nSD: U\nFI: {from},{from};{to},{to}\n",
+        size - 1
+    )
+    .parse()
+    .map_err(|e| Error::maze("new maze", e))
+}
+
+fn clockwise(direction: StartDirection) -> StartDirection {
+    match direction {
+        StartDirection::Up => StartDirection::Right,
+        StartDirection::Right => StartDirection::Down,
+        StartDirection::Down => StartDirection::Left,
+        StartDirection::Left => StartDirection::Up,
+    }
+}
+
+impl Editor {
+    fn new(path: PathBuf, maze: Maze) -> Self {
+        let grid = Grid::from_maze(&maze);
+        let mut editor = Self {
+            path,
+            maze,
+            grid,
+            problems: Vec::new(),
+            changed: false,
+            message: None,
+            drag: None,
+            cursor: Vec2::ZERO,
+            scale: 1.0,
+            panel_width: 0.0,
+            pointer_over_ui: false,
+        };
+        editor.sync();
+        editor
+    }
+
+    /// Writes the edited walls back into the maze and checks it again.
+    fn sync(&mut self) {
+        self.maze.walls.retain(|wall| wall.layer != 0);
+        self.maze.walls.extend(self.grid.to_walls());
+        self.problems = lint::check_maze(&self.maze.to_string(), false).unwrap_or_else(|e| {
+            vec![Problem {
+                line: Some(e.line),
+                message: e.message,
+            }]
+        });
+    }
+
+    fn edit(&mut self, change: impl FnOnce(&mut Self)) {
+        change(self);
+        self.changed = true;
+        self.message = None;
+        self.sync();
+    }
+
+    /// Saves in the text format, next to the original if that was a binary `.maz` file.
+    fn save(&mut self) {
+        let is_maz = self
+            .path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("maz"));
+        let path = if is_maz {
+            self.path.with_extension("maze")
+        } else {
+            self.path.clone()
+        };
+        self.message = Some(match std::fs::write(&path, self.maze.to_string()) {
+            Ok(()) => {
+                self.changed = false;
+                format!("Saved to {}", path.display())
+            }
+            Err(e) => format!("Could not save to {}: {e}", path.display()),
+        });
+    }
+
+    fn cell_at(&self, point: Vec2) -> Option<Cell> {
+        let cell = (point.x.floor() as usize, point.y.floor() as usize);
+        (point.min_element() >= 0.0 && self.grid.contains(cell)).then_some(cell)
+    }
+
+    /// The wall closest to the point, if it is near enough to the line between two cells.
+    fn edge_at(&self, point: Vec2) -> Option<Edge> {
+        let (width, height) = (self.grid.width, self.grid.height);
+        if point.min_element() < -EDGE_DISTANCE {
+            return None;
+        }
+        let line = point.round();
+        let distance = (point - line).abs();
+        if distance.x < distance.y && distance.x < EDGE_DISTANCE {
+            let (column, (_, y)) = (line.x as usize, self.cell_at(vec2(0.0, point.y))?);
+            match column {
+                0 => Some(Edge {
+                    cell: (0, y),
+                    direction: WEST,
+                }),
+                c if c <= width => Some(Edge {
+                    cell: (c - 1, y),
+                    direction: EAST,
+                }),
+                _ => None,
+            }
+        } else if distance.y < EDGE_DISTANCE {
+            let (row, (x, _)) = (line.y as usize, self.cell_at(vec2(point.x, 0.0))?);
+            match row {
+                0 => Some(Edge {
+                    cell: (x, 0),
+                    direction: NORTH,
+                }),
+                r if r <= height => Some(Edge {
+                    cell: (x, r - 1),
+                    direction: SOUTH,
+                }),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    fn finish_contains(&self, cell: Cell) -> bool {
+        self.maze.finish_cells().contains(&cell)
+    }
+
+    /// Moves the finish by whole cells, keeping it inside the maze.
+    fn move_finish(&mut self, (dx, dy): (f32, f32)) {
+        let finish = &mut self.maze.finish;
+        let (min, max) = (finish.start.min(finish.end), finish.start.max(finish.end));
+        let size = max - min;
+        let limit = vec2(self.grid.width as f32, self.grid.height as f32) - size;
+        let moved = (min + vec2(dx, dy)).clamp(Vec2::ZERO, limit.max(Vec2::ZERO));
+        finish.start = moved;
+        finish.end = moved + size;
+    }
+
+    /// Places the start at a cell or turns it clockwise if it is already there.
+    fn drop_start(&mut self, cell: Cell) {
+        if cell == self.maze.start_cell() {
+            self.edit(|editor| {
+                editor.maze.start_direction = clockwise(editor.maze.start_direction)
+            });
+        } else {
+            self.edit(|editor| editor.maze.start = vec2(cell.0 as f32 + 0.5, cell.1 as f32 + 0.5));
+        }
+    }
+
+    fn to_screen(&self, point: Vec2) -> (f32, f32) {
+        (point * self.scale + vec2(MARGIN, MARGIN)).into()
+    }
+}
+
+/// Opens a window to edit the maze at the path, starting with an empty maze if the file doesn't exist yet.
+pub fn open(path: PathBuf, source: Option<String>, size: usize) -> Result<(), Error> {
+    let maze = match source {
+        Some(source) => source
+            .parse()
+            .map_err(|e| Error::maze(&path.display().to_string(), e))?,
+        None => new_maze(size)?,
+    };
+    let win_config = WindowConfig::new()
+        .set_size(1015, 810)
+        .set_min_size(400, 300)
+        .set_resizable(true)
+        .set_high_dpi(true)
+        .set_vsync(true);
+
+    notan::init_with(|| Editor::new(path, maze))
+        .add_config(win_config)
+        .add_config(DrawConfig)
+        .add_config(EguiConfig)
+        .update(update)
+        .draw(draw)
+        .build()
+        .map_err(Error::Window)
+}
+
+fn update(app: &mut App, editor: &mut Editor) {
+    let (x, y) = app.mouse.position();
+    editor.cursor = (vec2(x, y) - vec2(MARGIN, MARGIN)) / editor.scale;
+    let cursor = editor.cursor;
+
+    if app.mouse.left_was_pressed() && !editor.pointer_over_ui {
+        let edge = editor.edge_at(cursor);
+        match (editor.cell_at(cursor), edge) {
+            (_, Some(Edge { cell, direction })) => editor.edit(|editor| {
+                let present = editor.grid.has_wall(cell, direction);
+                editor.grid.set_wall(cell, direction, !present);
+            }),
+            (Some(cell), None) if cell == editor.maze.start_cell() => {
+                editor.drag = Some(Handle::Start)
+            }
+            (Some(cell), None) if editor.finish_contains(cell) => {
+                editor.drag = Some(Handle::Finish { grabbed: cell })
+            }
+            _ => (),
+        }
+    }
+    if app.mouse.left_was_released() {
+        let target = editor.cell_at(cursor);
+        match (editor.drag.take(), target) {
+            (Some(Handle::Start), Some(cell)) => editor.drop_start(cell),
+            (Some(Handle::Finish { grabbed }), Some(cell)) if cell != grabbed => {
+                let offset = (
+                    cell.0 as f32 - grabbed.0 as f32,
+                    cell.1 as f32 - grabbed.1 as f32,
+                );
+                editor.edit(|editor| editor.move_finish(offset));
+            }
+            _ => (),
+        }
+    }
+
+    if app.keyboard.ctrl() && app.keyboard.was_pressed(KeyCode::S) {
+        editor.save();
+    }
+}
+
+fn draw(_app: &mut App, gfx: &mut Graphics, plugins: &mut Plugins, editor: &mut Editor) {
+    let mut draw = gfx.create_draw();
+    draw.clear(Color::GRAY);
+
+    let (width, height) = (editor.grid.width as f32, editor.grid.height as f32);
+    let available = vec2(draw.width() - editor.panel_width, draw.height()) - MARGIN * 2.0;
+    editor.scale = (available / vec2(width, height)).min_element().max(1.0);
+    let scale = editor.scale;
+
+    let finish = &editor.maze.finish;
+    let (min, max) = (finish.start.min(finish.end), finish.start.max(finish.end));
+    draw.rect(editor.to_screen(min), ((max - min) * scale).into())
+        .color(Color::from_hex(editor.maze.finish_color));
+
+    // The corners of the cells, where the walls meet
+    for y in 0..=editor.grid.height {
+        for x in 0..=editor.grid.width {
+            let (px, py) = editor.to_screen(vec2(x as f32, y as f32));
+            draw.circle((scale * 0.04).max(1.0))
+                .position(px, py)
+                .color(Color::from_rgb(0.3, 0.3, 0.3));
+        }
+    }
+
+    let thickness = (scale * 0.08).max(2.0);
+    let wall_color = Color::from_hex(editor.maze.wall_color);
+    for wall in editor.maze.walls.iter().filter(|wall| wall.layer == 0) {
+        draw.line(editor.to_screen(wall.start), editor.to_screen(wall.end))
+            .width(thickness)
+            .color(wall_color);
+    }
+
+    // Shows which wall a click would add or remove
+    let hovered = (!editor.pointer_over_ui && editor.drag.is_none())
+        .then(|| editor.edge_at(editor.cursor))
+        .flatten();
+    if let Some(Edge { cell, direction }) = hovered {
+        let corner = vec2(cell.0 as f32, cell.1 as f32);
+        let (from, to) = match direction {
+            NORTH => (corner, corner + vec2(1.0, 0.0)),
+            EAST => (corner + vec2(1.0, 0.0), corner + vec2(1.0, 1.0)),
+            SOUTH => (corner + vec2(0.0, 1.0), corner + vec2(1.0, 1.0)),
+            _ => (corner, corner + vec2(0.0, 1.0)),
+        };
+        let color = if editor.grid.has_wall(cell, direction) {
+            Color::RED
+        } else {
+            Color::GREEN
+        };
+        draw.line(editor.to_screen(from), editor.to_screen(to))
+            .width(thickness)
+            .color(color);
+    }
+
+    // The start as a triangle pointing in the direction the mouse faces
+    let (x, y) = editor.maze.start_cell();
+    let center = vec2(x as f32 + 0.5, y as f32 + 0.5);
+    let forward = match editor.maze.start_direction {
+        StartDirection::Up => vec2(0.0, -1.0),
+        StartDirection::Right => vec2(1.0, 0.0),
+        StartDirection::Down => vec2(0.0, 1.0),
+        StartDirection::Left => vec2(-1.0, 0.0),
+    };
+    let side = forward.perp() * 0.25;
+    draw.triangle(
+        editor.to_screen(center + forward * 0.3),
+        editor.to_screen(center - forward * 0.25 + side),
+        editor.to_screen(center - forward * 0.25 - side),
+    )
+    .color(Color::BLUE);
+
+    if let (Some(_), Some((x, y))) = (editor.drag, editor.cell_at(editor.cursor)) {
+        draw.rect(editor.to_screen(vec2(x as f32, y as f32)), (scale, scale))
+            .stroke(2.0)
+            .color(Color::BLUE);
+    }
+
+    gfx.render(&draw);
+
+    let output = plugins.egui(|ctx| {
+        let panel = egui::SidePanel::new(egui::panel::Side::Right, "Editor").show(ctx, |ui| {
+            ui.heading("Maze Editor");
+            ui.label(editor.path.display().to_string());
+            ui.label(format!(
+                "{}x{} cells",
+                editor.grid.width, editor.grid.height
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Save (Ctrl+S)").clicked() {
+                    editor.save();
+                }
+                if editor.changed {
+                    ui.label("Unsaved changes");
+                }
+            });
+            if let Some(message) = &editor.message {
+                ui.label(message);
+            }
+
+            ui.separator();
+            ui.label("Click between two cells to add or remove the wall there.");
+            ui.label("Drag the start or the finish to another cell, click the start to turn it.");
+            if editor.maze.layers() > 1 {
+                ui.label(
+                    "Only the floor can be edited, the upper layer of the bridge is kept as it is.",
+                );
+            }
+
+            ui.separator();
+            ui.strong("Problems");
+            if editor.problems.is_empty() {
+                ui.label("None");
+            }
+            ScrollArea::vertical().show(ui, |ui| {
+                // The lines of the problems are those of the saved file, which isn't open here
+                for problem in &editor.problems {
+                    ui.label(&problem.message);
+                }
+            });
+        });
+        editor.panel_width = panel.response.rect.width() * ctx.zoom_factor();
+        editor.pointer_over_ui = ctx.wants_pointer_input();
+    });
+
+    gfx.render(&output);
+}
@@ -0,0 +1,268 @@
+use std::{fmt::Write, path::Path};
+
+use clap::ValueEnum;
+use mazeparser::grid::{EAST, NORTH, SOUTH, WEST};
+use mimosi_core::internal::{
+    error::{self, Error},
+    maze::{Maze, StartDirection},
+    replay::Frame,
+};
+use notan::math::{vec2, Vec2};
+
+use crate::{raster::Raster, renderer::Renderer};
+
+/// Pixels around the maze, so the outer walls aren't cut off.
+const MARGIN: f32 = 2.0;
+const WALL_WIDTH: f32 = 2.0;
+/// Points drawn per trajectory, more don't make a visible difference.
+const MAX_POINTS: usize = 2000;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Vector image, e.g. for documentation
+    #[default]
+    Svg,
+    /// Raster image, e.g. for chats and issue trackers
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+type Color = [u8; 4]; // RGBA
+
+enum Shape {
+    Rect {
+        min: Vec2,
+        max: Vec2,
+        color: Color,
+    },
+    Line {
+        points: Vec<Vec2>,
+        width: f32,
+        color: Color,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+        color: Color,
+    },
+}
+
+fn rgba(color: u32) -> Color {
+    color.to_be_bytes()
+}
+
+fn rgba_color([r, g, b, a]: Color) -> notan::app::Color {
+    notan::app::Color::from_bytes(r, g, b, a)
+}
+
+/// An SVG color attribute like `fill`, with its opacity.
+fn paint(attribute: &str, [r, g, b, a]: Color) -> String {
+    format!(
+        r#"{attribute}="rgb({r},{g},{b})" {attribute}-opacity="{:.3}""#,
+        a as f32 / 255.0
+    )
+}
+
+/// The maze with its start and finish, and optionally a recorded run, in pixels.
+pub struct Image {
+    width: u32,
+    height: u32,
+    shapes: Vec<Shape>,
+}
+
+impl Image {
+    /// Draws the floor of the maze with `cell` pixels per cell, and the path of the frames on it.
+    pub fn new(maze: &Maze, frames: &[Frame], cell: f32) -> Self {
+        let to_pixels = |p: Vec2| p / maze.cell_size * cell + MARGIN;
+        let grid = &maze.grid;
+        let mut shapes = Vec::new();
+
+        shapes.push(Shape::Rect {
+            min: to_pixels(maze.finish.p1.min(maze.finish.p3)),
+            max: to_pixels(maze.finish.p1.max(maze.finish.p3)),
+            color: rgba(maze.finish_material.color),
+        });
+
+        let half = WALL_WIDTH / 2.0;
+        let wall_color = rgba(maze.wall_material.color);
+        // Walls between two cells are only drawn from one of them
+        let last = (grid.width.saturating_sub(1), grid.height.saturating_sub(1));
+        for (x, y) in grid.cells() {
+            let (left, top) = (x as f32 * cell + MARGIN, y as f32 * cell + MARGIN);
+            let (right, bottom) = (left + cell, top + cell);
+            for (direction, (min, max)) in [
+                (NORTH, (vec2(left, top), vec2(right, top))),
+                (EAST, (vec2(right, top), vec2(right, bottom))),
+                (SOUTH, (vec2(left, bottom), vec2(right, bottom))),
+                (WEST, (vec2(left, top), vec2(left, bottom))),
+            ] {
+                let shared =
+                    (direction == EAST && x < last.0) || (direction == SOUTH && y < last.1);
+                if !shared && grid.has_wall((x, y), direction) {
+                    shapes.push(Shape::Rect {
+                        min: min - half,
+                        max: max + half,
+                        color: wall_color,
+                    });
+                }
+            }
+        }
+
+        // The start as a dot with a line towards the direction the mouse faces
+        let start = to_pixels(maze.start);
+        let facing = match maze.start_direction {
+            StartDirection::Up => vec2(0.0, -1.0),
+            StartDirection::Right => vec2(1.0, 0.0),
+            StartDirection::Down => vec2(0.0, 1.0),
+            StartDirection::Left => vec2(-1.0, 0.0),
+        };
+        let start_color = [0, 0, 200, 255];
+        shapes.push(Shape::Circle {
+            center: start,
+            radius: cell * 0.15,
+            color: start_color,
+        });
+        shapes.push(Shape::Line {
+            points: vec![start, start + facing * cell * 0.35],
+            width: WALL_WIDTH,
+            color: start_color,
+        });
+
+        if !frames.is_empty() {
+            let every = (frames.len() / MAX_POINTS).max(1);
+            shapes.push(Shape::Line {
+                points: frames
+                    .iter()
+                    .step_by(every)
+                    .chain(frames.last())
+                    .map(|f| to_pixels(f.position))
+                    .collect(),
+                width: 1.5,
+                color: [0, 0, 255, 255],
+            });
+            for (frame, color) in [
+                (frames.first(), [0, 160, 0, 255]),
+                (frames.last(), [220, 0, 0, 255]),
+            ] {
+                if let Some(frame) = frame {
+                    shapes.push(Shape::Circle {
+                        center: to_pixels(frame.position),
+                        radius: 4.0,
+                        color,
+                    });
+                }
+            }
+        }
+
+        Self {
+            width: (grid.width as f32 * cell + 2.0 * MARGIN).ceil() as u32,
+            height: (grid.height as f32 * cell + 2.0 * MARGIN).ceil() as u32,
+            shapes,
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width, self.height);
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">
+<rect width="100%" height="100%" fill="white"/>
+"#
+        );
+        for shape in &self.shapes {
+            let _ = match shape {
+                Shape::Rect { min, max, color } => writeln!(
+                    svg,
+                    r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" {}/>"#,
+                    min.x,
+                    min.y,
+                    max.x - min.x,
+                    max.y - min.y,
+                    paint("fill", *color)
+                ),
+                Shape::Line {
+                    points,
+                    width,
+                    color,
+                } => {
+                    let points: Vec<String> = points
+                        .iter()
+                        .map(|p| format!("{:.1},{:.1}", p.x, p.y))
+                        .collect();
+                    writeln!(
+                        svg,
+                        r#"<polyline points="{}" fill="none" stroke-width="{width}" stroke-linecap="round" {}/>"#,
+                        points.join(" "),
+                        paint("stroke", *color)
+                    )
+                }
+                Shape::Circle {
+                    center,
+                    radius,
+                    color,
+                } => writeln!(
+                    svg,
+                    r#"<circle cx="{:.1}" cy="{:.1}" r="{radius:.1}" {}/>"#,
+                    center.x,
+                    center.y,
+                    paint("fill", *color)
+                ),
+            };
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn to_png(&self, text: &[(&str, String)]) -> Vec<u8> {
+        let mut raster = Raster::new(self.width, self.height);
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect { min, max, color } => {
+                    raster.rect(*min, *max - *min, rgba_color(*color))
+                }
+                // Segment by segment, so each only looks at the pixels around it
+                Shape::Line {
+                    points,
+                    width,
+                    color,
+                } => {
+                    for pair in points.windows(2) {
+                        raster.line(pair[0], pair[1], *width, rgba_color(*color));
+                    }
+                }
+                Shape::Circle {
+                    center,
+                    radius,
+                    color,
+                } => raster.circle(*center, *radius, rgba_color(*color)),
+            }
+        }
+        raster.to_png(text)
+    }
+}
+
+/// Writes the maze and the path of the frames as an image.
+pub fn export(
+    path: &Path,
+    format: ImageFormat,
+    maze: &Maze,
+    frames: &[Frame],
+    cell: f32,
+) -> Result<(), Error> {
+    let image = Image::new(maze, frames, cell);
+    let bytes = match format {
+        ImageFormat::Svg => image.to_svg().into_bytes(),
+        ImageFormat::Png => {
+            let title = maze.name.clone().map(|name| ("Title", name));
+            image.to_png(&title.into_iter().collect::<Vec<_>>())
+        }
+    };
+    error::write(path, bytes)
+}
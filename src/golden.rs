@@ -0,0 +1,509 @@
+//! Golden-image tests: fixed states are drawn without a window and compared with the images in `test_data/golden`,
+//! so a misplaced offset or a wrong scale shows up as a failing test.
+//!
+//! After an intended change of the drawing, `UPDATE_GOLDEN=1 cargo test golden` writes the images again.
+//!
+//! The window can't be opened in tests, so the calls its renderer would get are recorded instead.
+
+use std::{collections::HashMap, io::Read, path::PathBuf};
+
+use flate2::read::ZlibDecoder;
+use mimosi_core::internal::{
+    maze::Maze, mouse::MouseConfig, scenario::Scenario, simulation::Simulation,
+};
+use notan::{
+    app::Color,
+    egui::{self, epaint::Primitive, ImageData, Pos2, RawInput, Rect, TextureId},
+    math::{vec2, Mat3, Vec2},
+};
+use rhai::{Dynamic, Map, Scope};
+
+use crate::{
+    hud,
+    raster::Raster,
+    render::{self, RayColors, RenderSettings},
+    renderer::Renderer,
+    ruler::Ruler,
+    teleport,
+    view::{RobotView, View, ROBOT_VIEW_SIZE},
+};
+
+/// Pixels that differ by more than this in any channel count as different.
+const TOLERANCE: u8 = 2;
+/// Pixels that may differ, for rounding that isn't the same on every machine. A single missing wall is more.
+const MAX_DIFFERENT: usize = 4;
+/// Physics steps of the fixed run.
+const STEPS: usize = 60;
+const DT: f32 = 1.0 / 60.0;
+
+/// Drives a curve to the right, so the trail, the rays and the script state have something to show.
+const SCRIPT: &str = r#"
+mouse.left_power = 0.6;
+mouse.right_power = 0.45;
+state.calls = if "calls" in state { state.calls + 1 } else { 1 };
+print(`front ${mouse.sensors["FRONT"].value}`);
+"#;
+
+fn simulation(maze: &str) -> Simulation {
+    let maze = Maze::parse(maze).unwrap();
+    let mouse: MouseConfig = toml::from_str(include_str!("../test_data/mouse.toml")).unwrap();
+    let mut sim = Simulation::new(SCRIPT.to_string(), maze, mouse, Scenario::default()).unwrap();
+    sim.update(0.0);
+    sim
+}
+
+fn scope() -> Scope<'static> {
+    let mut scope = Scope::new();
+    scope.push_dynamic("state", Dynamic::from_map(Map::new()));
+    scope
+}
+
+/// The example maze after the script drove for a while.
+fn driven() -> (Simulation, Scope<'static>) {
+    let mut sim = simulation(include_str!("../test_data/example.maze"));
+    let mut scope = scope();
+    for _ in 0..STEPS {
+        sim.step(&mut scope, DT).unwrap();
+    }
+    (sim, scope)
+}
+
+/// Draws the scene with the maze fitted into an image of the given size, as the window does.
+fn draw(sim: &Simulation, size: (u32, u32), scene: impl FnOnce(&mut Raster)) -> Raster {
+    let mut view = View::default();
+    view.fit((size.0 as f32, size.1 as f32), 0.0, &sim.maze);
+    let mut raster = Raster::new(size.0, size.1);
+    raster.push_transform(view.matrix());
+    scene(&mut raster);
+    raster.pop_transform();
+    raster
+}
+
+/// Reads the RGB pixels of a PNG as `png::encode_rgb` writes them.
+fn read_png(bytes: &[u8]) -> (u32, u32, Vec<u8>) {
+    let mut rest = &bytes[8..];
+    let (mut width, mut height, mut data) = (0, 0, Vec::new());
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let (kind, content) = (&rest[4..8], &rest[8..8 + length]);
+        match kind {
+            b"IHDR" => {
+                width = u32::from_be_bytes(content[..4].try_into().unwrap());
+                height = u32::from_be_bytes(content[4..8].try_into().unwrap());
+                assert_eq!(&content[8..10], [8, 2], "Only 8 bit RGB is supported");
+            }
+            b"IDAT" => data.extend(content),
+            _ => (),
+        }
+        rest = &rest[12 + length..];
+    }
+    let mut rows = Vec::new();
+    ZlibDecoder::new(&data[..]).read_to_end(&mut rows).unwrap();
+    let pixels = rows
+        .chunks(width as usize * 3 + 1)
+        .flat_map(|row| {
+            assert_eq!(row[0], 0, "Only unfiltered rows are supported");
+            row[1..].to_vec()
+        })
+        .collect();
+    (width, height, pixels)
+}
+
+/// Compares the image with the golden one of that name, or replaces it with `UPDATE_GOLDEN` set.
+fn assert_golden(name: &str, raster: &Raster) {
+    let path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/golden"))
+        .join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, raster.to_png(&[])).unwrap();
+        return;
+    }
+    let golden = std::fs::read(&path)
+        .unwrap_or_else(|e| panic!("{}: {e}, run with UPDATE_GOLDEN=1", path.display()));
+    let (width, height, expected) = read_png(&golden);
+    assert_eq!((width, height), (raster.width, raster.height), "{name}");
+    let actual = raster.rgb();
+    let different = expected
+        .chunks(3)
+        .zip(actual.chunks(3))
+        .filter(|(e, a)| {
+            e.iter()
+                .zip(a.iter())
+                .any(|(e, a)| e.abs_diff(*a) > TOLERANCE)
+        })
+        .count();
+    let pixels = (width * height) as usize;
+    if different > MAX_DIFFERENT {
+        let actual_path = std::env::temp_dir().join(format!("{name}.actual.png"));
+        std::fs::write(&actual_path, raster.to_png(&[])).unwrap();
+        panic!(
+            "{different} of {pixels} pixels differ from {}, the image is in {}",
+            path.display(),
+            actual_path.display()
+        );
+    }
+}
+
+#[test]
+fn maze_and_mouse_at_the_start() {
+    let sim = simulation(include_str!("../test_data/example.maze"));
+    let image = draw(&sim, (256, 256), |raster| {
+        render::render(&sim, raster, &RenderSettings::default(), false, None, &[]);
+    });
+    assert_golden("start", &image);
+}
+
+#[test]
+fn mouse_with_trail_and_rays_by_group() {
+    let (sim, _) = driven();
+    let settings = RenderSettings {
+        trail_by_speed: true,
+        ray_colors: RayColors::Group,
+        ..Default::default()
+    };
+    let image = draw(&sim, (256, 256), |raster| {
+        render::render(&sim, raster, &settings, false, None, &[]);
+    });
+    assert_golden("driven", &image);
+}
+
+#[test]
+fn decimating_limits_the_rays() {
+    let (sim, _) = driven();
+    let settings = RenderSettings {
+        max_sensor_rays: 1,
+        ..Default::default()
+    };
+    let image = draw(&sim, (256, 256), |raster| {
+        render::render(&sim, raster, &settings, true, None, &[]);
+    });
+    assert_golden("decimated", &image);
+}
+
+/// The upper layer is drawn next to the floor, and so is the mouse when it is up there.
+#[test]
+fn bridge_with_the_mouse_on_the_upper_layer() {
+    let sim = simulation(include_str!("../test_data/bridge.maze"));
+    let mut frame = sim.frame();
+    frame.layer = 1;
+    frame.position += vec2(0.0, sim.maze.cell_size);
+    let image = draw(&sim, (256, 128), |raster| {
+        render::render(
+            &sim,
+            raster,
+            &RenderSettings::default(),
+            false,
+            Some(&frame),
+            &[],
+        );
+    });
+    assert_golden("bridge", &image);
+}
+
+#[test]
+fn overlays_and_ghosts() {
+    let (sim, _) = driven();
+    let start = sim.maze.start;
+    let ruler = Ruler {
+        enabled: true,
+        start: Some(start),
+        end: Some(start + vec2(3.0, 2.0) * sim.maze.cell_size),
+        cursor: Vec2::ZERO,
+    };
+    let mut crashed = sim.frame();
+    crashed.position = start + vec2(0.0, 2.0) * sim.maze.cell_size;
+    crashed.collided = true;
+    let mut finished = sim.frame();
+    finished.position = start + vec2(2.0, 0.0) * sim.maze.cell_size;
+    finished.finished = true;
+    let ghosts = [
+        (crashed, render::rival_color(0)),
+        (finished, render::rival_color(1)),
+    ];
+    let image = draw(&sim, (256, 256), |raster| {
+        render::render(
+            &sim,
+            raster,
+            &RenderSettings::default(),
+            false,
+            None,
+            &ghosts,
+        );
+        ruler.render(raster);
+        teleport::render_handle(&sim, raster);
+    });
+    assert_golden("overlays", &image);
+}
+
+/// The maze turned around the mouse, which stays in the center facing up.
+#[test]
+fn robot_view() {
+    let (sim, _) = driven();
+    let mut raster = Raster::new(ROBOT_VIEW_SIZE, ROBOT_VIEW_SIZE);
+    let mouse = &sim.mouse;
+    raster.push_transform(RobotView::matrix(
+        mouse.position,
+        mouse.orientation,
+        sim.maze.cell_size,
+    ));
+    render::render(
+        &sim,
+        &mut raster,
+        &RenderSettings::default(),
+        false,
+        None,
+        &[],
+    );
+    raster.pop_transform();
+    assert_golden("robot_view", &raster);
+}
+
+/// The cursor maps back to where the view draws a point, and following puts the mouse in the middle.
+#[test]
+fn view_maps_the_window_back_to_the_world() {
+    let (sim, _) = driven();
+    let mut view = View::default();
+    view.fit((320.0, 240.0), 0.0, &sim.maze);
+    view.zoom_at(vec2(40.0, 30.0), 3.0);
+    let position = sim.mouse.position;
+    let drawn = view.matrix().transform_point2(position);
+    assert!(view.to_world(drawn).distance(position) < 1e-3);
+
+    view.follow = true;
+    view.follow(position);
+    let drawn = view.matrix().transform_point2(position);
+    assert!(drawn.distance(vec2(160.0, 120.0)) < 1e-3);
+}
+
+/// Fills the triangles egui tessellated, the way its renderers do.
+fn draw_egui(
+    raster: &mut Raster,
+    primitives: &[egui::ClippedPrimitive],
+    textures: &HashMap<TextureId, ([usize; 2], Vec<egui::Color32>)>,
+) {
+    for primitive in primitives {
+        let Primitive::Mesh(mesh) = &primitive.primitive else {
+            continue;
+        };
+        let clip = primitive.clip_rect;
+        let Some((size, texels)) = textures.get(&mesh.texture_id) else {
+            continue;
+        };
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let [pa, pb, pc] = [a, b, c].map(|v| vec2(v.pos.x, v.pos.y));
+            let area = (pb - pa).perp_dot(pc - pa);
+            if area == 0.0 {
+                continue;
+            }
+            let min = pa
+                .min(pb)
+                .min(pc)
+                .max(vec2(clip.min.x, clip.min.y))
+                .max(Vec2::ZERO);
+            let max = pa.max(pb).max(pc).min(vec2(clip.max.x, clip.max.y));
+            for y in min.y.floor() as u32..max.y.ceil().max(0.0) as u32 {
+                for x in min.x.floor() as u32..max.x.ceil().max(0.0) as u32 {
+                    let p = vec2(x as f32 + 0.5, y as f32 + 0.5);
+                    let weights = [
+                        (pc - pb).perp_dot(p - pb) / area,
+                        (pa - pc).perp_dot(p - pc) / area,
+                        (pb - pa).perp_dot(p - pa) / area,
+                    ];
+                    if weights.iter().any(|w| *w < 0.0) || !clip.contains(Pos2::new(p.x, p.y)) {
+                        continue;
+                    }
+                    let uv = [a, b, c]
+                        .iter()
+                        .zip(weights)
+                        .fold(Vec2::ZERO, |uv, (v, w)| uv + vec2(v.uv.x, v.uv.y) * w);
+                    let [tx, ty] = [0, 1]
+                        .map(|axis| ((uv[axis] * size[axis] as f32) as usize).min(size[axis] - 1));
+                    let texel = texels[ty * size[0] + tx];
+                    // Colors are premultiplied by their alpha
+                    let rgba: [f32; 4] = std::array::from_fn(|channel| {
+                        let vertex: f32 = [a, b, c]
+                            .iter()
+                            .zip(weights)
+                            .map(|(v, w)| v.color.to_array()[channel] as f32 * w)
+                            .sum();
+                        vertex / 255.0 * texel.to_array()[channel] as f32
+                    });
+                    let alpha = rgba[3] / 255.0;
+                    if alpha > 0.0 {
+                        raster.blend(x, y, [rgba[0], rgba[1], rgba[2]].map(|c| c / alpha), alpha);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn hud_after_the_run() {
+    let (sim, scope) = driven();
+    let (width, height) = (320, 500);
+    let ctx = egui::Context::default();
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(
+            Pos2::ZERO,
+            egui::vec2(width as f32, height as f32),
+        )),
+        ..Default::default()
+    };
+    let mut textures = HashMap::new();
+    let mut output = None;
+    // Windows take a few frames to settle on their size
+    for _ in 0..3 {
+        let frame = ctx.run(input.clone(), |ctx| hud::show(ctx, &sim, &scope));
+        for (id, delta) in &frame.textures_delta.set {
+            let texels: Vec<_> = match &delta.image {
+                ImageData::Color(image) => image.pixels.clone(),
+                ImageData::Font(image) => image.srgba_pixels(None).collect(),
+            };
+            let [w, h] = delta.image.size();
+            match delta.pos {
+                None => {
+                    textures.insert(*id, ([w, h], texels));
+                }
+                Some([x0, y0]) => {
+                    let (size, existing): &mut ([usize; 2], Vec<_>) = textures.get_mut(id).unwrap();
+                    for y in 0..h {
+                        for x in 0..w {
+                            existing[(y0 + y) * size[0] + x0 + x] = texels[y * w + x];
+                        }
+                    }
+                }
+            }
+        }
+        output = Some(frame);
+    }
+    let output = output.unwrap();
+    let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+    let mut raster = Raster::new(width, height);
+    raster.clear(Color::GRAY);
+    draw_egui(&mut raster, &primitives, &textures);
+    assert_golden("hud", &raster);
+}
+
+/// A call the renderer got.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Call {
+    Clear(Color),
+    PushTransform(Mat3),
+    PopTransform,
+    Rect(Vec2, Vec2, Color),
+    Triangle([Vec2; 3], Color),
+    Line(Vec2, Vec2, f32, Color),
+    Circle(Vec2, f32, Color),
+}
+
+/// Keeps the calls instead of drawing them, to see what the window would be asked to draw.
+#[derive(Default)]
+struct Recording(Vec<Call>);
+
+impl Recording {
+    fn replay(&self, renderer: &mut impl Renderer) {
+        for call in &self.0 {
+            match *call {
+                Call::Clear(color) => renderer.clear(color),
+                Call::PushTransform(matrix) => renderer.push_transform(matrix),
+                Call::PopTransform => renderer.pop_transform(),
+                Call::Rect(position, size, color) => renderer.rect(position, size, color),
+                Call::Triangle(points, color) => renderer.triangle(points, color),
+                Call::Line(from, to, width, color) => renderer.line(from, to, width, color),
+                Call::Circle(center, radius, color) => renderer.circle(center, radius, color),
+            }
+        }
+    }
+}
+
+impl Renderer for Recording {
+    fn clear(&mut self, color: Color) {
+        self.0.push(Call::Clear(color));
+    }
+
+    fn push_transform(&mut self, matrix: Mat3) {
+        self.0.push(Call::PushTransform(matrix));
+    }
+
+    fn pop_transform(&mut self) {
+        self.0.push(Call::PopTransform);
+    }
+
+    fn rect(&mut self, position: Vec2, size: Vec2, color: Color) {
+        self.0.push(Call::Rect(position, size, color));
+    }
+
+    fn triangle(&mut self, points: [Vec2; 3], color: Color) {
+        self.0.push(Call::Triangle(points, color));
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color) {
+        self.0.push(Call::Line(from, to, width, color));
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.0.push(Call::Circle(center, radius, color));
+    }
+}
+
+/// Everything the window draws of the driven run, into any renderer.
+fn overlays(sim: &Simulation, renderer: &mut impl Renderer) {
+    let start = sim.maze.start;
+    let ruler = Ruler {
+        enabled: true,
+        start: Some(start),
+        end: Some(start + vec2(3.0, 2.0) * sim.maze.cell_size),
+        cursor: Vec2::ZERO,
+    };
+    let settings = RenderSettings {
+        ray_colors: RayColors::Distance,
+        ..Default::default()
+    };
+    render::render(sim, renderer, &settings, false, None, &[]);
+    ruler.render(renderer);
+    teleport::render_handle(sim, renderer);
+}
+
+/// The image is made from the calls alone, so the window, which gets the same calls, shows the same.
+#[test]
+fn replaying_the_calls_draws_the_same_image() {
+    let (sim, _) = driven();
+    let mut recording = Recording::default();
+    overlays(&sim, &mut recording);
+    let direct = draw(&sim, (256, 256), |raster| overlays(&sim, raster));
+    let replayed = draw(&sim, (256, 256), |raster| recording.replay(raster));
+    assert_eq!(direct.rgb(), replayed.rgb());
+}
+
+/// The floor is drawn once and, with a bridge, again faintly under the upper layer.
+#[test]
+fn every_wall_is_drawn_as_a_rect() {
+    for (maze, copies) in [
+        (include_str!("../test_data/example.maze"), 1),
+        (include_str!("../test_data/bridge.maze"), 2),
+    ] {
+        let sim = simulation(maze);
+        let mut recording = Recording::default();
+        render::render(
+            &sim,
+            &mut recording,
+            &RenderSettings::default(),
+            false,
+            None,
+            &[],
+        );
+        let wall_color = Color::from_hex(sim.maze.wall_material.color);
+        let walls = |alpha: f32| {
+            let color = wall_color.with_alpha(alpha);
+            recording
+                .0
+                .iter()
+                .filter(|call| matches!(call, Call::Rect(_, _, c) if *c == color))
+                .count()
+        };
+        let upper = sim.maze.upper.as_ref().map_or(0, |upper| upper.walls.len());
+        assert_eq!(walls(1.0), sim.maze.walls.len() + upper);
+        assert_eq!(walls(0.2), sim.maze.walls.len() * (copies - 1));
+    }
+}
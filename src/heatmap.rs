@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use mazeparser::grid::{Cell, Grid, EAST, NORTH, SOUTH, WEST};
+use mimosi_core::internal::maze::Maze;
+
+const CELL: usize = 20;
+
+/// The walls of the grid as SVG lines, with the given size of a cell in pixels.
+pub fn walls_svg(grid: &Grid, cell: usize) -> String {
+    let mut svg = String::new();
+    for (x, y) in grid.cells() {
+        let (left, top) = (x * cell, y * cell);
+        let (right, bottom) = (left + cell, top + cell);
+        for (direction, (x1, y1, x2, y2)) in [
+            (NORTH, (left, top, right, top)),
+            (EAST, (right, top, right, bottom)),
+            (SOUTH, (left, bottom, right, bottom)),
+            (WEST, (left, top, left, bottom)),
+        ] {
+            if grid.has_wall((x, y), direction) {
+                svg.push_str(&format!(
+                    r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="black" stroke-width="2"/>
+"#
+                ));
+            }
+        }
+    }
+    svg
+}
+
+/// Renders how often each cell was visited as an SVG image.
+pub fn heatmap_svg(maze: &Maze, visits: &HashMap<Cell, usize>, coverage: f32) -> String {
+    let grid = &maze.grid;
+    let max = visits.values().copied().max().unwrap_or(1).max(1);
+    let (width, height) = (grid.width * CELL, grid.height * CELL);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{}" viewBox="0 0 {width} {}">
+<rect width="100%" height="100%" fill="white"/>
+"#,
+        height + CELL,
+        height + CELL
+    );
+
+    for (&(x, y), &count) in visits {
+        // Blend from blue (visited once) to red (visited most)
+        let t = if max > 1 {
+            (count - 1) as f32 / (max - 1) as f32
+        } else {
+            0.0
+        };
+        let (r, b) = ((t * 255.0) as u8, ((1.0 - t) * 255.0) as u8);
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="rgb({r},0,{b})" fill-opacity="0.6"><title>{count}</title></rect>
+"#,
+            x * CELL,
+            y * CELL
+        ));
+    }
+
+    svg.push_str(&walls_svg(grid, CELL));
+
+    svg.push_str(&format!(
+        r#"<text x="4" y="{}" font-family="sans-serif" font-size="14">Coverage: {:.1}%</text>
+</svg>
+"#,
+        height + CELL - 5,
+        coverage * 100.0
+    ));
+    svg
+}
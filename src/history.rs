@@ -0,0 +1,121 @@
+use std::{
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mimosi_core::internal::error::{from_toml, read, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bench::Outcome,
+    report::{OutputFormat, Report, Value},
+};
+
+/// A single headless run, kept to compare experiments over time.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub timestamp: u64, // Seconds since the unix epoch
+    #[serde(default)]
+    pub tag: Option<String>,
+    pub maze: String,
+    pub seed: u64,
+    #[serde(default)]
+    pub finish_time: Option<f32>,
+    pub crashed: bool,
+    #[serde(default)]
+    pub wall_touches: usize,
+    pub coverage: f32,
+    #[serde(default)]
+    pub left_start: Option<f32>, // Run time the mouse first left the start cell
+    #[serde(default)]
+    pub left_start_tick: Option<u64>,
+    #[serde(default)]
+    pub entered_goal: Option<f32>, // Run time the mouse first entered the goal area
+    #[serde(default)]
+    pub entered_goal_tick: Option<u64>,
+}
+
+impl Entry {
+    pub fn new(tag: Option<String>, maze: String, seed: u64, outcome: &Outcome) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            tag,
+            maze,
+            seed,
+            finish_time: outcome.finish_time,
+            crashed: outcome.crashed,
+            wall_touches: outcome.wall_touches,
+            coverage: outcome.coverage,
+            left_start: outcome.left_start.map(|(_, time)| time),
+            left_start_tick: outcome.left_start.map(|(tick, _)| tick),
+            entered_goal: outcome.entered_goal.map(|(_, time)| time),
+            entered_goal_tick: outcome.entered_goal.map(|(tick, _)| tick),
+        }
+    }
+}
+
+/// The history file is a TOML array of tables, so new runs can simply be appended.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct History {
+    #[serde(default)]
+    runs: Vec<Entry>,
+}
+
+pub fn append(path: &Path, entries: Vec<Entry>) -> Result<(), Error> {
+    let history = toml::to_string(&History { runs: entries })
+        .map_err(|e| Error::format(path.display(), e))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| Error::io(path, e))?;
+    writeln!(file, "{history}").map_err(|e| Error::io(path, e))
+}
+
+pub fn load(path: &Path) -> Result<Vec<Entry>, Error> {
+    let history: History = from_toml(&path.display().to_string(), &read(path)?)?;
+    Ok(history.runs)
+}
+
+/// Prints the runs in the history, optionally only those of one experiment.
+pub fn print_history(path: &Path, tag: Option<&str>, output: OutputFormat) -> Result<(), Error> {
+    let entries = load(path)?;
+    let entries: Vec<&Entry> = entries
+        .iter()
+        .filter(|e| tag.is_none() || e.tag.as_deref() == tag)
+        .collect();
+
+    let mut report = Report::new(&[
+        "timestamp",
+        "tag",
+        "maze",
+        "seed",
+        "finish_time",
+        "left_start",
+        "entered_goal",
+        "crashed",
+        "wall_touches",
+        "coverage",
+    ]);
+    for e in &entries {
+        report.push(vec![
+            e.timestamp.into(),
+            e.tag.as_deref().into(),
+            e.maze.as_str().into(),
+            e.seed.into(),
+            e.finish_time
+                .map_or(Value::Missing, |t| Value::number(t, 2)),
+            e.left_start.map_or(Value::Missing, |t| Value::number(t, 3)),
+            e.entered_goal
+                .map_or(Value::Missing, |t| Value::number(t, 3)),
+            e.crashed.into(),
+            e.wall_touches.into(),
+            Value::number(e.coverage * 100.0, 1),
+        ]);
+    }
+    report.footer(format!("{} runs", entries.len()));
+    report.print(output)
+}
@@ -0,0 +1,218 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use mimosi_core::internal::{
+    error::{self, Error},
+    maze::Maze,
+    replay::{Frame, ReplayFile},
+};
+
+use crate::{
+    heatmap::walls_svg,
+    report::{Report, Value},
+};
+
+/// Pixels per cell of the trajectory images.
+const CELL: usize = 20;
+/// Size of the speed charts in pixels.
+const CHART_WIDTH: f32 = 400.0;
+const CHART_HEIGHT: f32 = 80.0;
+/// Points drawn per trajectory and chart, more don't make a visible difference.
+const MAX_POINTS: usize = 500;
+
+/// A recorded run shown with its trajectory and speed in the HTML report.
+pub struct RunView<'a> {
+    pub title: String,
+    pub maze: &'a Maze,
+    pub frames: &'a [Frame],
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn every(frames: &[Frame]) -> usize {
+    (frames.len() / MAX_POINTS).max(1)
+}
+
+/// The maze with the path the mouse drove, green where it started and red where it stopped.
+fn trajectory_svg(maze: &Maze, frames: &[Frame]) -> String {
+    let grid = &maze.grid;
+    let (width, height) = (grid.width * CELL, grid.height * CELL);
+    let to_pixels = CELL as f32 / maze.cell_size;
+    let points: Vec<String> = frames
+        .iter()
+        .step_by(every(frames))
+        .chain(frames.last())
+        .map(|f| {
+            let p = f.position * to_pixels;
+            format!("{:.1},{:.1}", p.x, p.y)
+        })
+        .collect();
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="-2 -2 {} {}">
+<rect x="-2" y="-2" width="100%" height="100%" fill="white"/>
+"#,
+        width + 4,
+        height + 4,
+        width + 4,
+        height + 4
+    );
+    svg.push_str(&walls_svg(grid, CELL));
+    svg.push_str(&format!(
+        r#"<polyline points="{}" fill="none" stroke="blue" stroke-width="1.5"/>
+"#,
+        points.join(" ")
+    ));
+    for (frame, color) in [(frames.first(), "green"), (frames.last(), "red")] {
+        if let Some(frame) = frame {
+            let p = frame.position * to_pixels;
+            svg.push_str(&format!(
+                r#"<circle cx="{:.1}" cy="{:.1}" r="4" fill="{color}"/>
+"#,
+                p.x, p.y
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The speed over time as a line chart.
+fn speed_svg(maze: &Maze, frames: &[Frame]) -> String {
+    let meters = maze.meters_per_unit();
+    let speeds: Vec<(f32, f32)> = frames
+        .windows(2)
+        .filter(|pair| pair[1].time > pair[0].time)
+        .map(|pair| {
+            let distance = pair[0].position.distance(pair[1].position) * meters;
+            (pair[1].time, distance / (pair[1].time - pair[0].time))
+        })
+        .collect();
+    let start = frames.first().map_or(0.0, |f| f.time);
+    let duration = frames
+        .last()
+        .map_or(0.0, |f| f.time - start)
+        .max(f32::EPSILON);
+    let top_speed = speeds.iter().map(|s| s.1).fold(f32::EPSILON, f32::max);
+    let points: Vec<String> = speeds
+        .iter()
+        .step_by(every(frames))
+        .map(|(time, speed)| {
+            format!(
+                "{:.1},{:.1}",
+                (time - start) / duration * CHART_WIDTH,
+                CHART_HEIGHT - speed / top_speed * (CHART_HEIGHT - 2.0)
+            )
+        })
+        .collect();
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{CHART_WIDTH}" height="{}" viewBox="0 0 {CHART_WIDTH} {}">
+<rect width="{CHART_WIDTH}" height="{CHART_HEIGHT}" fill="white" stroke="gray"/>
+<polyline points="{}" fill="none" stroke="green" stroke-width="1"/>
+<text x="2" y="{}" font-family="sans-serif" font-size="11">Top speed {top_speed:.2} m/s, {duration:.2}s</text>
+</svg>
+"#,
+        CHART_HEIGHT + 16.0,
+        CHART_HEIGHT + 16.0,
+        points.join(" "),
+        CHART_HEIGHT + 13.0
+    )
+}
+
+/// Renders the results and the recorded runs as a single HTML page without external resources.
+pub fn render(title: &str, results: &Report, runs: &[RunView]) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: right; }}
+th {{ background: #eee; }}
+.runs {{ display: flex; flex-wrap: wrap; gap: 2em; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+"#,
+        title = escape(title)
+    );
+    html.push_str(&results.to_html());
+    if !runs.is_empty() {
+        html.push_str("<h2>Runs</h2>\n<div class=\"runs\">\n");
+        for run in runs {
+            let _ = write!(
+                html,
+                "<figure>\n<figcaption>{}</figcaption>\n{}{}</figure>\n",
+                escape(&run.title),
+                trajectory_svg(run.maze, run.frames),
+                speed_svg(run.maze, run.frames)
+            );
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn save(path: &Path, title: &str, results: &Report, runs: &[RunView]) -> Result<(), Error> {
+    error::write(path, render(title, results, runs))
+}
+
+/// Writes a report of saved replays, with a row and the trajectory for each.
+pub fn save_replays(path: &Path, replays: &[(PathBuf, ReplayFile)]) -> Result<(), Error> {
+    let mazes = replays
+        .iter()
+        .map(|(path, replay)| {
+            Maze::parse(&replay.maze)
+                .map_err(|e| Error::maze(&format!("maze in {}", path.display()), e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = Report::new(&[
+        "replay",
+        "tag",
+        "duration",
+        "finished",
+        "crashed",
+        "path_length",
+    ]);
+    let mut runs = Vec::new();
+    for ((file, replay), maze) in replays.iter().zip(&mazes) {
+        let frames = &replay.frames;
+        let meters = maze.meters_per_unit();
+        let path_length = frames
+            .windows(2)
+            .map(|pair| pair[0].position.distance(pair[1].position))
+            .fold(0.0, |sum, d| sum + d)
+            * meters;
+        let title = file.display().to_string();
+        results.push(vec![
+            title.as_str().into(),
+            replay.tag.as_deref().into(),
+            Value::number(frames.last().map_or(0.0, |f| f.time), 2),
+            frames.iter().any(|f| f.finished).into(),
+            frames.iter().any(|f| f.collided).into(),
+            Value::number(path_length, 2),
+        ]);
+        runs.push(RunView {
+            title,
+            maze,
+            frames,
+        });
+    }
+    results.footer("Times are in seconds and path lengths in meters.");
+    save(path, "Replay Report", &results, &runs)
+}
@@ -0,0 +1,147 @@
+use notan::egui::{self, Context, Grid};
+use rhai::{Map, Scope};
+
+use mimosi_core::internal::simulation::Simulation;
+
+/// Longest value of a script variable that is shown, longer ones are cut off.
+const MAX_VALUE_LENGTH: usize = 60;
+
+fn row(ui: &mut egui::Ui, label: &str, value: impl std::fmt::Display) {
+    ui.label(label);
+    ui.monospace(value.to_string());
+    ui.end_row();
+}
+
+/// Shows what the controller sees and does, updated every frame.
+pub fn show(ctx: &Context, sim: &Simulation, scope: &Scope) {
+    let mouse = &sim.mouse;
+    egui::Window::new("Telemetry")
+        .default_width(280.0)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.strong("Pose");
+                Grid::new("pose").num_columns(2).show(ui, |ui| {
+                    row(ui, "Time", format!("{:.3} s", sim.time));
+                    row(
+                        ui,
+                        "Position",
+                        format!("{:.1}, {:.1}", mouse.position.x, mouse.position.y),
+                    );
+                    row(
+                        ui,
+                        "Orientation",
+                        format!("{:.1}°", mouse.orientation.to_degrees()),
+                    );
+                    let (x, y) = sim.maze.cell_at(mouse.position);
+                    row(ui, "Cell", format!("{x}, {y}"));
+                    row(ui, "Layer", mouse.layer);
+                });
+
+                ui.separator();
+                ui.strong("Run");
+                let run = sim.run_state();
+                Grid::new("run").num_columns(2).show(ui, |ui| {
+                    row(ui, "Phase", &run.phase);
+                    row(ui, "Run Time", format!("{:.3} s", run.run_time));
+                    if run.time_left >= 0.0 {
+                        row(ui, "Time Left", format!("{:.1} s", run.time_left));
+                    }
+                    row(ui, "Wall Touches", run.wall_touches);
+                    if run.runs_left >= 0 {
+                        row(ui, "Penalty", format!("{:.1} s", run.penalty));
+                        row(ui, "Runs", format!("{}, {} left", run.runs, run.runs_left));
+                        row(ui, "Maze Time Left", format!("{:.1} s", run.maze_time_left));
+                    }
+                    if run.best_score >= 0.0 {
+                        row(ui, "Best Score", format!("{:.2}", run.best_score));
+                    }
+                });
+
+                ui.separator();
+                ui.strong("Wheels");
+                Grid::new("wheels").num_columns(3).show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Left");
+                    ui.label("Right");
+                    ui.end_row();
+                    let mut wheel = |label: &str, left: String, right: String| {
+                        ui.label(label);
+                        ui.monospace(left);
+                        ui.monospace(right);
+                        ui.end_row();
+                    };
+                    wheel(
+                        "Power",
+                        format!("{:.2}", mouse.left_power),
+                        format!("{:.2}", mouse.right_power),
+                    );
+                    wheel(
+                        "Velocity",
+                        format!("{:.1}", mouse.left_velocity),
+                        format!("{:.1}", mouse.right_velocity),
+                    );
+                    wheel(
+                        "Encoder",
+                        mouse.left_encoder.to_string(),
+                        mouse.right_encoder.to_string(),
+                    );
+                    wheel(
+                        "Brake",
+                        mouse.left_brake.to_string(),
+                        mouse.right_brake.to_string(),
+                    );
+                });
+
+                // What the script decided in its last call, the powers above are what it set
+                if let Some(time) = sim.last_control {
+                    ui.separator();
+                    ui.strong(format!("Last Call at {time:.3} s"));
+                    let mut logged = sim
+                        .console
+                        .lines
+                        .iter()
+                        .filter(|line| line.time == time)
+                        .peekable();
+                    if logged.peek().is_none() {
+                        ui.label("Nothing logged");
+                    }
+                    for line in logged {
+                        ui.monospace(&line.message);
+                    }
+                }
+
+                ui.separator();
+                ui.strong("Sensors");
+                Grid::new("sensors").num_columns(2).show(ui, |ui| {
+                    let mut sensors: Vec<_> = mouse.sensors.iter().collect();
+                    sensors.sort_by(|a, b| a.0.cmp(b.0));
+                    for (name, sensor) in sensors {
+                        row(ui, name, format!("{:.1}", sensor.value));
+                    }
+                    row(ui, "Gyro Z", format!("{:.2}", mouse.gyro_z));
+                    row(ui, "Accel X", format!("{:.2}", mouse.accel_x));
+                    row(ui, "Accel Y", format!("{:.2}", mouse.accel_y));
+                });
+
+                ui.separator();
+                ui.strong("Script State");
+                match scope.get_value::<Map>("state") {
+                    Some(state) if !state.is_empty() => {
+                        Grid::new("script_state").num_columns(2).show(ui, |ui| {
+                            for (name, value) in state {
+                                let mut value = value.to_string();
+                                if value.chars().count() > MAX_VALUE_LENGTH {
+                                    value = value.chars().take(MAX_VALUE_LENGTH).collect();
+                                    value.push('…');
+                                }
+                                row(ui, &name, value);
+                            }
+                        });
+                    }
+                    _ => {
+                        ui.label("The script keeps nothing in `state`");
+                    }
+                }
+            });
+        });
+}
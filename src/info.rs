@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+use mazeparser::{
+    grid::Grid,
+    path::{diagonal_path, estimate_time, path_length, straight_path},
+};
+use mimosi_core::internal::error::Error;
+
+use crate::{
+    corpus,
+    report::{OutputFormat, Report, Value},
+};
+
+/// Prints statistics and a small preview of a maze.
+///
+/// Path lengths are in cells and times in seconds. The preview is only shown in tables.
+pub fn print_info(
+    maze: &str,
+    speed: f32,
+    acceleration: f32,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let maze = mazeparser::Maze::from_str(maze).map_err(|e| Error::maze("maze", e))?;
+    let grid = Grid::from_maze(&maze);
+    let start = maze.start_cell();
+    let goals = maze.finish_cells();
+
+    let edges: u32 = grid.cells().map(|cell| grid.walls(cell).count_ones()).sum();
+
+    let mut values: Vec<(&str, Value)> = vec![
+        ("name", maze.name.clone().into()),
+        ("author", maze.author.clone().into()),
+        ("width", grid.width.into()),
+        ("height", grid.height.into()),
+        ("cells", (grid.width * grid.height).into()),
+        ("walls", maze.walls.len().into()),
+        ("cell_sides", (edges as usize).into()),
+        ("dead_ends", grid.dead_ends().into()),
+        ("layers", maze.layers().into()),
+        ("ramps", maze.ramps.len().into()),
+        ("canonical_hash", corpus::canonical_hash(&maze).into()),
+    ];
+    let path = grid.shortest_path(start, &goals);
+    values.push(("reachable", path.is_some().into()));
+    match path {
+        Some(path) => {
+            let straight = straight_path(&path);
+            let diagonal = diagonal_path(&path);
+            values.extend([
+                ("shortest_path_steps", (path.len() - 1).into()),
+                ("straight_length", Value::number(path_length(&straight), 2)),
+                (
+                    "straight_time",
+                    Value::number(estimate_time(&straight, speed, acceleration), 1),
+                ),
+                ("diagonal_length", Value::number(path_length(&diagonal), 2)),
+                (
+                    "diagonal_segments",
+                    diagonal.iter().filter(|s| s.is_diagonal()).count().into(),
+                ),
+                (
+                    "diagonal_time",
+                    Value::number(estimate_time(&diagonal, speed, acceleration), 1),
+                ),
+            ]);
+        }
+        None => values.extend(
+            [
+                "shortest_path_steps",
+                "straight_length",
+                "straight_time",
+                "diagonal_length",
+                "diagonal_segments",
+                "diagonal_time",
+            ]
+            .map(|column| (column, Value::Missing)),
+        ),
+    }
+
+    let mut report = Report::record(values);
+    report.footer("");
+    if maze.layers() > 1 {
+        report.footer("Paths and preview only cover the floor, the upper layer is not shown");
+    }
+    report.footer(grid.to_ascii(start, &goals).trim_end());
+    report.print(output)
+}
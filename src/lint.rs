@@ -0,0 +1,170 @@
+use std::fmt::Display;
+
+use mazeparser::{
+    grid::{Grid, EAST, NORTH, SOUTH, WEST},
+    Maze, Orientation, ParseError, Wall,
+};
+use notan::math::Vec2;
+
+/// A mistake in a maze that the parser accepts, with the line it is in if it has one.
+pub struct Problem {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// If the point is on the wall, including its ends.
+fn on_wall(point: Vec2, wall: &Wall) -> bool {
+    let (min, max) = (wall.start.min(wall.end), wall.start.max(wall.end));
+    match wall.orientation {
+        Orientation::Horizontal => point.y == min.y && (min.x..=max.x).contains(&point.x),
+        Orientation::Vertical => point.x == min.x && (min.y..=max.y).contains(&point.y),
+    }
+}
+
+/// The size given by the top and left walls of the outer boundary, if the maze has them.
+fn boundary_size(maze: &Maze) -> Option<Vec2> {
+    let floor = || maze.walls.iter().filter(|wall| wall.layer == 0);
+    let width = floor()
+        .filter(|wall| matches!(wall.orientation, Orientation::Horizontal) && wall.start.y == 0.0)
+        .map(|wall| wall.start.x.max(wall.end.x))
+        .reduce(f32::max)?;
+    let height = floor()
+        .filter(|wall| matches!(wall.orientation, Orientation::Vertical) && wall.start.x == 0.0)
+        .map(|wall| wall.start.y.max(wall.end.y))
+        .reduce(f32::max)?;
+    Some(Vec2::new(width, height))
+}
+
+/// Looks for problems in a maze in the text format, pointing at the lines that cause them.
+///
+/// Strict mode also rejects numbers with a decimal comma, which the parser otherwise accepts.
+pub fn check_maze(text: &str, strict: bool) -> Result<Vec<Problem>, ParseError> {
+    let (maze, lines) = if strict {
+        Maze::parse_strict(text)?
+    } else {
+        Maze::parse_with_lines(text)?
+    };
+    let grid = Grid::from_maze(&maze);
+    if grid.width == 0 || grid.height == 0 {
+        return Ok(vec![Problem {
+            line: None,
+            message: String::from("The maze has no cells"),
+        }]);
+    }
+    let mut problems = Vec::new();
+    let mut report =
+        |line: Option<usize>, message: String| problems.push(Problem { line, message });
+
+    if let Some((width, height)) = maze.declared_size {
+        if (width, height) != (grid.width, grid.height) {
+            report(
+                lines.size,
+                format!(
+                    "SIZE says {width}x{height} cells, but the walls make it {}x{}",
+                    grid.width, grid.height
+                ),
+            );
+        }
+    }
+
+    let boundary = boundary_size(&maze);
+    for (wall, &line) in maze.walls.iter().zip(&lines.walls) {
+        let (start, end) = (wall.start, wall.end);
+        let on_grid = |p: Vec2| p.min_element() >= 0.0 && p.fract() == Vec2::ZERO;
+        if !on_grid(start) || !on_grid(end) {
+            report(
+                Some(line),
+                format!("The wall from {start} to {end} is not on the lines between the cells"),
+            );
+        } else if boundary.is_some_and(|size| start.max(end).cmpgt(size).any()) {
+            report(
+                Some(line),
+                format!("The wall from {start} to {end} reaches past the outer boundary"),
+            );
+        }
+    }
+
+    let start = maze.start_cell();
+    if !grid.contains(start) {
+        report(
+            lines.start,
+            format!(
+                "The start ({}, {}) is outside of the maze, which is {}x{} cells",
+                start.0, start.1, grid.width, grid.height
+            ),
+        );
+    } else if grid.walls(start) == NORTH | EAST | SOUTH | WEST {
+        report(lines.start, String::from("The start cell is walled in"));
+    }
+    let floor = maze.walls.iter().zip(&lines.walls);
+    for (_, &line) in floor.filter(|(wall, _)| wall.layer == 0 && on_wall(maze.start, wall)) {
+        report(
+            lines.start,
+            format!("The start is inside the wall in line {line}"),
+        );
+    }
+
+    if lines.finish.is_none() {
+        report(None, String::from("The maze has no finish (FI)"));
+    } else if maze.finish_cells().is_empty() {
+        report(
+            lines.finish,
+            String::from("The finish doesn't cover any cell"),
+        );
+    } else if grid.contains(start) {
+        // The maze of the simulation also knows how to cross bridges
+        let reachable =
+            mimosi_core::internal::maze::Maze::from_string(text, 1.0)?.is_finish_reachable();
+        if !reachable {
+            report(
+                lines.finish,
+                String::from("The finish can't be reached from the start"),
+            );
+        }
+    }
+
+    let sides = [
+        (
+            "north",
+            NORTH,
+            (0..grid.width).map(|x| (x, 0)).collect::<Vec<_>>(),
+        ),
+        (
+            "south",
+            SOUTH,
+            (0..grid.width).map(|x| (x, grid.height - 1)).collect(),
+        ),
+        ("west", WEST, (0..grid.height).map(|y| (0, y)).collect()),
+        (
+            "east",
+            EAST,
+            (0..grid.height).map(|y| (grid.width - 1, y)).collect(),
+        ),
+    ];
+    for (name, direction, cells) in sides {
+        let gaps: Vec<String> = cells
+            .into_iter()
+            .filter(|&cell| !grid.has_wall(cell, direction))
+            .map(|(x, y)| format!("({x}, {y})"))
+            .collect();
+        if !gaps.is_empty() {
+            report(
+                None,
+                format!(
+                    "The outer boundary is open on the {name} side of {}",
+                    gaps.join(", ")
+                ),
+            );
+        }
+    }
+    Ok(problems)
+}
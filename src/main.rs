@@ -1,44 +1,192 @@
 use clap::Parser;
 use egui::{ScrollArea, Ui};
-use maze::Maze;
-use mouse::{Micromouse, MouseConfig};
+use mimosi_core::internal::{
+    analysis,
+    error::{from_toml, read, write, Error},
+    external::ExternalController,
+    manual::{manual_powers, CommandTrack},
+    maze::Maze,
+    mouse::{Micromouse, MouseConfig},
+    replay::{EventFilter, Player, ReplayFile},
+    scenario::Scenario,
+    shared_memory::SharedMemoryController,
+    simulation::{Simulation, Snapshot, Timings, MAX_STEPS_PER_FRAME},
+    validate,
+    wasm::WasmController,
+};
 
 use notan::draw::*;
 use notan::egui::{self, *};
 use notan::prelude::*;
 
-use std::{fmt::Display, path::PathBuf};
+use std::{fmt::Display, path::PathBuf, time::Duration};
 
-use args::{Args, Command};
+use args::{
+    AbArgs, Args, BatchArgs, BenchArgs, Command, Heading, MazesCommand, SimulateArgs, StartArgs,
+    TournamentArgs,
+};
+use notan::math::vec2;
+use perf::PerfOverlay;
+use race::{Ghost, Rival};
+use render::{RayColors, RenderSettings};
+use report::OutputFormat;
+use results::RunResult;
 use rhai::{Dynamic, Scope};
-use simulation::Simulation;
+use ruler::Ruler;
+use script::ScriptFile;
+use stream::TelemetryStream;
 use stringlit::s;
+use summary::{RunOutcome, Summary};
+use telemetry::{Telemetry, TelemetryFormat};
+use teleport::Drag;
+use tuning::{tuning_panel, Params};
+use tutorial::Tutorial;
+use view::{layer_offset, RobotView, View};
 
+mod ab;
 mod args;
-mod engine;
-mod helper;
-mod maze;
-mod mouse;
-mod ray;
-mod simulation;
+mod batch;
+mod bench;
+mod console;
+mod convert;
+mod corpus;
+mod editor;
+mod export;
+#[cfg(test)]
+mod golden;
+mod heatmap;
+mod history;
+mod html;
+mod hud;
+mod info;
+mod lint;
+mod pacer;
+mod patch;
+mod perf;
+mod png;
+mod race;
+mod raster;
+mod render;
+mod renderer;
+mod report;
+mod results;
+mod ruler;
+mod script;
+mod stream;
+mod summary;
+mod telemetry;
+mod teleport;
+mod template;
+mod tournament;
+mod tuning;
+mod tutorial;
+mod view;
 
 const DEFAULT_MAZE: &str = include_str!("../test_data/example.maze");
 const DEFAULT_MOUSE: &str = include_str!("../test_data/mouse.toml");
 const DEFAULT_SCRIPT: &str = include_str!("../test_data/test.rhai");
 
+/// Reads the scenario, with the seed given on the command line instead of its own.
+fn read_scenario(scenario: Option<PathBuf>, seed: Option<u64>) -> Result<Scenario, Error> {
+    let mut scenario: Scenario = match scenario {
+        Some(path) => from_toml(&path.display().to_string(), &read(&path)?)?,
+        None => Scenario::default(),
+    };
+    if let Some(seed) = seed {
+        scenario.seed = seed;
+    }
+    Ok(scenario)
+}
+
+fn read_track(path: PathBuf) -> Result<CommandTrack, Error> {
+    from_toml(&path.display().to_string(), &read(&path)?)
+}
+
+/// Reads a maze file, converting classic binary `.maz` mazes to the text format.
+fn read_maze(path: PathBuf) -> Result<String, Error> {
+    let is_maz = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("maz"));
+    if !is_maz {
+        return read(&path);
+    }
+    let bytes = std::fs::read(&path).map_err(|source| Error::io(&path, source))?;
+    mazeparser::Maze::from_maz(&bytes)
+        .map(|maze| maze.to_string())
+        .map_err(|e| Error::format(path.display(), e))
+}
+
+/// Moves the start of the maze to the cell and heading given on the command line.
+///
+/// The maze is rewritten, so everything that uses it, like the reachability check and saved replays, sees the new start.
+fn apply_start(name: &str, maze: String, start: &StartArgs) -> Result<String, Error> {
+    if start.start_cell.is_none() && start.start_heading.is_none() {
+        return Ok(maze);
+    }
+    let mut parsed: mazeparser::Maze = maze.parse().map_err(|e| Error::maze(name, e))?;
+    if let Some((x, y)) = start.start_cell {
+        let (width, height) = parsed.size();
+        if x >= width || y >= height {
+            return Err(Error::Invalid(format!(
+                "The start cell ({x}, {y}) is outside of {name}, which is {width}x{height} cells"
+            )));
+        }
+        parsed.start = vec2(x as f32 + 0.5, y as f32 + 0.5);
+    }
+    if let Some(heading) = start.start_heading {
+        parsed.start_direction = match heading {
+            Heading::North => mazeparser::StartDirection::Up,
+            Heading::East => mazeparser::StartDirection::Right,
+            Heading::South => mazeparser::StartDirection::Down,
+            Heading::West => mazeparser::StartDirection::Left,
+        };
+    }
+    Ok(parsed.to_string())
+}
+
+/// The name of a file for messages, or what is used instead of it.
+fn name(file: &Option<PathBuf>, default: &str) -> String {
+    file.as_ref()
+        .map_or(String::from(default), |f| f.display().to_string())
+}
+
+/// Reads the maze files, expanding directories into the mazes they contain.
+fn read_maze_files(paths: &[PathBuf]) -> Result<Vec<(PathBuf, String)>, Error> {
+    batch::maze_files(paths)?
+        .into_iter()
+        .map(|path| read_maze(path.clone()).map(|maze| (path, maze)))
+        .collect()
+}
+
+/// Limits the threads that run in parallel, by default there is one per core.
+fn thread_pool(jobs: Option<usize>) -> Result<(), Error> {
+    if let Some(jobs) = jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .map_err(|e| Error::Invalid(format!("Could not start {jobs} jobs: {e}")))?;
+    }
+    Ok(())
+}
+
+/// Reads a script, with the file name in the error.
+fn read_script(path: PathBuf) -> Result<(PathBuf, String), Error> {
+    read(&path).map(|script| (path, script))
+}
+
 fn read_with_defaults(
     maze: Option<PathBuf>,
     mouse: Option<PathBuf>,
     script: Option<PathBuf>,
-) -> anyhow::Result<(String, String, String)> {
+) -> Result<(String, String, String), Error> {
     Ok((
-        maze.map(std::fs::read_to_string)
+        maze.map(read_maze)
             .unwrap_or_else(|| Ok(s!(DEFAULT_MAZE)))?,
         mouse
-            .map(std::fs::read_to_string)
+            .map(|path| read(&path))
             .unwrap_or_else(|| Ok(s!(DEFAULT_MOUSE)))?,
         script
-            .map(std::fs::read_to_string)
+            .map(|path| read(&path))
             .unwrap_or_else(|| Ok(s!(DEFAULT_SCRIPT)))?,
     ))
 }
@@ -50,21 +198,407 @@ fn value<D: Display>(ui: &mut Ui, text: &str, value: D) {
     });
 }
 
-fn draw(_app: &mut App, gfx: &mut Graphics, plugins: &mut Plugins, state: &mut State) {
+fn replay_panel(ui: &mut Ui, state: &mut State) {
+    if !state.paused {
+        ui.label("Pause to review the run");
+        return;
+    }
+    if !state.replay_only {
+        ui.checkbox(&mut state.reviewing, "Review Run");
+    }
+    if !state.reviewing {
+        return;
+    }
+
+    let recorder = &state.sim.recorder;
+    let player = &mut state.player;
+    let mut time = player.time;
+    ui.add(egui::Slider::new(&mut time, 0.0..=recorder.duration()).text("Time"));
+    if time != player.time {
+        player.seek(time, recorder);
+    }
+    ui.add(
+        egui::Slider::new(&mut player.speed, -10.0..=10.0)
+            .text("Speed")
+            .logarithmic(true),
+    );
+    ui.horizontal(|ui| {
+        if ui.button("|<").clicked() {
+            player.step(-1, recorder);
+        }
+        let label = if player.playing { "Pause" } else { "Play" };
+        if ui.button(label).clicked() {
+            player.playing = !player.playing;
+        }
+        if ui.button(">|").clicked() {
+            player.step(1, recorder);
+        }
+    });
+    for (label, filter) in [
+        ("Event", EventFilter::Any),
+        ("Cell", EventFilter::CellChange),
+        ("Crash", EventFilter::Crash),
+        ("Finish", EventFilter::Finish),
+        ("Marker", EventFilter::Marker),
+    ] {
+        ui.horizontal(|ui| {
+            if ui.button("<").clicked() {
+                player.previous_event(&state.sim.events, filter, recorder);
+            }
+            ui.label(label);
+            if ui.button(">").clicked() {
+                player.next_event(&state.sim.events, filter, recorder);
+            }
+        });
+    }
+}
+
+/// A line of the race section, in the color the racer is drawn in.
+fn racer(ui: &mut Ui, index: usize, name: &str, standing: String) {
+    let color = render::rival_color(index);
+    ui.horizontal(|ui| {
+        ui.colored_label(
+            Color32::from_rgb(
+                (color.r * 255.0) as u8,
+                (color.g * 255.0) as u8,
+                (color.b * 255.0) as u8,
+            ),
+            format!("- {name}:"),
+        );
+        ui.label(standing);
+    });
+}
+
+fn draw(app: &mut App, gfx: &mut Graphics, plugins: &mut Plugins, state: &mut State) {
+    if state.robot_view && state.robot_texture.is_none() {
+        match RobotView::new(gfx) {
+            Ok(robot_view) => state.robot_texture = Some(robot_view),
+            Err(e) => {
+                eprintln!("Could not create the robot view: {e}");
+                state.robot_view = false;
+            }
+        }
+    }
+
     let mut draw = gfx.create_draw();
 
     // Render the simulation
-    state.sim.render(&mut draw);
+    let decimating = state
+        .render_settings
+        .is_decimating(state.time_scale, state.paused);
+    let replay = if state.reviewing {
+        state.sim.recorder.frame_at(state.player.time)
+    } else {
+        None
+    };
+    // The robot view takes the right half of the space next to the panel
+    let robot_width = if state.robot_view {
+        ((draw.width() - state.panel_width) / 2.0).clamp(0.0, draw.height())
+    } else {
+        0.0
+    };
+    state.view.fit(
+        draw.size(),
+        state.panel_width + robot_width,
+        &state.sim.maze,
+    );
+    let (followed, layer) = replay
+        .map_or((state.sim.mouse.position, state.sim.mouse.layer), |frame| {
+            (frame.position, frame.layer)
+        });
+    state
+        .view
+        .follow(followed + layer_offset(&state.sim.maze, layer));
+    draw.transform().push(state.view.matrix());
+    // Rivals first and then the replays, in the order of their colors
+    let time = if state.reviewing {
+        state.player.time
+    } else {
+        state.sim.time
+    };
+    let rivals = state.rivals.iter().map(|rival| {
+        if state.reviewing {
+            rival.sim.recorder.frame_at(time).cloned()
+        } else {
+            Some(rival.sim.frame())
+        }
+    });
+    let replays = state
+        .ghosts
+        .iter()
+        .map(|ghost| ghost.recorder.frame_at(time).cloned());
+    let ghosts: Vec<_> = rivals
+        .chain(replays)
+        .enumerate()
+        .filter_map(|(i, frame)| Some((frame?, render::rival_color(i))))
+        .collect();
+    render::render(
+        &state.sim,
+        &mut draw,
+        &state.render_settings,
+        decimating,
+        replay,
+        &ghosts,
+    );
+    state.ruler.render(&mut draw);
+    if !state.replay_only && state.paused && !state.ruler.enabled {
+        teleport::render_handle(&state.sim, &mut draw);
+    }
+    draw.transform().pop();
+
+    if let Some(robot_view) = state.robot_texture.as_ref().filter(|_| state.robot_view) {
+        let orientation = replay.map_or(state.sim.mouse.orientation, |frame| frame.orientation);
+        let matrix = RobotView::matrix(
+            followed + layer_offset(&state.sim.maze, layer),
+            orientation,
+            state.sim.maze.cell_size,
+        );
+        renderer::render_to_texture(gfx, &robot_view.texture, matrix, |robot_draw| {
+            render::render(
+                &state.sim,
+                robot_draw,
+                &state.render_settings,
+                decimating,
+                replay,
+                &ghosts,
+            );
+        });
+
+        let x = draw.width() - state.panel_width - robot_width;
+        draw.image(&robot_view.texture)
+            .position(x, 0.0)
+            .size(robot_width, robot_width);
+        draw.rect((x, 0.0), (robot_width, robot_width))
+            .stroke(2.0)
+            .color(Color::BLACK);
+    }
 
     gfx.render(&draw);
 
     let output = plugins.egui(|ctx| {
-        egui::SidePanel::new(egui::panel::Side::Right, "Control").show(ctx, |ui| {
-            ui.checkbox(&mut state.paused, "Pause (Space)");
+        ctx.set_zoom_factor(state.ui_scale);
+        let panel = egui::SidePanel::new(egui::panel::Side::Right, "Control").show(ctx, |ui| {
+            if let Some(tag) = &state.tag {
+                value(ui, "Experiment", tag);
+            }
+            if state.sim.is_calibrating() {
+                value(ui, "Phase", "Calibration");
+                if ui.button("Start Run").clicked() {
+                    state.sim.start_run();
+                }
+            }
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut state.paused, "Pause (Space)");
+                let can_step = state.paused && !state.replay_only;
+                if ui
+                    .add_enabled(can_step, egui::Button::new("Step (N)"))
+                    .clicked()
+                {
+                    state.step_once = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                let can_save = !state.replay_only;
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save State (F5)"))
+                    .clicked()
+                {
+                    save_snapshot(state);
+                }
+                let can_restore = can_save && state.snapshot.is_some();
+                if ui
+                    .add_enabled(can_restore, egui::Button::new("Restore (F9)"))
+                    .clicked()
+                {
+                    restore_snapshot(state);
+                }
+            });
+            if let Some(snapshot) = &state.snapshot {
+                value(ui, "Saved At", format!("{:.3} s", snapshot.time));
+            }
+            ui.add(egui::Slider::new(&mut state.time_scale, 0.1..=20.0).text("Time Scale"));
+            ui.checkbox(&mut state.ruler.enabled, "Ruler (R)");
+            ui.checkbox(&mut state.robot_view, "Robot View (V)");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut state.view.follow, "Follow Mouse (F)");
+                if ui.button("Fit Maze (H)").clicked() {
+                    state.view.reset();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut state.render_settings.trail, "Trail");
+                ui.checkbox(&mut state.render_settings.trail_by_speed, "By Speed");
+                if ui.button("Clear (T)").clicked() {
+                    state.sim.trail.clear();
+                }
+            });
+            ui.collapsing("Sensor Rays", |ui| {
+                ui.horizontal(|ui| {
+                    for (colors, label) in [
+                        (RayColors::Uniform, "Uniform"),
+                        (RayColors::Group, "By Group"),
+                        (RayColors::Distance, "By Distance"),
+                    ] {
+                        ui.radio_value(&mut state.render_settings.ray_colors, colors, label);
+                    }
+                });
+                let mut sensors: Vec<_> = state.sim.mouse.sensors.iter_mut().collect();
+                sensors.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, sensor) in sensors {
+                    let label = match &sensor.group {
+                        Some(group) => format!("{name} ({group})"),
+                        None => name.clone(),
+                    };
+                    let mut shown = !sensor.hide_ray;
+                    if ui.checkbox(&mut shown, label).changed() {
+                        sensor.hide_ray = !shown;
+                    }
+                }
+            });
+            ui.checkbox(&mut state.manual, "Drive Manually (M, Arrow Keys)");
+            if !state.recorded_track.commands.is_empty() {
+                value(
+                    ui,
+                    "- Recorded Commands",
+                    state.recorded_track.commands.len(),
+                );
+                if ui.button("Save Track").clicked() {
+                    let track = toml::to_string(&state.recorded_track).map_err(|e| e.to_string());
+                    if let Err(e) = track.and_then(|t| {
+                        std::fs::write(&state.track_output, t).map_err(|e| e.to_string())
+                    }) {
+                        eprintln!("Could not save track: {e}");
+                    }
+                }
+            }
+            if state.ruler.enabled {
+                let (x, y) = state.sim.maze.cell_at(state.ruler.cursor);
+                value(ui, "- Cell", format!("{x}, {y}"));
+                if let (Some(distance), Some(angle)) = (state.ruler.distance(), state.ruler.angle())
+                {
+                    let meters = distance * state.sim.maze.meters_per_unit();
+                    value(ui, "- Distance", format!("{distance:.1} ({meters:.3} m)"));
+                    value(ui, "- Angle", format!("{angle:.1}°"));
+                }
+            }
+            if !state.rivals.is_empty() || !state.ghosts.is_empty() {
+                ui.separator();
+                ui.collapsing("Race", |ui| {
+                    value(ui, "- Main", race::standing(&state.sim));
+                    for (i, rival) in state.rivals.iter().enumerate() {
+                        racer(ui, i, &rival.name, race::standing(&rival.sim));
+                        if let Some(error) = &rival.error {
+                            ui.colored_label(Color32::RED, error);
+                        }
+                    }
+                    for (i, ghost) in state.ghosts.iter().enumerate() {
+                        racer(ui, state.rivals.len() + i, &ghost.name, ghost.standing());
+                    }
+                });
+            }
+            ui.separator();
+            ui.collapsing("Replay", |ui| {
+                replay_panel(ui, state);
+                if !state.replay_only && ui.button("Save Replay").clicked() {
+                    let replay = ReplayFile {
+                        maze: state.maze_source.clone(),
+                        mouse: state.mouse_source.clone(),
+                        tag: state.tag.clone(),
+                        seed: state.sim.seed,
+                        events: state.sim.events.clone(),
+                        frames: state.sim.recorder.frames.clone(),
+                    };
+                    if let Err(e) = replay.save(&state.replay_output) {
+                        eprintln!("Could not save replay: {e}");
+                    }
+                }
+            });
             ui.separator();
             ui.heading("Debug");
             value(ui, "- FPS", format!("{:.0}", state.fps));
             value(ui, "- DT", state.delta_time);
+            let mut show_perf = state.perf.is_some();
+            if ui.checkbox(&mut show_perf, "Performance (F3)").changed() {
+                toggle_perf(state);
+            }
+            ui.checkbox(&mut state.show_console, "Script Console");
+            ui.checkbox(&mut state.show_hud, "Telemetry (F2)");
+            for channel in state.sim.channels.values() {
+                value(ui, &format!("- {}", channel.name), channel.value);
+            }
+            value(
+                ui,
+                "- Coverage",
+                format!("{:.1}%", state.sim.coverage() * 100.0),
+            );
+            if ui.button("Save Heatmap").clicked() {
+                let svg =
+                    heatmap::heatmap_svg(&state.sim.maze, &state.sim.visits, state.sim.coverage());
+                if let Err(e) = std::fs::write(&state.heatmap_path, svg) {
+                    eprintln!("Could not save heatmap: {e}");
+                }
+            }
+
+            ui.separator();
+            ui.collapsing("Tuning", |ui| {
+                let changed = tuning_panel(ui, &mut state.sim, &state.config_params);
+                if changed {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        "The physics differ from the config",
+                    );
+                }
+                if ui.button("Export Mouse Config").clicked() {
+                    if let Err(e) = export_mouse_config(state) {
+                        eprintln!("Could not export mouse config: {e}");
+                    }
+                }
+            });
+
+            if let Some(score) = &state.sim.score {
+                ui.separator();
+                ui.collapsing("Score", |ui| {
+                    let maze_time = score.maze_time().min(score.rules.maze_time);
+                    value(
+                        ui,
+                        "- Maze Time",
+                        format!("{maze_time:.2}s / {:.0}s", score.rules.maze_time),
+                    );
+                    if let Some(run) = score.current_run() {
+                        value(ui, "- Current Run", format!("{run:.2}s"));
+                    }
+                    for (i, run) in score.runs.iter().enumerate() {
+                        value(
+                            ui,
+                            &format!("- Run {}", i + 1),
+                            format!("{:.2}s, {} touches", run.run_time, run.touches),
+                        );
+                    }
+                    if let Some(best) = score.best() {
+                        value(ui, "- Score", format!("{:.2}", best.score));
+                    }
+                    if score.is_over() {
+                        ui.label("No more runs count");
+                    }
+                });
+            }
+
+            if let Some(kidnapper) = &state.sim.kidnapper {
+                ui.separator();
+                ui.collapsing("Kidnappings", |ui| {
+                    for kidnapping in &kidnapper.kidnappings {
+                        value(
+                            ui,
+                            &format!("- {:.2}s", kidnapping.time),
+                            match kidnapping.relocalized {
+                                Some(after) => format!("found after {after:.2}s"),
+                                None => String::from("lost"),
+                            },
+                        );
+                    }
+                    value(ui, "- Finished After", kidnapper.finished);
+                });
+            }
 
             ui.separator();
             ui.collapsing("Maze Config", |ui| {
@@ -75,6 +609,7 @@ fn draw(_app: &mut App, gfx: &mut Graphics, plugins: &mut Plugins, state: &mut S
             ui.collapsing("Mouse Config", |ui| {
                 ScrollArea::new([false, true]).show(ui, |ui| {
                     value(ui, "- Crashed", state.sim.collided);
+                    value(ui, "- Wall Touches", state.sim.wall_touches);
                     value(ui, "- Width", state.sim.mouse.width);
                     value(ui, "- Length", state.sim.mouse.length);
                     value(ui, "- Wheel Radius", state.sim.mouse.wheel_radius);
@@ -84,26 +619,99 @@ fn draw(_app: &mut App, gfx: &mut Graphics, plugins: &mut Plugins, state: &mut S
                     value(ui, "- Right Power", state.sim.mouse.right_power);
                     value(ui, "- Left Encoder", state.sim.mouse.left_encoder);
                     value(ui, "- Right Encoder", state.sim.mouse.right_encoder);
+                    value(ui, "- Temperature", state.sim.mouse.temperature);
+                    if let Some(motor) = &state.sim.mouse.motor {
+                        value(ui, "- Supply Voltage", motor.supply_voltage);
+                        value(
+                            ui,
+                            "- Motor Top Speed",
+                            motor.top_speed(state.sim.mouse.wheel_radius),
+                        );
+                    }
+                    if let Some(drift) = &state.sim.drift {
+                        value(ui, "- IR Scale", drift.ir_scale());
+                        value(ui, "- Gyro Bias", drift.gyro_bias());
+                    }
 
                     ui.label("Sensors:");
-                    ui.label(toml::to_string_pretty(&state.sim.mouse.sensors).unwrap());
+                    ui.label(
+                        toml::to_string_pretty(&state.sim.mouse.sensors)
+                            .unwrap_or_else(|e| e.to_string()),
+                    );
                 });
             });
         });
+        state.panel_width = panel.response.rect.width() * ctx.zoom_factor();
+        if let Some(perf) = &state.perf {
+            perf.show(ctx, state.fps, state.time_scale, state.paused);
+        }
+        if state.show_hud {
+            hud::show(ctx, &state.sim, &state.scope);
+        }
+        if let Some(error) = &state.script_error {
+            let mut open = true;
+            egui::Window::new("Script Error")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label(RichText::new(error).monospace().color(Color32::RED));
+                    ui.label("The simulation is paused, continuing runs the script again.");
+                    if state.script.is_watched() {
+                        ui.label("Saving the script loads it again.");
+                    }
+                });
+            if !open {
+                state.script_error = None;
+            }
+        }
+        if let Some(tutorial) = &mut state.tutorial {
+            tutorial.show(ctx, &state.sim);
+        }
+        if state.show_console && !state.sim.console.lines.is_empty() {
+            console::show(&mut state.sim.console, ctx);
+        }
+        if let Some(summary) = &state.summary {
+            if summary.show(ctx, &mut state.summary_open) {
+                save_summary(summary, &state.summary_output);
+            }
+        }
+        state.pointer_over_ui = ctx.wants_pointer_input();
         ctx.input(|i| {
             for f in &i.raw.dropped_files {
                 if let Some(bytes) = &f.bytes {
-                    let s = String::from_utf8_lossy(bytes).to_string();
-                    if let Ok(config) = toml::from_str::<MouseConfig>(&s) {
+                    let s = match mazeparser::Maze::from_maz(bytes) {
+                        Ok(maze) if f.name.to_lowercase().ends_with(".maz") => maze.to_string(),
+                        _ => String::from_utf8_lossy(bytes).to_string(),
+                    };
+                    if let Ok(replay) = toml::from_str::<ReplayFile>(&s) {
+                        state
+                            .ghosts
+                            .push(Ghost::new(f.name.clone(), replay, &state.maze_source));
+                    } else if let Ok(config) = toml::from_str::<MouseConfig>(&s) {
+                        state.mouse_source = s;
                         state.sim.mouse = Micromouse::new(
                             config,
                             state.sim.mouse.position,
                             state.sim.mouse.orientation,
                         );
+                        state.config_params = Params::from_sim(&state.sim);
                     } else if let Ok(ast) = state.sim.engine.compile(&s) {
                         state.sim.ast = ast;
-                    } else if let Ok(maze) = Maze::from_string(&s, 50.0) {
-                        state.sim.maze = maze;
+                        state.script = ScriptFile::dropped(f.name.clone(), s);
+                    } else if let Ok(maze) = Maze::parse(&s) {
+                        // Parsed again for each rival, since mazes can't be cloned
+                        for rival in &mut state.rivals {
+                            if let Ok(maze) = Maze::parse(&s) {
+                                replace_maze(&mut rival.sim, maze);
+                            }
+                        }
+                        replace_maze(&mut state.sim, maze);
+                        state.maze_source = s;
+                        // The saved poses belong to the old maze
+                        state.snapshot = None;
+                        state.rival_snapshots.clear();
+                        state.config_params = Params::from_sim(&state.sim);
+                        state.view.reset();
+                        app.window().set_title(&window_title(&state.sim.maze));
                     }
                 }
             }
@@ -118,33 +726,223 @@ fn update(app: &mut App, state: &mut State) {
     if state.tick % 100 == 0 {
         state.fps = app.timer.fps();
     }
+    if app.keyboard.was_pressed(KeyCode::M) {
+        state.manual = !state.manual;
+    }
+    state.manual_powers = manual_powers(
+        app.keyboard.is_down(KeyCode::Up),
+        app.keyboard.is_down(KeyCode::Down),
+        app.keyboard.is_down(KeyCode::Left),
+        app.keyboard.is_down(KeyCode::Right),
+    );
+    if app.keyboard.was_pressed(KeyCode::F3) {
+        toggle_perf(state);
+    }
+    if app.keyboard.was_pressed(KeyCode::F2) {
+        state.show_hud = !state.show_hud;
+    }
+    if app.keyboard.was_pressed(KeyCode::N) {
+        state.step_once = true;
+    }
+    if app.keyboard.was_pressed(KeyCode::F5) && !state.replay_only {
+        save_snapshot(state);
+    }
+    if app.keyboard.was_pressed(KeyCode::F9) && !state.replay_only {
+        restore_snapshot(state);
+    }
+    if app.keyboard.was_pressed(KeyCode::V) {
+        state.robot_view = !state.robot_view;
+    }
+    if app.keyboard.was_pressed(KeyCode::R) {
+        state.ruler.enabled = !state.ruler.enabled;
+    }
+    if app.keyboard.was_pressed(KeyCode::F) {
+        state.view.follow = !state.view.follow;
+    }
+    if app.keyboard.was_pressed(KeyCode::H) {
+        state.view.reset();
+    }
+    if app.keyboard.was_pressed(KeyCode::T) {
+        state.sim.trail.clear();
+    }
+    let (x, y) = app.mouse.position();
+
+    // Zoom with the wheel and pan by dragging with the right button
+    let cursor = vec2(x, y);
+    if !state.pointer_over_ui && app.mouse.wheel_delta.y != 0.0 {
+        state
+            .view
+            .zoom_at(cursor, 1.1f32.powf(app.mouse.wheel_delta.y.signum()));
+    }
+    if app.mouse.right_is_down() && !state.pointer_over_ui {
+        if let Some(last) = state.pan_from {
+            state.view.pan_by(cursor - last);
+        }
+        state.pan_from = Some(cursor);
+    } else {
+        state.pan_from = None;
+    }
+    state.ruler.cursor = state.view.to_world(vec2(x, y));
+    if state.ruler.enabled && app.mouse.left_was_pressed() && !state.pointer_over_ui {
+        state.ruler.click(state.ruler.cursor);
+    }
+
+    // Drag the mouse to a new pose while paused, on the layer it is on
+    let cursor = state.ruler.cursor - layer_offset(&state.sim.maze, state.sim.mouse.layer);
+    if !state.replay_only && state.paused && !state.ruler.enabled {
+        if app.mouse.left_was_pressed() && !state.pointer_over_ui {
+            state.drag = teleport::start_drag(&state.sim, cursor);
+        }
+        if let Some(drag) = state.drag {
+            teleport::drag(&mut state.sim, drag, cursor);
+        }
+    }
+    if (app.mouse.left_was_released() || !state.paused) && state.drag.take().is_some() {
+        let (position, orientation) = (state.sim.mouse.position, state.sim.mouse.orientation);
+        state.sim.teleport(position, orientation);
+    }
+
     if app.keyboard.is_down(KeyCode::Space) && state.pause_timer == 0 {
         state.pause_timer = 20;
-        state.paused = !state.paused;
+        if state.replay_only {
+            state.player.playing = !state.player.playing;
+        } else {
+            state.paused = !state.paused;
+        }
+    }
+    if state.replay_only {
+        state.paused = true;
+        state.reviewing = true;
     }
 
-    if !state.paused && !state.sim.collided {
-        let mut mouse_data = state
-            .sim
-            .mouse
-            .get_data(state.delta_time, state.sim.collided);
-        state.scope.push("mouse", mouse_data);
+    if state.paused && state.reviewing {
+        state.player.advance(state.delta_time, &state.sim.recorder);
+    } else {
+        state.reviewing = false;
+    }
 
-        state
-            .sim
-            .engine
-            .run_ast_with_scope(&mut state.scope, &state.sim.ast)
-            .unwrap();
+    // Run the physics with a fixed time step, so the results don't depend on the frame rate
+    let dt = state.physics_dt;
+    state.sim.timings = Timings::default();
+    let mut steps_done = 0;
+    if state.paused || state.sim.collided || state.sim.timed_out() {
+        state.accumulator = 0.0;
+    } else {
+        state.accumulator += state.delta_time * state.time_scale;
+    }
+    while state.accumulator >= dt && steps_done < MAX_STEPS_PER_FRAME {
+        step(state, dt);
+        state.accumulator -= dt;
+        steps_done += 1;
+        if state.sim.collided || state.paused {
+            break;
+        }
+        if state.pause_at.is_some_and(|time| state.sim.time >= time) {
+            state.pause_at = None;
+            state.paused = true;
+            state.accumulator = 0.0;
+            break;
+        }
+    }
+    if steps_done == MAX_STEPS_PER_FRAME {
+        state.accumulator = 0.0;
+    }
+    // A single step runs the next call of the script and the physics up to the one after it
+    if std::mem::take(&mut state.step_once)
+        && state.paused
+        && !state.replay_only
+        && !state.sim.collided
+    {
+        state.script_error = None;
+        loop {
+            step(state, dt);
+            steps_done += 1;
+            if state.sim.collided
+                || state.sim.control_due(dt)
+                || state.script_error.is_some()
+                || steps_done == MAX_STEPS_PER_FRAME
+            {
+                break;
+            }
+        }
+    }
+
+    // Load the script again when it was saved, checked a few times per second
+    if state.tick % 20 == 0 {
+        match state.script.reload(&state.sim.engine) {
+            Some(Ok(ast)) => {
+                println!("Reloaded {}", state.script.name);
+                state.sim.ast = ast;
+                state.script_error = None;
+            }
+            Some(Err(e)) => {
+                eprintln!("{e}");
+                state.script_error = Some(e);
+                state.paused = true;
+            }
+            None => (),
+        }
+    }
 
-        mouse_data = state.scope.get_value("mouse").unwrap();
-        state.sim.mouse.update_from_data(mouse_data);
+    if let Some(tutorial) = &mut state.tutorial {
+        if tutorial.update(&state.sim) && !state.replay_only {
+            state.paused = true;
+            state.accumulator = 0.0;
+        }
+    }
 
-        state.sim.update(state.delta_time);
+    // Sum up the run once it is over
+    match RunOutcome::of(&state.sim) {
+        Some(outcome) if state.summary.is_none() && !state.replay_only => {
+            state.summary = Some(Summary::new(&state.sim, outcome));
+            state.summary_open = true;
+            state.paused = true;
+        }
+        None => state.summary = None,
+        _ => (),
+    }
+    if state.summary_open && app.keyboard.was_pressed(KeyCode::P) {
+        if let Some(summary) = &state.summary {
+            save_summary(summary, &state.summary_output);
+        }
+    }
+    if let Some(perf) = &mut state.perf {
+        perf.update(
+            steps_done,
+            state.delta_time,
+            dt * steps_done as f32,
+            state.sim.timings,
+        );
+        if state.tick % 100 == 0 {
+            perf.update_memory();
+        }
     }
 
     // Exit the simulation with ESC
     #[cfg(not(target_arch = "wasm32"))]
     if app.keyboard.is_down(KeyCode::Escape) {
+        println!("Coverage: {:.1}%", state.sim.coverage() * 100.0);
+        if let Some(score) = &state.sim.score {
+            print!("{score}");
+        }
+        if let Some(kidnapper) = &state.sim.kidnapper {
+            print!("{kidnapper}");
+        }
+        if let Some((path, format)) = &state.telemetry {
+            let telemetry = Telemetry {
+                tag: state.tag.clone(),
+                frames: state.sim.recorder.frames.clone(),
+            };
+            if let Err(e) = telemetry::write_telemetry(path, &telemetry, *format) {
+                eprintln!("Could not save telemetry: {e}");
+            }
+        }
+        if let Some((path, maze, script)) = &state.results {
+            let result = RunResult::new(&state.sim, maze.clone(), script.clone());
+            if let Err(e) = results::write_results(path, &[result]) {
+                eprintln!("Could not save results: {e}");
+            }
+        }
         std::process::exit(0);
     }
 
@@ -152,11 +950,142 @@ fn update(app: &mut App, state: &mut State) {
     state.pause_timer = state.pause_timer.saturating_sub(1);
 }
 
+fn export_mouse_config(state: &State) -> Result<(), Error> {
+    let mut config: MouseConfig = from_toml("mouse config", &state.mouse_source)?;
+    Params::from_sim(&state.sim).apply_to_config(&mut config);
+    let config = toml::to_string_pretty(&config)
+        .map_err(|e| Error::format(state.tuned_mouse.display(), e))?;
+    write(&state.tuned_mouse, config)
+}
+
+fn save_summary(summary: &Summary, path: &std::path::Path) {
+    if let Err(e) = std::fs::write(path, summary.to_png()) {
+        eprintln!("Could not save summary: {e}");
+    }
+}
+
+/// The title of the window, with the name and author of the maze if it has them.
+fn window_title(maze: &Maze) -> String {
+    match (&maze.name, &maze.author) {
+        (Some(name), Some(author)) => format!("MiMoSi - {name} by {author}"),
+        (Some(name), None) => format!("MiMoSi - {name}"),
+        _ => String::from("MiMoSi"),
+    }
+}
+
+/// Swaps the maze of a simulation for a dropped one, forgetting where the mouse has been.
+fn replace_maze(sim: &mut Simulation, maze: Maze) {
+    sim.reachable_cells = maze.reachable_cells();
+    sim.visits.clear();
+    sim.trail.clear();
+    sim.current_cell = None;
+    sim.maze = maze;
+}
+
+fn save_snapshot(state: &mut State) {
+    state.snapshot = Some(state.sim.snapshot(&state.scope));
+    state.rival_snapshots = state.rivals.iter().map(Rival::snapshot).collect();
+}
+
+/// Goes back to the saved state, as often as needed to try something again from there.
+fn restore_snapshot(state: &mut State) {
+    if let Some(snapshot) = &state.snapshot {
+        state.sim.restore(snapshot, &mut state.scope);
+        for (rival, snapshot) in state.rivals.iter_mut().zip(&state.rival_snapshots) {
+            rival.restore(snapshot);
+        }
+        state.accumulator = 0.0;
+        state.drag = None;
+    }
+}
+
+fn toggle_perf(state: &mut State) {
+    state.perf = match state.perf {
+        Some(_) => None,
+        None => Some(PerfOverlay::new()),
+    };
+    state.sim.profiling = state.perf.is_some();
+}
+
+fn step(state: &mut State, dt: f32) {
+    for rival in &mut state.rivals {
+        rival.step(dt);
+    }
+    if state.manual {
+        let (left_power, right_power) = state.manual_powers;
+        state
+            .recorded_track
+            .record(state.sim.time, left_power, right_power);
+        state.sim.mouse.set_left_power(left_power);
+        state.sim.mouse.set_right_power(right_power);
+        state.sim.update(dt);
+        if let Some(stream) = &mut state.stream {
+            stream.send(&state.sim);
+        }
+        return;
+    }
+    // Only errors the scenario doesn't handle itself end up here
+    if let Err(e) = state.sim.step(&mut state.scope, dt) {
+        let error = state.script.runtime_error(e);
+        state.script_error = Some(format!("[{:.3}s] {error}", state.sim.time));
+        state.paused = true;
+        state.accumulator = 0.0;
+    }
+    if let Some(stream) = &mut state.stream {
+        stream.send(&state.sim);
+    }
+}
+
 #[derive(AppState)]
 struct State<'a> {
     sim: Simulation,
+    render_settings: RenderSettings,
+    time_scale: f32,
+    ruler: Ruler,
+    pointer_over_ui: bool,
+    drag: Option<Drag>,
+    view: View,
+    pan_from: Option<notan::math::Vec2>, // Where the cursor was while panning
+    robot_view: bool,
+    robot_texture: Option<RobotView>, // Created the first time the robot view is shown
+    panel_width: f32,
+    ui_scale: f32,
+    heatmap_path: PathBuf,
+    maze_source: String,
+    mouse_source: String,
+    replay_output: PathBuf,
+    replay_only: bool,
+    tag: Option<String>,
+    config_params: Params,
+    tuned_mouse: PathBuf,
+    manual: bool,
+    manual_powers: (f32, f32),
+    recorded_track: CommandTrack,
+    track_output: PathBuf,
+    perf: Option<PerfOverlay>,
+    show_console: bool,
+    show_hud: bool,
+    summary: Option<Summary>,
+    summary_open: bool,
+    summary_output: PathBuf,
+    telemetry: Option<(PathBuf, TelemetryFormat)>,
+    stream: Option<TelemetryStream>, // Clients watching the run live
+    results: Option<(PathBuf, String, String)>, // Where to save the results, with the names of the maze and script
+    player: Player,
+    reviewing: bool,
     paused: bool,
+    step_once: bool,       // Advance to the next call of the script while paused
+    pause_at: Option<f32>, // Simulated time to pause at
+    snapshot: Option<Snapshot<'a>>, // Saved with F5, restored with F9
+    rivals: Vec<Rival<'a>>, // Scripts racing the main one
+    rival_snapshots: Vec<Snapshot<'a>>, // Saved together with `snapshot`
+    ghosts: Vec<Ghost>,    // Recorded runs played back next to the mouse
+    tutorial: Option<Tutorial>,
+    script_error: Option<String>, // Last error of the script that paused the simulation
+    script: ScriptFile,
     pause_timer: usize,
+    physics_dt: f32,
+    accumulator: f32,
     scope: Scope<'a>,
     delta_time: f32,
     tick: usize,
@@ -164,54 +1093,616 @@ struct State<'a> {
 }
 
 #[notan_main]
-fn main() -> Result<(), String> {
-    let args = Args::parse();
-
-    match args.command.unwrap_or(Command::Simulate {
-        maze: None,
-        mouse: None,
-        script: None,
-    }) {
+fn main() {
+    // Printed here, since returning the error would show it quoted and with escaped line breaks
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    match args
+        .command
+        .unwrap_or_else(|| Command::Simulate(Box::default()))
+    {
         Command::ExampleScript => Ok(println!("{}", DEFAULT_SCRIPT)),
         Command::ExampleMouse => Ok(println!("{}", DEFAULT_MOUSE)),
         Command::ExampleMaze => Ok(println!("{}", DEFAULT_MAZE)),
-        Command::Simulate {
-            maze,
+        Command::Bench(BenchArgs {
+            mazes,
             mouse,
             script,
+            scenario,
+            track,
+            controller,
+            wasm,
+            shared_memory,
+            seed,
+            seeds,
+            duration,
+            dt,
+            tag,
+            history,
+            watchdog,
+            start,
+            realtime,
+            tolerance,
+            output,
+        }) => {
+            let (_, mouse, script) = read_with_defaults(None, mouse, script)?;
+            let scenario = read_scenario(scenario, seed)?;
+            let track = track.map(read_track).transpose()?;
+            let mazes: Vec<_> = if mazes.is_empty() {
+                vec![(PathBuf::from("example.maze"), s!(DEFAULT_MAZE))]
+            } else {
+                mazes
+                    .into_iter()
+                    .map(|path| read_maze(path.clone()).map(|maze| (path, maze)))
+                    .collect::<Result<_, _>>()?
+            };
+            let mazes = mazes
+                .into_iter()
+                .map(|(path, maze)| {
+                    let maze = apply_start(&path.display().to_string(), maze, &start)?;
+                    Ok((path, maze))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let setup = bench::Setup {
+                mouse: &mouse,
+                script: &script,
+                scenario: &scenario,
+                track: track.as_ref(),
+                controller: controller.as_deref(),
+                wasm: wasm.as_deref(),
+                shared_memory: shared_memory.as_deref(),
+                tag: tag.as_deref(),
+                // Logs would mix with the results other programs read
+                echo: output == OutputFormat::Table,
+                limits: bench::Limits::from_args(watchdog)?,
+                realtime: realtime.then(|| Duration::from_secs_f32(tolerance / 1000.0)),
+                all_runs: false,
+            };
+            bench::bench(
+                &mazes,
+                &setup,
+                seeds,
+                duration,
+                scenario.simulation.physics_dt(dt),
+                history.as_deref(),
+                output,
+            )
+        }
+        Command::Batch(BatchArgs {
+            mazes,
+            scripts,
+            mouse,
+            scenario,
+            seed,
+            duration,
+            dt,
+            jobs,
+            watchdog,
+            output,
+            html,
+            results,
+        }) => {
+            let (_, mouse, _) = read_with_defaults(None, mouse, None)?;
+            let scenario = read_scenario(scenario, seed)?;
+            let mazes = read_maze_files(&mazes)?;
+            let scripts = if scripts.is_empty() {
+                vec![(PathBuf::from("example.rhai"), s!(DEFAULT_SCRIPT))]
+            } else {
+                scripts
+                    .into_iter()
+                    .map(read_script)
+                    .collect::<Result<_, _>>()?
+            };
+            thread_pool(jobs)?;
+            let setup = bench::Setup::from_args(&mouse, &scenario, watchdog)?;
+            batch::batch(
+                &mazes,
+                &scripts,
+                &setup,
+                duration,
+                scenario.simulation.physics_dt(dt),
+                output,
+                batch::Exports {
+                    html: html.as_deref(),
+                    results: results.as_deref(),
+                },
+            )
+        }
+        Command::Ab(AbArgs {
+            a,
+            b,
+            mazes,
+            mouse,
+            scenario,
+            seed,
+            seeds,
+            duration,
+            dt,
+            jobs,
+            watchdog,
+            output,
+        }) => {
+            let (_, mouse, _) = read_with_defaults(None, mouse, None)?;
+            let scenario = read_scenario(scenario, seed)?;
+            let mazes = read_maze_files(&mazes)?;
+            let (a, b) = (read_script(a)?, read_script(b)?);
+            thread_pool(jobs)?;
+            let setup = bench::Setup::from_args(&mouse, &scenario, watchdog)?;
+            ab::ab(
+                &mazes,
+                (&a, &b),
+                &setup,
+                seeds,
+                duration,
+                scenario.simulation.physics_dt(dt),
+                output,
+            )
+        }
+        Command::Tournament(TournamentArgs {
+            mazes,
+            scripts,
+            mouse,
+            scenario,
+            seed,
+            duration,
+            dt,
+            jobs,
+            watchdog,
+            output,
+            html,
+            results,
+        }) => {
+            let (_, mouse, _) = read_with_defaults(None, mouse, None)?;
+            let mut scenario = read_scenario(scenario, seed)?;
+            let rules = *scenario.scoring.get_or_insert_with(Default::default);
+            let mazes = read_maze_files(&mazes)?;
+            let scripts = scripts
+                .into_iter()
+                .map(read_script)
+                .collect::<Result<Vec<_>, _>>()?;
+            thread_pool(jobs)?;
+            let setup = bench::Setup::from_args(&mouse, &scenario, watchdog)?;
+            tournament::tournament(
+                &mazes,
+                &scripts,
+                &setup,
+                duration.unwrap_or(rules.maze_time),
+                scenario.simulation.physics_dt(dt),
+                output,
+                batch::Exports {
+                    html: html.as_deref(),
+                    results: results.as_deref(),
+                },
+            )
+        }
+        Command::CheckReplay {
+            file,
+            maze: maze_path,
+            mouse: mouse_path,
+            max_acceleration,
         } => {
-            let (maze, mouse, script) =
-                read_with_defaults(maze, mouse, script).map_err(|e| format!("{e}"))?;
-            let maze = Maze::from_string(&maze, 50.0)?;
+            let maze_name = name(&maze_path, "example.maze");
+            let mouse_name = name(&mouse_path, "example mouse");
+            let (maze, mouse, _) = read_with_defaults(maze_path, mouse_path, None)?;
+            let maze = Maze::parse(&maze).map_err(|e| Error::maze(&maze_name, e))?;
+            let mouse: MouseConfig = from_toml(&mouse_name, &mouse)?;
+            let max_acceleration = max_acceleration
+                .or(mouse.max_acceleration)
+                .unwrap_or(mouse.max_speed * 10.0);
 
-            let mouse_config: MouseConfig = toml::from_str(&mouse).unwrap();
+            let frames = telemetry::read_telemetry(&file)?.frames;
+            let anomalies =
+                validate::check_replay(&frames, &maze, mouse.max_speed, max_acceleration);
+            for anomaly in &anomalies {
+                println!("{anomaly}");
+            }
+            if anomalies.is_empty() {
+                println!("No anomalies in {} frames", frames.len());
+                Ok(())
+            } else {
+                Err(Error::Found {
+                    count: anomalies.len(),
+                    what: "anomalies",
+                })
+            }
+        }
+        Command::ReplayReport { replays, html } => {
+            let replays = replays
+                .into_iter()
+                .map(|path| ReplayFile::load(&path).map(|replay| (path, replay)))
+                .collect::<Result<Vec<_>, _>>()?;
+            html::save_replays(&html, &replays)
+        }
+        Command::TrackToScript { file } => {
+            print!("{}", read_track(file)?.to_script());
+            Ok(())
+        }
+        Command::Export {
+            maze,
+            replay,
+            format,
+            output,
+            cell,
+        } => {
+            let is_toml = |path: &PathBuf| path.extension().is_some_and(|ext| ext == "toml");
+            let (saved_maze, frames) = match &replay {
+                Some(path) if is_toml(path) => {
+                    let replay = ReplayFile::load(path)?;
+                    (Some(replay.maze), replay.frames)
+                }
+                Some(path) => (None, telemetry::read_telemetry(path)?.frames),
+                None => (None, Vec::new()),
+            };
+            let input = maze.clone().or(replay).unwrap_or_default();
+            let source = match (maze, saved_maze) {
+                (Some(path), _) => read_maze(path)?,
+                (None, Some(source)) => source,
+                (None, None) => Err(Error::Invalid(s!(
+                    "Pass a maze, or a replay that contains one"
+                )))?,
+            };
+            let maze =
+                Maze::parse(&source).map_err(|e| Error::maze(&input.display().to_string(), e))?;
+            let output = output.unwrap_or_else(|| input.with_extension(format.extension()));
+            export::export(&output, format, &maze, &frames, cell)
+        }
+        Command::Convert {
+            input,
+            output,
+            from,
+            to,
+        } => convert::convert(&input, &output, from, to),
+        Command::DecodeTelemetry { file } => {
+            let telemetry = telemetry::read_telemetry(&file)?;
+            telemetry::write_csv(&mut std::io::stdout().lock(), &telemetry)
+                .map_err(|e| Error::io(std::path::Path::new("stdout"), e))
+        }
+        Command::History { file, tag, output } => {
+            history::print_history(&file, tag.as_deref(), output)
+        }
+        Command::Info {
+            maze,
+            speed,
+            acceleration,
+            output,
+        } => {
+            let maze = maze
+                .map(read_maze)
+                .unwrap_or_else(|| Ok(s!(DEFAULT_MAZE)))?;
+            info::print_info(&maze, speed, acceleration, output)
+        }
+        Command::Validate { maze: path, strict } => {
+            let maze = read_maze(path.clone())?;
+            let problems = lint::check_maze(&maze, strict)
+                .map_err(|e| Error::maze(&path.display().to_string(), e))?;
+            for problem in &problems {
+                println!("{}: {problem}", path.display());
+            }
+            if problems.is_empty() {
+                println!("No problems in {}", path.display());
+                Ok(())
+            } else {
+                Err(Error::Found {
+                    count: problems.len(),
+                    what: "problems",
+                })
+            }
+        }
+        Command::Mazes { command } => match command {
+            MazesCommand::List => corpus::print_sets(),
+            MazesCommand::Fetch { set, dir, update } => corpus::fetch(&set, &dir, update),
+            MazesCommand::Duplicates { mazes, output } => {
+                corpus::print_duplicates(&read_maze_files(&mazes)?, output)
+            }
+            MazesCommand::Edit {
+                maze,
+                add_wall,
+                remove_wall,
+                set,
+                layer,
+                output,
+                force,
+            } => {
+                let source = read_maze(maze.clone())?;
+                let output = output.unwrap_or_else(|| {
+                    let is_maz = maze
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("maz"));
+                    if is_maz {
+                        maze.with_extension("maze")
+                    } else {
+                        maze
+                    }
+                });
+                let patch = patch::Patch {
+                    add: add_wall,
+                    remove: remove_wall,
+                    set,
+                    layer,
+                };
+                patch::edit(&source, &patch, &output, force)
+            }
+        },
+        Command::Edit { maze, size } => {
+            let source = maze.exists().then(|| read_maze(maze.clone())).transpose()?;
+            editor::open(maze, source, size)
+        }
+        Command::NewScript { mouse } => {
+            let name = name(&mouse, "example mouse");
+            let mouse = mouse
+                .map(|path| read(&path))
+                .unwrap_or_else(|| Ok(s!(DEFAULT_MOUSE)))?;
+            let config: MouseConfig = from_toml(&name, &mouse)?;
+            print!("{}", template::generate_script(&config));
+            Ok(())
+        }
+        Command::Replay { file } => {
+            let replay = ReplayFile::load(&file)?;
+            let maze = Maze::parse(&replay.maze)
+                .map_err(|e| Error::maze(&format!("maze in {}", file.display()), e))?;
+            let mouse_config: MouseConfig = from_toml(
+                &format!("mouse config in {}", file.display()),
+                &replay.mouse,
+            )?;
+            // Nothing drives in a replay, so it doesn't matter where the finish is
+            let scenario = Scenario {
+                allow_unreachable_goal: true,
+                ..Default::default()
+            };
+            let mut sim = Simulation::new(String::new(), maze, mouse_config, scenario)?;
+            sim.events = replay.events;
+            sim.recorder.frames = replay.frames;
+            let args = SimulateArgs {
+                tag: replay.tag,
+                ..SimulateArgs::default()
+            };
+            open_window(
+                sim,
+                (ScriptFile::new(None, String::new()), None),
+                (Vec::new(), Vec::new()),
+                args,
+                (replay.maze, replay.mouse),
+                true,
+            )
+        }
+        Command::Drive {
+            maze,
+            mouse,
+            scenario,
+            track_output,
+        } => run(Args {
+            command: Some(Command::Simulate(Box::new(SimulateArgs {
+                maze,
+                mouse,
+                scenario,
+                track_output,
+                manual: true,
+                ..Default::default()
+            }))),
+        }),
+        Command::Simulate(args) => {
+            let args = *args;
+            let (maze_source, mouse_source, script) =
+                read_with_defaults(args.maze.clone(), args.mouse.clone(), args.script.clone())?;
+            let maze_name = name(&args.maze, "example.maze");
+            let maze_source = apply_start(&maze_name, maze_source, &args.start)?;
+            let maze = Maze::parse(&maze_source).map_err(|e| Error::maze(&maze_name, e))?;
 
-            let mut sim = Simulation::new(script, maze, mouse_config);
+            let mouse_config: MouseConfig =
+                from_toml(&name(&args.mouse, "example mouse"), &mouse_source)?;
+
+            let scenario = read_scenario(args.scenario.clone(), args.seed)?;
+
+            for warning in analysis::analyze(&script, &scenario.engine_config()) {
+                eprintln!("Warning: {warning}");
+            }
+
+            let rivals = args
+                .race
+                .iter()
+                .map(|path| {
+                    let script = read(path)?;
+                    for warning in analysis::analyze(&script, &scenario.engine_config()) {
+                        eprintln!("Warning: {}: {warning}", path.display());
+                    }
+                    Rival::new(
+                        path.display().to_string(),
+                        script,
+                        (&maze_source, &mouse_source),
+                        &scenario,
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let ghosts = args
+                .ghost
+                .iter()
+                .map(|path| {
+                    let replay = ReplayFile::load(path)?;
+                    Ok(Ghost::new(path.display().to_string(), replay, &maze_source))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let physics_dt = scenario.simulation.physics_dt(args.physics_dt);
+            // A script that doesn't compile is shown in the window, to fix it there and save it again
+            let script = ScriptFile::new(args.script.clone(), script);
+            let mut sim = Simulation::new(String::new(), maze, mouse_config, scenario)?;
+            let script_error = match script.compile(&sim.engine) {
+                Ok(ast) => {
+                    sim.ast = ast;
+                    None
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    Some(e)
+                }
+            };
+            for warning in validate::check_setup(&sim) {
+                eprintln!("Warning: {warning}");
+            }
+            sim.track = args.track.clone().map(read_track).transpose()?;
+            if let Some(address) = &args.controller {
+                println!("Waiting for a controller on {address}");
+                sim.external = Some(Box::new(ExternalController::listen(address, &sim.mouse)?));
+            }
+            if let Some(path) = &args.wasm {
+                sim.external = Some(Box::new(WasmController::load(path, &sim.mouse)?));
+            }
+            if let Some(path) = &args.shared_memory {
+                println!("Waiting for a controller on {}", path.display());
+                sim.external = Some(Box::new(SharedMemoryController::create(path, &sim.mouse)?));
+            }
 
             // Update the simulation
             sim.update(0.0);
 
-            let win_config = WindowConfig::new().set_size(1015, 810).set_vsync(true);
-
-            notan::init_with(|| {
-                let mut scope = Scope::new();
-                scope.push_dynamic("state", Dynamic::from_map(Default::default()));
-                State {
-                    sim,
-                    paused: true,
-                    pause_timer: 0,
-                    scope,
-                    delta_time: 0.0,
-                    fps: 0.0,
-                    tick: 0,
-                }
-            })
-            .add_config(win_config)
-            .add_config(DrawConfig)
-            .add_config(EguiConfig)
-            .update(update)
-            .draw(draw)
-            .build()
+            let args = SimulateArgs { physics_dt, ..args };
+            open_window(
+                sim,
+                (script, script_error),
+                (rivals, ghosts),
+                args,
+                (maze_source, mouse_source),
+                false,
+            )
         }
     }
 }
+
+/// Shows the simulation in a window. With `replay_only`, only the recorded frames can be reviewed.
+fn open_window(
+    mut sim: Simulation,
+    (script_file, script_error): (ScriptFile, Option<String>),
+    (rivals, ghosts): (Vec<Rival<'static>>, Vec<Ghost>),
+    SimulateArgs {
+        maze,
+        script,
+        time_scale,
+        decimation_threshold,
+        physics_dt,
+        max_sensor_rays,
+        replay_output,
+        tuned_mouse,
+        heatmap: heatmap_path,
+        summary: summary_output,
+        track_output,
+        ui_scale,
+        perf,
+        manual,
+        telemetry,
+        telemetry_format,
+        telemetry_port,
+        telemetry_address,
+        tag,
+        pause_at,
+        results,
+        tutorial,
+        ..
+    }: SimulateArgs,
+    (maze_source, mouse_source): (String, String),
+    replay_only: bool,
+) -> Result<(), Error> {
+    sim.profiling = perf;
+    let tutorial = tutorial.map(|path| Tutorial::load(&path)).transpose()?;
+    let stream = telemetry_port
+        .map(|port| TelemetryStream::listen(telemetry_address, port))
+        .transpose()?;
+    let config_params = Params::from_sim(&sim);
+
+    let win_config = WindowConfig::new()
+        .set_title(&window_title(&sim.maze))
+        .set_size(1015, 810)
+        .set_min_size(400, 300)
+        .set_resizable(true)
+        .set_high_dpi(true)
+        .set_vsync(true);
+
+    notan::init_with(|| {
+        let mut scope = Scope::new();
+        scope.push_dynamic("state", Dynamic::from_map(Default::default()));
+        State {
+            sim,
+            render_settings: RenderSettings {
+                decimation_threshold,
+                max_sensor_rays,
+                // Shows how far the walls are while driving by hand
+                ray_colors: if manual {
+                    RayColors::Distance
+                } else {
+                    RayColors::Uniform
+                },
+                ..Default::default()
+            },
+            time_scale,
+            ruler: Ruler::default(),
+            pointer_over_ui: false,
+            drag: None,
+            view: View::default(),
+            pan_from: None,
+            robot_view: false,
+            robot_texture: None,
+            panel_width: 0.0,
+            ui_scale,
+            heatmap_path,
+            maze_source,
+            mouse_source,
+            replay_output,
+            replay_only,
+            tag,
+            config_params,
+            tuned_mouse,
+            manual,
+            manual_powers: (0.0, 0.0),
+            recorded_track: CommandTrack::default(),
+            track_output,
+            perf: perf.then(PerfOverlay::new),
+            show_console: true,
+            show_hud: manual,
+            summary: None,
+            summary_open: false,
+            summary_output,
+            telemetry: telemetry.map(|path| (path, telemetry_format)),
+            stream,
+            results: results.map(|path| {
+                let name = |file: Option<PathBuf>, default: &str| {
+                    file.map_or(String::from(default), |f| f.display().to_string())
+                };
+                (
+                    path,
+                    name(maze, "example.maze"),
+                    name(script, "example.rhai"),
+                )
+            }),
+            player: Player::default(),
+            reviewing: replay_only,
+            paused: !manual,
+            step_once: false,
+            pause_at,
+            snapshot: None,
+            rivals,
+            rival_snapshots: Vec::new(),
+            ghosts,
+            tutorial,
+            script_error,
+            script: script_file,
+            pause_timer: 0,
+            physics_dt,
+            accumulator: 0.0,
+            scope,
+            delta_time: 0.0,
+            fps: 0.0,
+            tick: 0,
+        }
+    })
+    .add_config(win_config)
+    .add_config(DrawConfig)
+    .add_config(EguiConfig)
+    .update(update)
+    .draw(draw)
+    .build()
+    .map_err(Error::Window)
+}
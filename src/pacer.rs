@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+/// Below this, the pacer spins instead of sleeping, since sleeps overshoot by about as much.
+const SPIN: Duration = Duration::from_micros(200);
+
+/// How closely a run kept to the wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pacing {
+    pub jitter: f32,    // Standard deviation of how late the steps were, in seconds
+    pub latest: f32,    // Latest a step was, in seconds
+    pub resyncs: usize, // How often the simulation fell behind by more than the tolerance
+}
+
+/// Holds the simulation back until the wall clock catches up, so firmware driving the mouse
+/// gets its sensor readings at the cadence it would on the real mouse.
+pub struct Pacer {
+    start: Instant,
+    tolerance: Duration,
+    lateness: Vec<f32>, // Seconds each step was behind the wall clock
+    resyncs: usize,
+}
+
+impl Pacer {
+    pub fn new(tolerance: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            tolerance,
+            lateness: Vec::new(),
+            resyncs: 0,
+        }
+    }
+
+    /// Waits until the wall clock reaches the simulated time.
+    ///
+    /// A simulation that falls behind by more than the tolerance moves on from where it is,
+    /// instead of rushing through the steps it missed.
+    pub fn pace(&mut self, time: f32) {
+        let target = self.start + Duration::from_secs_f32(time.max(0.0));
+        if let Some(remaining) = target.checked_duration_since(Instant::now()) {
+            if remaining > SPIN {
+                std::thread::sleep(remaining - SPIN);
+            }
+            while Instant::now() < target {
+                std::hint::spin_loop();
+            }
+        }
+        let late = Instant::now().saturating_duration_since(target);
+        self.lateness.push(late.as_secs_f32());
+        if late > self.tolerance {
+            self.start += late;
+            self.resyncs += 1;
+        }
+    }
+
+    pub fn pacing(&self) -> Pacing {
+        let n = self.lateness.len().max(1) as f32;
+        let mean = self.lateness.iter().sum::<f32>() / n;
+        let variance = self
+            .lateness
+            .iter()
+            .map(|l| (l - mean).powi(2))
+            .sum::<f32>()
+            / n;
+        Pacing {
+            jitter: variance.sqrt(),
+            latest: self.lateness.iter().copied().fold(0.0, f32::max),
+            resyncs: self.resyncs,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{path::Path, str::FromStr};
+
+use mazeparser::{Maze, WallRun};
+use mimosi_core::internal::error::{self, Error};
+
+use crate::lint;
+
+/// Keys of the text format that `--set` can change, the others need more than a single line.
+const SETTABLE: &[&str] = &["SP", "SD", "FI", "FR", "WT", "WC", "FC"];
+
+/// Changes to apply to a maze without opening the editor.
+pub struct Patch {
+    pub add: Vec<WallRun>,
+    pub remove: Vec<WallRun>,
+    pub set: Vec<String>, // `KEY:VALUE` lines of the text format
+    pub layer: usize,     // Layer the walls are added to and removed from
+}
+
+/// Replaces the line with the key in a maze in the text format.
+fn set_line(text: &str, assignment: &str) -> Result<String, Error> {
+    let (key, value) = assignment
+        .split_once(':')
+        .ok_or_else(|| Error::Invalid(format!("Expected KEY:VALUE, got '{assignment}'")))?;
+    let key = key.trim().to_uppercase();
+    if !SETTABLE.contains(&key.as_str()) {
+        return Err(Error::Invalid(format!(
+            "Can't set '{key}', only one of {}",
+            SETTABLE.join(", ")
+        )));
+    }
+    let line = format!("{key}: {}", value.trim());
+    let mut lines: Vec<String> = text
+        .lines()
+        .filter(|l| {
+            l.split_once(':')
+                .is_none_or(|(k, _)| k.trim().to_uppercase() != key)
+        })
+        .map(String::from)
+        .collect();
+    lines.insert(0, line);
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Applies the patch to a maze in the text format and writes the result.
+///
+/// The patched maze is checked like `mimosi validate` and only written if it has no problems, unless forced.
+pub fn edit(source: &str, patch: &Patch, output: &Path, force: bool) -> Result<(), Error> {
+    let mut maze = Maze::from_str(source).map_err(|e| Error::maze("maze", e))?;
+    for run in &patch.remove {
+        maze.set_walls(run, patch.layer, false);
+    }
+    for run in &patch.add {
+        maze.set_walls(run, patch.layer, true);
+    }
+    let mut text = maze.to_string();
+    for assignment in &patch.set {
+        text = set_line(&text, assignment)?;
+    }
+    // Parse the result again, so an invalid value is reported instead of written
+    let text = Maze::from_str(&text)
+        .map_err(|e| Error::maze("patched maze", e))?
+        .to_string();
+
+    let problems = lint::check_maze(&text, false).map_err(|e| Error::maze("patched maze", e))?;
+    for problem in &problems {
+        eprintln!("{}: {problem}", output.display());
+    }
+    if !problems.is_empty() && !force {
+        return Err(Error::Found {
+            count: problems.len(),
+            what: "problems, not writing the maze. Pass --force to write it anyway",
+        });
+    }
+    error::write(output, text)
+}
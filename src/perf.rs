@@ -0,0 +1,81 @@
+use mimosi_core::internal::simulation::Timings;
+use notan::egui::{self, Color32, Context};
+
+/// Resident memory of the process in bytes, where the platform tells us.
+fn memory_usage() -> Option<usize> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+/// Shows where the time of a frame goes, to spot when the simulation can't keep up.
+#[derive(Debug, Default)]
+pub struct PerfOverlay {
+    pub physics_steps: usize,
+    pub timings: Timings,
+    pub frame_time: f32,
+    pub sim_speed: f32,
+    pub memory: Option<usize>,
+}
+
+impl PerfOverlay {
+    pub fn new() -> Self {
+        let mut perf = Self::default();
+        perf.update_memory();
+        perf
+    }
+
+    pub fn update(
+        &mut self,
+        physics_steps: usize,
+        frame_time: f32,
+        sim_time: f32,
+        timings: Timings,
+    ) {
+        self.physics_steps = physics_steps;
+        self.timings = timings;
+        self.frame_time = frame_time;
+        self.sim_speed = if frame_time > 0.0 {
+            sim_time / frame_time
+        } else {
+            0.0
+        };
+    }
+
+    pub fn update_memory(&mut self) {
+        self.memory = memory_usage();
+    }
+
+    pub fn show(&self, ctx: &Context, fps: f32, time_scale: f32, paused: bool) {
+        egui::Window::new("Performance")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("FPS: {fps:.0}"));
+                ui.label(format!("Frame time: {:.2} ms", self.frame_time * 1000.0));
+                ui.label(format!("Physics steps per frame: {}", self.physics_steps));
+                ui.label(format!(
+                    "Script time: {:.2} ms",
+                    self.timings.script.as_secs_f32() * 1000.0
+                ));
+                ui.label(format!(
+                    "Raycast time: {:.2} ms",
+                    self.timings.raycast.as_secs_f32() * 1000.0
+                ));
+                if let Some(memory) = self.memory {
+                    ui.label(format!("Memory: {:.1} MB", memory as f32 / 1_000_000.0));
+                }
+
+                let physics_time = (self.timings.script + self.timings.raycast).as_secs_f32();
+                let behind = !paused
+                    && (self.sim_speed < time_scale * 0.95 || physics_time > self.frame_time);
+                ui.colored_label(
+                    if behind {
+                        Color32::RED
+                    } else {
+                        ui.visuals().text_color()
+                    },
+                    format!("Sim speed: {:.2}x of {:.2}x", self.sim_speed, time_scale),
+                );
+            });
+    }
+}
@@ -0,0 +1,60 @@
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Encodes RGB pixels, row by row, as a PNG with the given text entries.
+pub fn encode_rgb(width: u32, height: u32, pixels: &[u8], text: &[(&str, String)]) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::new();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    header.extend([8, 2, 0, 0, 0]); // 8 bit RGB, no interlacing
+    chunk(&mut png, b"IHDR", &header);
+
+    for (key, value) in text {
+        chunk(&mut png, b"tEXt", format!("{key}\0{value}").as_bytes());
+    }
+
+    // Every row starts with the filter type, 0 means unfiltered
+    let row = width as usize * 3;
+    let mut data = Vec::with_capacity((row + 1) * height as usize);
+    for line in pixels.chunks(row) {
+        data.push(0);
+        data.extend(line);
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&data)
+        .expect("Compressing into memory doesn't fail");
+    let data = encoder
+        .finish()
+        .expect("Compressing into memory doesn't fail");
+    chunk(&mut png, b"IDAT", &data);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
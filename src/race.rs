@@ -0,0 +1,118 @@
+use rhai::{Dynamic, Scope};
+
+use mimosi_core::internal::{
+    error::{from_toml, Error},
+    events::{Event, TimedEvent},
+    maze::Maze,
+    mouse::MouseConfig,
+    replay::{Recorder, ReplayFile},
+    scenario::Scenario,
+    simulation::{Simulation, Snapshot},
+};
+
+use crate::summary::RunOutcome;
+
+/// Another script racing the one of the window on the same maze, drawn as a ghost the mouse drives through.
+pub struct Rival<'a> {
+    pub name: String,
+    pub sim: Simulation,
+    pub scope: Scope<'a>,
+    pub error: Option<String>, // Error the script stopped with
+}
+
+impl<'a> Rival<'a> {
+    pub fn new(
+        name: String,
+        script: String,
+        (maze, mouse): (&str, &str),
+        scenario: &Scenario,
+    ) -> Result<Self, Error> {
+        let maze = Maze::parse(maze).map_err(|e| Error::maze("maze", e))?;
+        let mouse: MouseConfig = from_toml("mouse config", mouse)?;
+        let mut sim =
+            Simulation::new(script, maze, mouse, scenario.clone()).map_err(|e| Error::In {
+                name: name.clone(),
+                source: Box::new(e),
+            })?;
+        sim.update(0.0);
+        let mut scope = Scope::new();
+        scope.push_dynamic("state", Dynamic::from_map(Default::default()));
+        Ok(Self {
+            name,
+            sim,
+            scope,
+            error: None,
+        })
+    }
+
+    /// Advances the rival by a physics step, unless its race is over.
+    pub fn step(&mut self, dt: f32) {
+        if self.error.is_some() || self.sim.collided || self.sim.timed_out() {
+            return;
+        }
+        if let Err(e) = self.sim.step(&mut self.scope, dt) {
+            self.error = Some(format!("[{:.3}s] {e}", self.sim.time));
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot<'a> {
+        self.sim.snapshot(&self.scope)
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot<'a>) {
+        self.sim.restore(snapshot, &mut self.scope);
+        self.error = None;
+    }
+}
+
+/// A recorded run played back next to the live one, e.g. the best run of the previous tune.
+pub struct Ghost {
+    pub name: String,
+    pub recorder: Recorder,
+    pub events: Vec<TimedEvent>,
+}
+
+impl Ghost {
+    /// Warns if the run was recorded on a different maze, since the ghost would drive through its walls.
+    pub fn new(name: String, replay: ReplayFile, maze: &str) -> Self {
+        if replay.maze.trim() != maze.trim() {
+            eprintln!("Warning: {name} was recorded on a different maze");
+        }
+        Self {
+            name,
+            recorder: Recorder {
+                frames: replay.frames,
+            },
+            events: replay.events,
+        }
+    }
+
+    /// How the recorded run ended, from its events.
+    pub fn standing(&self) -> String {
+        let mut run_start = 0.0;
+        for e in &self.events {
+            match e.event {
+                Event::RunStart => run_start = e.time,
+                Event::Finish => return format!("Finished in {:.3} s", e.time - run_start),
+                Event::Crash => return String::from("Crashed"),
+                _ => {}
+            }
+        }
+        format!("Stopped after {:.3} s", self.recorder.duration())
+    }
+}
+
+/// How far a racer got, e.g. `Finished in 7.271 s`.
+pub fn standing(sim: &Simulation) -> String {
+    let finish = sim
+        .events
+        .iter()
+        .find(|e| matches!(e.event, Event::Finish))
+        .map(|e| e.time - sim.run_start);
+    match (RunOutcome::of(sim), finish) {
+        (_, Some(time)) => format!("Finished in {time:.3} s"),
+        (Some(RunOutcome::Crashed), None) => String::from("Crashed"),
+        (Some(RunOutcome::TimedOut), None) => String::from("Timed out"),
+        _ => format!("Running, {:.1}% explored", sim.coverage() * 100.0),
+    }
+}
@@ -0,0 +1,178 @@
+use notan::{
+    app::Color,
+    math::{vec2, Mat3, Vec2},
+};
+
+use crate::{png, renderer::Renderer};
+
+/// Samples per pixel along each axis when rasterizing, to smooth the edges.
+const SAMPLES: usize = 2;
+/// Thinnest stroke in pixels, so walls of a zoomed out maze don't fall between the samples.
+const MIN_STROKE: f32 = 1.0;
+
+/// Distance of a point to the segment from a to b.
+fn segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+/// Whether the point is inside the triangle, whichever way round its corners are.
+fn in_triangle(p: Vec2, [a, b, c]: [Vec2; 3]) -> bool {
+    let side = |from: Vec2, to: Vec2| (to - from).perp_dot(p - from);
+    let sides = [side(a, b), side(b, c), side(c, a)];
+    sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0)
+}
+
+/// Draws without a window into an image in memory, for exports and for testing what the window shows.
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<[f32; 3]>, // RGB from 0 to 255, row by row
+    transforms: Vec<Mat3>,
+}
+
+impl Raster {
+    /// A white image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[255.0; 3]; (width * height) as usize],
+            transforms: Vec::new(),
+        }
+    }
+
+    fn matrix(&self) -> Mat3 {
+        self.transforms.last().copied().unwrap_or(Mat3::IDENTITY)
+    }
+
+    /// How much the current transform scales lengths, for widths and radii.
+    fn scale(&self) -> f32 {
+        self.matrix().determinant().abs().sqrt()
+    }
+
+    /// Blends the color over the pixel, `alpha` being how much of it shows.
+    pub fn blend(&mut self, x: u32, y: u32, color: [f32; 3], alpha: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let pixel = &mut self.pixels[(y * self.width + x) as usize];
+        for (channel, value) in pixel.iter_mut().zip(color) {
+            *channel += (value - *channel) * alpha;
+        }
+    }
+
+    /// Fills the pixels covered by the shape, blending it over what is there by how much of each pixel it covers.
+    fn fill(&mut self, (min, max): (Vec2, Vec2), color: Color, inside: impl Fn(Vec2) -> bool) {
+        let (x0, y0) = (min.x.floor().max(0.0) as u32, min.y.floor().max(0.0) as u32);
+        let (x1, y1) = (
+            (max.x.ceil() as u32).min(self.width),
+            (max.y.ceil() as u32).min(self.height),
+        );
+        let rgb = [color.r, color.g, color.b].map(|channel| channel * 255.0);
+        let step = 1.0 / SAMPLES as f32;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let mut covered = 0;
+                for sy in 0..SAMPLES {
+                    for sx in 0..SAMPLES {
+                        let p = vec2(
+                            x as f32 + (sx as f32 + 0.5) * step,
+                            y as f32 + (sy as f32 + 0.5) * step,
+                        );
+                        covered += inside(p) as usize;
+                    }
+                }
+                if covered > 0 {
+                    let coverage = covered as f32 / (SAMPLES * SAMPLES) as f32;
+                    self.blend(x, y, rgb, color.a * coverage);
+                }
+            }
+        }
+    }
+
+    /// The pixels as RGB bytes, row by row.
+    pub fn rgb(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| pixel.map(|channel| channel.round() as u8))
+            .collect()
+    }
+
+    pub fn to_png(&self, text: &[(&str, String)]) -> Vec<u8> {
+        png::encode_rgb(self.width, self.height, &self.rgb(), text)
+    }
+}
+
+impl Renderer for Raster {
+    fn clear(&mut self, color: Color) {
+        let rgb = [color.r, color.g, color.b].map(|channel| channel * 255.0);
+        self.pixels.fill(rgb);
+    }
+
+    fn push_transform(&mut self, matrix: Mat3) {
+        self.transforms.push(self.matrix() * matrix);
+    }
+
+    fn pop_transform(&mut self) {
+        self.transforms.pop();
+    }
+
+    fn rect(&mut self, position: Vec2, size: Vec2, color: Color) {
+        // Sides thinner than the minimum stroke grow around their center
+        let min = MIN_STROKE / self.scale();
+        let thickened = size.abs().max(Vec2::splat(min)) * size.signum();
+        let (position, size) = (position - (thickened - size) / 2.0, thickened);
+        let matrix = self.matrix();
+        // Rotated rectangles are drawn as two triangles
+        if matrix.x_axis.y != 0.0 || matrix.y_axis.x != 0.0 {
+            let corners = [
+                position,
+                position + vec2(size.x, 0.0),
+                position + size,
+                position + vec2(0.0, size.y),
+            ];
+            self.triangle([corners[0], corners[1], corners[2]], color);
+            self.triangle([corners[0], corners[2], corners[3]], color);
+            return;
+        }
+        let (a, b) = (
+            matrix.transform_point2(position),
+            matrix.transform_point2(position + size),
+        );
+        let (min, max) = (a.min(b), a.max(b));
+        self.fill((min, max), color, |p| {
+            p.cmpge(min).all() && p.cmplt(max).all()
+        });
+    }
+
+    fn triangle(&mut self, points: [Vec2; 3], color: Color) {
+        let matrix = self.matrix();
+        let points = points.map(|p| matrix.transform_point2(p));
+        let min = points[0].min(points[1]).min(points[2]);
+        let max = points[0].max(points[1]).max(points[2]);
+        self.fill((min, max), color, |p| in_triangle(p, points));
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color) {
+        let matrix = self.matrix();
+        let (a, b) = (matrix.transform_point2(from), matrix.transform_point2(to));
+        let half = (width * self.scale()).max(MIN_STROKE) / 2.0;
+        self.fill((a.min(b) - half, a.max(b) + half), color, |p| {
+            segment_distance(p, a, b) <= half
+        });
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let center = self.matrix().transform_point2(center);
+        let radius = radius * self.scale();
+        self.fill((center - radius, center + radius), color, |p| {
+            p.distance(center) <= radius
+        });
+    }
+}
@@ -0,0 +1,318 @@
+use mazeparser::grid::{EAST, NORTH, SOUTH};
+use mimosi_core::internal::{
+    maze::Wall,
+    replay::{Frame, SensorFrame},
+    simulation::Simulation,
+};
+use notan::{
+    app::Color,
+    math::{vec2, Vec2},
+};
+
+use crate::{renderer::Renderer, view::layer_offset};
+
+/// Colors of the sensor groups, in the order of their names.
+const GROUP_COLORS: [Color; 8] = [
+    Color::ORANGE,
+    Color::AQUA,
+    Color::MAGENTA,
+    Color::YELLOW,
+    Color::TEAL,
+    Color::PINK,
+    Color::NAVY,
+    Color::OLIVE,
+];
+/// Opacity of the mice racing against the one of the window.
+const GHOST_ALPHA: f32 = 0.5;
+/// Distance in cells at which a sensor without a `max_range` counts as far for the coloring by distance.
+const FAR_CELLS: f32 = 2.0;
+
+/// How the rays of the sensors are colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RayColors {
+    #[default]
+    Uniform,
+    /// By the `group` of the sensor in the mouse config, purple without one
+    Group,
+    /// From red when close to a wall to green when far
+    Distance,
+}
+
+/// Controls how much detail the renderer draws per frame.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    /// Time scale from which on the renderer decimates
+    pub decimation_threshold: f32,
+    /// How many sensor rays are drawn while decimating
+    pub max_sensor_rays: usize,
+    /// Draw the path the mouse drove recently
+    pub trail: bool,
+    /// Color the trail from blue when slow to red when fast
+    pub trail_by_speed: bool,
+    pub ray_colors: RayColors,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            decimation_threshold: 4.0,
+            max_sensor_rays: 4,
+            trail: true,
+            trail_by_speed: false,
+            ray_colors: RayColors::Uniform,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn is_decimating(&self, time_scale: f32, paused: bool) -> bool {
+        !paused && time_scale >= self.decimation_threshold
+    }
+}
+
+/// Color of a rival or a replayed ghost, the same in the maze and in the panel.
+pub fn rival_color(index: usize) -> Color {
+    GROUP_COLORS[index % GROUP_COLORS.len()]
+}
+
+/// Renders the current state, or a recorded frame instead of the live mouse.
+///
+/// The ghosts are the poses of other mice in their colors, which are drawn without their sensors.
+pub fn render(
+    sim: &Simulation,
+    draw: &mut impl Renderer,
+    settings: &RenderSettings,
+    decimating: bool,
+    replay: Option<&Frame>,
+    ghosts: &[(Frame, Color)],
+) {
+    draw.clear(Color::GRAY);
+
+    // Render the maze with internal and outside walls
+    render_maze(sim, draw);
+
+    if settings.trail {
+        render_trail(sim, draw, settings.trail_by_speed);
+    }
+
+    for (frame, color) in ghosts {
+        render_ghost(sim, draw, frame, *color);
+    }
+
+    // Render the mouse
+    let max_sensor_rays = if decimating {
+        settings.max_sensor_rays
+    } else {
+        usize::MAX
+    };
+    let rays = (max_sensor_rays, settings.ray_colors);
+    match replay {
+        Some(frame) => render_mouse(sim, draw, rays, frame),
+        None => {
+            render_turn(sim, draw);
+            render_mouse(sim, draw, rays, &sim.frame());
+        }
+    }
+}
+
+fn render_maze(sim: &Simulation, draw: &mut impl Renderer) {
+    let finish = &sim.maze.finish;
+    draw.rect(
+        finish.p1,
+        finish.p3 - finish.p1,
+        Color::from_hex(sim.maze.finish_material.color),
+    );
+
+    let color = Color::from_hex(sim.maze.wall_material.color);
+    render_walls(draw, &sim.maze.walls, Vec2::ZERO, color);
+
+    // The upper layer is drawn next to the floor, over a faint copy of it to show where the bridge crosses
+    let mut layers = 1;
+    if let Some(upper) = &sim.maze.upper {
+        let offset = layer_offset(&sim.maze, 1);
+        render_walls(draw, &sim.maze.walls, offset, color.with_alpha(0.2));
+        render_walls(draw, &upper.walls, offset, color);
+        layers = 2;
+    }
+
+    // Ramps are shown on both layers, with a line towards the side that leads up
+    let size = sim.maze.cell_size;
+    for ramp in &sim.maze.ramps {
+        let up = match ramp.up {
+            NORTH => vec2(0.0, -1.0),
+            EAST => vec2(1.0, 0.0),
+            SOUTH => vec2(0.0, 1.0),
+            _ => vec2(-1.0, 0.0),
+        };
+        for layer in 0..layers {
+            let corner = vec2(ramp.cell.0 as f32, ramp.cell.1 as f32) * size
+                + layer_offset(&sim.maze, layer);
+            let center = corner + Vec2::splat(size / 2.0);
+            draw.rect(corner, Vec2::splat(size), Color::ORANGE.with_alpha(0.4));
+            draw.line(center, center + up * size * 0.4, 2.0, Color::ORANGE);
+        }
+    }
+}
+
+/// Walls are drawn as rectangles from one corner to the opposite one, as they run along a row or column.
+fn render_walls(draw: &mut impl Renderer, walls: &[Wall], offset: Vec2, color: Color) {
+    for wall in walls {
+        draw.rect(wall.p1 + offset, wall.p3 - wall.p1, color);
+    }
+}
+
+/// Draws the trail as a line that fades out towards its oldest points.
+fn render_trail(sim: &Simulation, draw: &mut impl Renderer, by_speed: bool) {
+    let points = &sim.trail.points;
+    let fastest = points.iter().map(|p| p.speed).fold(0.0, f32::max).max(1.0);
+    for (i, (a, b)) in points.iter().zip(points.iter().skip(1)).enumerate() {
+        // Don't connect the points where the mouse was moved or changed the layer
+        if a.layer != b.layer || a.position.distance(b.position) > sim.maze.cell_size / 2.0 {
+            continue;
+        }
+        let alpha = 0.1 + 0.7 * (i + 1) as f32 / points.len() as f32;
+        let color = if by_speed {
+            let t = b.speed / fastest;
+            Color::new(t, 0.0, 1.0 - t, alpha)
+        } else {
+            Color::BLUE.with_alpha(alpha)
+        };
+        let offset = layer_offset(&sim.maze, b.layer);
+        draw.line(a.position + offset, b.position + offset, 2.0, color);
+    }
+}
+
+fn render_turn(sim: &Simulation, draw: &mut impl Renderer) {
+    let offset = layer_offset(&sim.maze, sim.mouse.layer);
+    let Some(turn) = &sim.mouse.planned_turn else {
+        return;
+    };
+    let arc = turn.remaining_arc(sim.mouse.position, sim.mouse.orientation, 16);
+    for points in arc.windows(2) {
+        draw.line(points[0] + offset, points[1] + offset, 2.0, Color::ORANGE);
+    }
+}
+
+fn ray_color(
+    sim: &Simulation,
+    groups: &[&String],
+    reading: &SensorFrame,
+    colors: RayColors,
+) -> Color {
+    let sensor = sim.mouse.sensors.get(&reading.name);
+    match colors {
+        RayColors::Uniform => Color::PURPLE,
+        RayColors::Group => sensor
+            .and_then(|sensor| sensor.group.as_ref())
+            .and_then(|group| groups.iter().position(|g| *g == group))
+            .map_or(Color::PURPLE, |i| GROUP_COLORS[i % GROUP_COLORS.len()]),
+        RayColors::Distance => {
+            let units_per_mm = 1.0 / (sim.maze.meters_per_unit() * 1000.0);
+            let far = sensor
+                .and_then(|sensor| sensor.max_range)
+                .map_or(FAR_CELLS * sim.maze.cell_size, |range| range * units_per_mm);
+            let t = (reading.value / far).clamp(0.0, 1.0);
+            Color::new(1.0 - t, t, 0.0, 1.0)
+        }
+    }
+}
+
+/// The mouse in the view of the layer it is on.
+struct Outline {
+    body: Vec<Vec2>,
+    front: [Vec2; 3],         // Triangle pointing forward, as wide as the body
+    cross: [(Vec2, Vec2); 2], // Diagonals of the box around the body
+}
+
+impl Outline {
+    fn new(sim: &Simulation, frame: &Frame) -> Self {
+        let offset = layer_offset(&sim.maze, frame.layer);
+        let rotation = Vec2::from_angle(frame.orientation);
+        let place = |corner: Vec2| frame.position + corner.rotate(rotation) + offset;
+        let body = sim.mouse.outline();
+        let min = body.iter().copied().reduce(Vec2::min).unwrap_or_default();
+        let max = body.iter().copied().reduce(Vec2::max).unwrap_or_default();
+        let half_width = max.y.max(-min.y);
+        Self {
+            body: body.iter().copied().map(place).collect(),
+            front: [
+                vec2(max.x - half_width, -half_width),
+                vec2(max.x, 0.0),
+                vec2(max.x - half_width, half_width),
+            ]
+            .map(place),
+            cross: [
+                (place(min), place(max)),
+                (place(vec2(min.x, max.y)), place(vec2(max.x, min.y))),
+            ],
+        }
+    }
+
+    /// Fills the body, which is convex, with a fan of triangles.
+    fn fill(&self, draw: &mut impl Renderer, color: Color) {
+        for pair in self.body.windows(2).skip(1) {
+            draw.triangle([self.body[0], pair[0], pair[1]], color);
+        }
+    }
+
+    fn cross_out(&self, draw: &mut impl Renderer, color: Color) {
+        for (from, to) in self.cross {
+            draw.line(from, to, 2.0, color);
+        }
+    }
+}
+
+/// Draws a rival in a single color, crossed out like the mouse when it crashed or finished.
+fn render_ghost(sim: &Simulation, draw: &mut impl Renderer, frame: &Frame, color: Color) {
+    let outline = Outline::new(sim, frame);
+    outline.fill(draw, color.with_alpha(GHOST_ALPHA));
+    if frame.collided || frame.finished {
+        let mark = if frame.collided {
+            Color::BLACK
+        } else {
+            Color::GREEN
+        };
+        outline.cross_out(draw, mark.with_alpha(GHOST_ALPHA));
+    }
+}
+
+fn render_mouse(
+    sim: &Simulation,
+    draw: &mut impl Renderer,
+    (max_sensor_rays, colors): (usize, RayColors),
+    frame: &Frame,
+) {
+    // The mouse is drawn in the view of the layer it is on
+    let offset = layer_offset(&sim.maze, frame.layer);
+    let mouse = &sim.mouse;
+    let outline = Outline::new(sim, frame);
+    outline.fill(draw, Color::RED);
+    // Marks where the front is
+    let [left, tip, right] = outline.front;
+    draw.triangle([left, tip, right], Color::BLUE);
+
+    let mut groups: Vec<_> = mouse
+        .sensors
+        .values()
+        .filter_map(|sensor| sensor.group.as_ref())
+        .collect();
+    groups.sort();
+    groups.dedup();
+    let shown = frame.sensors.iter().filter(|reading| {
+        !mouse
+            .sensors
+            .get(&reading.name)
+            .is_some_and(|sensor| sensor.hide_ray)
+    });
+    for reading in shown.take(max_sensor_rays) {
+        let p1 = reading.origin + offset;
+        let p2 = reading.hit + offset;
+        draw.line(p1, p2, 2.0, ray_color(sim, &groups, reading, colors));
+    }
+
+    if frame.collided {
+        outline.cross_out(draw, Color::BLACK);
+    } else if frame.finished {
+        outline.cross_out(draw, Color::GREEN);
+    }
+}
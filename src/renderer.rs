@@ -0,0 +1,68 @@
+use notan::draw::*;
+use notan::{
+    app::Color,
+    math::{Mat3, Vec2},
+    prelude::{Graphics, RenderTexture},
+};
+
+/// The shapes the maze view is made of, so it can be drawn to the window, a texture or an image.
+pub trait Renderer {
+    fn clear(&mut self, color: Color);
+    /// Applies the matrix to everything drawn until the matching `pop_transform`.
+    fn push_transform(&mut self, matrix: Mat3);
+    fn pop_transform(&mut self);
+    fn rect(&mut self, position: Vec2, size: Vec2, color: Color);
+    fn triangle(&mut self, points: [Vec2; 3], color: Color);
+    fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color);
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color);
+}
+
+impl Renderer for Draw {
+    fn clear(&mut self, color: Color) {
+        Draw::clear(self, color);
+    }
+
+    fn push_transform(&mut self, matrix: Mat3) {
+        self.transform().push(matrix);
+    }
+
+    fn pop_transform(&mut self) {
+        self.transform().pop();
+    }
+
+    fn rect(&mut self, position: Vec2, size: Vec2, color: Color) {
+        DrawShapes::rect(self, position.into(), size.into())
+            .color(color)
+            .fill();
+    }
+
+    fn triangle(&mut self, [a, b, c]: [Vec2; 3], color: Color) {
+        DrawShapes::triangle(self, a.into(), b.into(), c.into()).color(color);
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color) {
+        DrawShapes::line(self, from.into(), to.into())
+            .width(width)
+            .color(color);
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        DrawShapes::circle(self, radius)
+            .position(center.x, center.y)
+            .color(color);
+    }
+}
+
+/// Draws into the texture instead of the window, with the matrix applied to the whole scene.
+pub fn render_to_texture(
+    gfx: &mut Graphics,
+    texture: &RenderTexture,
+    matrix: Mat3,
+    scene: impl FnOnce(&mut Draw),
+) {
+    let mut draw = texture.create_draw();
+    draw.push_transform(matrix);
+    scene(&mut draw);
+    draw.pop_transform();
+    gfx.render_to(texture, &draw);
+}
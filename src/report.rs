@@ -0,0 +1,298 @@
+use std::{io::Write, path::Path};
+
+use clap::ValueEnum;
+use mimosi_core::internal::error::Error;
+
+use crate::html::escape;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    /// An array of objects, one per row
+    Json,
+    /// A header and one line per row
+    Csv,
+}
+
+/// A single value of a report.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Text(String),
+    Integer(i64),
+    Number(f32, usize), // The value and how many decimals tables show
+    Bool(bool),
+    Missing,
+}
+
+impl Value {
+    pub fn number(value: f32, decimals: usize) -> Self {
+        Value::Number(value, decimals)
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, Value::Text(_))
+    }
+
+    fn to_table(&self) -> String {
+        match self {
+            Value::Text(text) => text.clone(),
+            Value::Integer(value) => value.to_string(),
+            Value::Number(value, decimals) => format!("{value:.decimals$}"),
+            Value::Bool(value) => String::from(if *value { "yes" } else { "no" }),
+            Value::Missing => String::from("-"),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        match self {
+            Value::Text(text) if text.contains([',', '"', '\n']) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            }
+            Value::Text(text) => text.clone(),
+            Value::Integer(value) => value.to_string(),
+            Value::Number(value, _) => value.to_string(),
+            Value::Bool(value) => value.to_string(),
+            Value::Missing => String::new(),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Text(text) => text.clone().into(),
+            Value::Integer(value) => (*value).into(),
+            Value::Number(value, _) => (*value).into(),
+            Value::Bool(value) => (*value).into(),
+            Value::Missing => serde_json::Value::Null,
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Missing, Into::into)
+    }
+}
+
+/// Turns `finish_time` into `Finish Time` for table headers.
+fn title(column: &str) -> String {
+    column
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Results of a subcommand as rows of values under named columns,
+/// so they can be printed for reading or for other programs.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Value>>,
+    record: bool,        // A single row, shown as one value per line in tables
+    header: Vec<String>, // Lines above the table, only shown in tables
+    footer: Vec<String>, // Lines below the table, only shown in tables
+}
+
+impl Report {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// A report of a single row. Tables show one value per line, JSON a single object.
+    pub fn record(values: Vec<(&'static str, Value)>) -> Self {
+        let (columns, row) = values.into_iter().unzip();
+        Self {
+            columns,
+            rows: vec![row],
+            record: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn header(&mut self, line: impl Into<String>) {
+        self.header.push(line.into());
+    }
+
+    pub fn footer(&mut self, line: impl Into<String>) {
+        self.footer.push(line.into());
+    }
+
+    pub fn print(&self, format: OutputFormat) -> Result<(), Error> {
+        self.write(&mut std::io::stdout().lock(), format)
+            .map_err(|e| Error::io(Path::new("stdout"), e))
+    }
+
+    pub fn write(&self, out: &mut impl Write, format: OutputFormat) -> std::io::Result<()> {
+        match format {
+            OutputFormat::Table => self.write_table(out),
+            OutputFormat::Json => self.write_json(out),
+            OutputFormat::Csv => self.write_csv(out),
+        }
+    }
+
+    fn write_table(&self, out: &mut impl Write) -> std::io::Result<()> {
+        for line in &self.header {
+            writeln!(out, "{line}")?;
+        }
+        let titles: Vec<String> = self.columns.iter().map(|c| title(c)).collect();
+        if self.record {
+            for (title, value) in titles.iter().zip(self.rows.iter().flatten()) {
+                writeln!(out, "{title}: {}", value.to_table())?;
+            }
+        } else {
+            let cells: Vec<Vec<String>> = self
+                .rows
+                .iter()
+                .map(|row| row.iter().map(Value::to_table).collect())
+                .collect();
+            let widths: Vec<usize> = titles
+                .iter()
+                .enumerate()
+                .map(|(i, title)| {
+                    cells
+                        .iter()
+                        .map(|row| row[i].chars().count())
+                        .fold(title.chars().count(), usize::max)
+                })
+                .collect();
+            // Text is aligned to the left, everything else to the right
+            let left: Vec<bool> = (0..self.columns.len())
+                .map(|i| self.rows.iter().any(|row| row[i].is_text()))
+                .collect();
+            let line = |fields: &[String]| {
+                fields
+                    .iter()
+                    .zip(&widths)
+                    .zip(&left)
+                    .map(|((field, &width), &left)| {
+                        if left {
+                            format!("{field:<width$}")
+                        } else {
+                            format!("{field:>width$}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ")
+                    .trim_end()
+                    .to_string()
+            };
+            writeln!(out, "{}", line(&titles))?;
+            for row in &cells {
+                writeln!(out, "{}", line(row))?;
+            }
+        }
+        for line in &self.footer {
+            writeln!(out, "{line}")?;
+        }
+        Ok(())
+    }
+
+    /// The report as an HTML table, with the header and footer as paragraphs.
+    pub fn to_html(&self) -> String {
+        let paragraphs = |lines: &[String]| -> String {
+            lines
+                .iter()
+                .filter(|line| !line.is_empty())
+                .map(|line| format!("<p>{}</p>\n", escape(line)))
+                .collect()
+        };
+        let mut html = paragraphs(&self.header);
+        html.push_str("<table>\n<tr>");
+        for column in &self.columns {
+            html.push_str(&format!("<th>{}</th>", escape(&title(column))));
+        }
+        html.push_str("</tr>\n");
+        for row in &self.rows {
+            html.push_str("<tr>");
+            for value in row {
+                let style = if value.is_text() {
+                    " style=\"text-align: left\""
+                } else {
+                    ""
+                };
+                html.push_str(&format!("<td{style}>{}</td>", escape(&value.to_table())));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html.push_str(&paragraphs(&self.footer));
+        html
+    }
+
+    fn write_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let objects: Vec<serde_json::Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let object = self
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, value)| (column.to_string(), value.to_json()))
+                    .collect();
+                serde_json::Value::Object(object)
+            })
+            .collect();
+        let json = match (self.record, objects.first()) {
+            (true, Some(object)) => object.clone(),
+            _ => serde_json::Value::Array(objects),
+        };
+        serde_json::to_writer_pretty(&mut *out, &json)?;
+        writeln!(out)
+    }
+
+    fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "{}", self.columns.join(","))?;
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(Value::to_csv).collect();
+            writeln!(out, "{}", fields.join(","))?;
+        }
+        Ok(())
+    }
+}
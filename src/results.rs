@@ -0,0 +1,171 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+
+use mimosi_core::internal::{
+    error::{self, Error},
+    run_state::RunState,
+    simulation::Simulation,
+};
+
+use crate::report::Value;
+
+/// Smallest and largest reading of a sensor during a run, in mm.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct SensorRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// What happened in a run, for analysis scripts and leaderboards.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RunResult {
+    pub maze: String,
+    pub maze_name: Option<String>, // From the NAME header of the maze
+    pub script: String,
+    pub seed: u64, // Seed of the run, to reproduce its noise with `--seed`
+    pub finish_time: Option<f32>, // Run time in seconds, if the mouse finished
+    pub crashed: bool,
+    pub crash_position: Option<[f32; 2]>, // In cells from the top left corner of the maze
+    pub wall_touches: usize,
+    pub path_length: f32, // m
+    pub coverage: f32,    // Percent of the reachable cells visited
+    pub sensors: BTreeMap<String, SensorRange>,
+    pub controller_calls: u64,
+    pub call_time_mean: f32, // ms
+    pub call_time_max: f32,  // ms
+    pub error: Option<String>,
+    pub run: RunState, // Phase and score at the end
+}
+
+impl RunResult {
+    pub fn new(sim: &Simulation, maze: String, script: String) -> Self {
+        let units_per_mm = 1.0 / (sim.maze.meters_per_unit() * 1000.0);
+        let mut sensors = BTreeMap::new();
+        for reading in sim.recorder.frames.iter().flat_map(|f| &f.sensors) {
+            let value = reading.value / units_per_mm;
+            sensors
+                .entry(reading.name.clone())
+                .and_modify(|range: &mut SensorRange| {
+                    range.min = range.min.min(value);
+                    range.max = range.max.max(value);
+                })
+                .or_insert(SensorRange {
+                    min: value,
+                    max: value,
+                });
+        }
+        let position = sim.mouse.position / sim.maze.cell_size;
+        Self {
+            maze,
+            maze_name: sim.maze.name.clone(),
+            script,
+            seed: sim.seed,
+            finish_time: sim.finished.then_some(sim.run_time()),
+            crashed: sim.collided,
+            crash_position: sim.collided.then_some(position.into()),
+            wall_touches: sim.wall_touches,
+            path_length: sim.path_length(),
+            coverage: sim.coverage() * 100.0,
+            sensors,
+            controller_calls: sim.calls.calls,
+            call_time_mean: sim.calls.mean().as_secs_f32() * 1000.0,
+            call_time_max: sim.calls.slowest.as_secs_f32() * 1000.0,
+            error: None,
+            run: sim.run_state(),
+        }
+    }
+
+    /// A run that couldn't be done at all.
+    pub fn failed(maze: String, script: String, error: String) -> Self {
+        Self {
+            maze,
+            script,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes the results as CSV if the path ends in `.csv`, otherwise as JSON.
+///
+/// CSV has a pair of columns for the smallest and largest reading of every sensor of any run.
+pub fn write_results(path: &Path, results: &[RunResult]) -> Result<(), Error> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let text = if is_csv {
+        to_csv(results)
+    } else {
+        serde_json::to_string_pretty(results).map_err(|e| Error::format(path.display(), e))? + "\n"
+    };
+    error::write(path, text)
+}
+
+fn to_csv(results: &[RunResult]) -> String {
+    let mut sensors: Vec<&String> = results.iter().flat_map(|r| r.sensors.keys()).collect();
+    sensors.sort();
+    sensors.dedup();
+
+    let mut columns: Vec<String> = [
+        "maze",
+        "maze_name",
+        "script",
+        "seed",
+        "finish_time",
+        "crashed",
+        "crash_x",
+        "crash_y",
+        "wall_touches",
+        "path_length",
+        "coverage",
+        "controller_calls",
+        "call_time_mean",
+        "call_time_max",
+        "error",
+        "phase",
+        "runs",
+        "best_score",
+    ]
+    .map(String::from)
+    .to_vec();
+    for name in &sensors {
+        columns.push(format!("{name}_min"));
+        columns.push(format!("{name}_max"));
+    }
+    let mut csv = columns.join(",") + "\n";
+
+    for result in results {
+        let mut row: Vec<Value> = vec![
+            result.maze.as_str().into(),
+            result.maze_name.clone().into(),
+            result.script.as_str().into(),
+            result.seed.into(),
+            result.finish_time.map(|t| Value::number(t, 3)).into(),
+            result.crashed.into(),
+            result.crash_position.map(|p| Value::number(p[0], 2)).into(),
+            result.crash_position.map(|p| Value::number(p[1], 2)).into(),
+            result.wall_touches.into(),
+            Value::number(result.path_length, 3),
+            Value::number(result.coverage, 1),
+            result.controller_calls.into(),
+            Value::number(result.call_time_mean, 3),
+            Value::number(result.call_time_max, 3),
+            result.error.clone().into(),
+            result.run.phase.as_str().into(),
+            Value::Integer(result.run.runs),
+            (result.run.best_score >= 0.0)
+                .then(|| Value::number(result.run.best_score, 2))
+                .into(),
+        ];
+        for name in &sensors {
+            let range = result.sensors.get(*name);
+            row.push(range.map(|r| Value::number(r.min, 1)).into());
+            row.push(range.map(|r| Value::number(r.max, 1)).into());
+        }
+        let fields: Vec<String> = row.iter().map(Value::to_csv).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
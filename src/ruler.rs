@@ -0,0 +1,60 @@
+use notan::{app::Color, math::Vec2};
+
+use crate::renderer::Renderer;
+
+/// Measures distances and angles between two points in the maze.
+#[derive(Debug, Default)]
+pub struct Ruler {
+    pub enabled: bool,
+    pub start: Option<Vec2>,
+    pub end: Option<Vec2>,
+    pub cursor: Vec2,
+}
+
+impl Ruler {
+    /// Sets the next point of the measurement. A third click starts a new one.
+    pub fn click(&mut self, point: Vec2) {
+        match (self.start, self.end) {
+            (Some(_), None) => self.end = Some(point),
+            _ => {
+                self.start = Some(point);
+                self.end = None;
+            }
+        }
+    }
+
+    /// The end of the measurement, following the cursor until the second click.
+    fn current_end(&self) -> Vec2 {
+        self.end.unwrap_or(self.cursor)
+    }
+
+    pub fn distance(&self) -> Option<f32> {
+        self.start.map(|start| start.distance(self.current_end()))
+    }
+
+    /// Angle of the measured line in degrees, using the same convention as the mouse orientation.
+    pub fn angle(&self) -> Option<f32> {
+        self.start.map(|start| {
+            let delta = self.current_end() - start;
+            let angle = delta.y.atan2(delta.x).to_degrees();
+            if angle < 0.0 {
+                angle + 360.0
+            } else {
+                angle
+            }
+        })
+    }
+
+    pub fn render(&self, draw: &mut impl Renderer) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(start) = self.start {
+            let end = self.current_end();
+            draw.line(start, end, 1.0, Color::YELLOW);
+            for p in [start, end] {
+                draw.circle(p, 2.0, Color::YELLOW);
+            }
+        }
+    }
+}
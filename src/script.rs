@@ -0,0 +1,90 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use rhai::{Engine, EvalAltResult, ParseError, Position, AST};
+
+/// The script shown in the window, loaded again whenever its file changes.
+pub struct ScriptFile {
+    pub name: String,
+    path: Option<PathBuf>, // None for the example script
+    source: String,
+    modified: Option<SystemTime>,
+}
+
+fn modified(path: &Option<PathBuf>) -> Option<SystemTime> {
+    std::fs::metadata(path.as_ref()?).ok()?.modified().ok()
+}
+
+impl ScriptFile {
+    pub fn new(path: Option<PathBuf>, source: String) -> Self {
+        Self {
+            name: path
+                .as_ref()
+                .map_or(String::from("example.rhai"), |p| p.display().to_string()),
+            modified: modified(&path),
+            path,
+            source,
+        }
+    }
+
+    /// A script dropped into the window, which has no file to watch.
+    pub fn dropped(name: String, source: String) -> Self {
+        Self {
+            name,
+            path: None,
+            source,
+            modified: None,
+        }
+    }
+
+    pub fn is_watched(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Formats an error like a compiler, with the line of the script it is in and a marker under the column.
+    fn describe(&self, message: &str, position: Position) -> String {
+        let Some(line) = position.line() else {
+            return format!("{}: {message}", self.name);
+        };
+        let column = position.position().unwrap_or(1);
+        let mut text = format!("{}:{line}:{column}: {message}", self.name);
+        if let Some(code) = self.source.lines().nth(line - 1) {
+            let margin = " ".repeat(line.to_string().len());
+            // Rhai counts a tab as one column
+            let code = code.replace('\t', " ");
+            text += &format!("\n{line} | {code}\n{margin} | {:>column$}", "^");
+        }
+        text
+    }
+
+    pub fn compile_error(&self, error: ParseError) -> String {
+        self.describe(&error.0.to_string(), error.1)
+    }
+
+    pub fn runtime_error(&self, mut error: Box<EvalAltResult>) -> String {
+        let position = error.take_position();
+        self.describe(&error.to_string(), position)
+    }
+
+    pub fn compile(&self, engine: &Engine) -> Result<AST, String> {
+        engine
+            .compile(&self.source)
+            .map_err(|e| self.compile_error(e))
+    }
+
+    /// Reads the file again if it was saved since the last time, returning the new script compiled.
+    pub fn reload(&mut self, engine: &Engine) -> Option<Result<AST, String>> {
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        let source = std::fs::read_to_string(self.path.as_ref()?);
+        Some(match source {
+            Ok(source) => {
+                self.source = source;
+                self.compile(engine)
+            }
+            Err(e) => Err(format!("{}: {e}", self.name)),
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    net::{IpAddr, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+use tungstenite::{HandshakeError, Message, WebSocket};
+
+use mimosi_core::internal::{
+    error::Error, events::TimedEvent, replay::Frame, simulation::Simulation,
+};
+
+/// How long the server waits for telemetry before looking for new clients.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+/// Clients that can't take a message for this long are dropped, so they don't hold up the others.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Clients that don't finish the handshake in this time are turned away, so they can't stall the server.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Messages waiting for the server thread, newer ones are dropped while it is this far behind.
+const QUEUE_SIZE: usize = 1024;
+
+/// The message sent for every physics step.
+#[derive(Serialize)]
+struct Tick<'a> {
+    tick: u64,
+    #[serde(flatten)]
+    frame: &'a Frame, // Pose, sensors and powers
+    events: &'a [TimedEvent], // Events since the previous step
+}
+
+/// Streams the telemetry of every physics step as JSON to WebSocket clients, e.g. a plotter in the browser.
+///
+/// The clients are served on a thread of their own, so the simulation never waits for them.
+/// When they can't keep up, steps are skipped instead of queueing up without limit.
+pub struct TelemetryStream {
+    sender: SyncSender<String>,
+    events: usize, // Events of the simulation that were already sent
+}
+
+impl TelemetryStream {
+    /// Listens on the given address, which should only be reachable from other machines on purpose.
+    pub fn listen(address: IpAddr, port: u16) -> Result<Self, Error> {
+        let listener = TcpListener::bind((address, port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .and_then(|listener| listener.local_addr().map(|local| (listener, local)));
+        let (listener, local) = listener.map_err(|e| Error::Network {
+            address: format!("{address}:{port}"),
+            message: format!("Could not stream telemetry: {e}"),
+        })?;
+        eprintln!("Streaming telemetry on ws://{local}");
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_SIZE);
+        thread::spawn(move || serve(listener, receiver));
+        Ok(Self { sender, events: 0 })
+    }
+
+    /// Sends the last frame of the simulation with the events that happened since the previous one.
+    pub fn send(&mut self, sim: &Simulation) {
+        let Some(frame) = sim.recorder.frames.last() else {
+            return;
+        };
+        // Restoring a snapshot removes events, some of which may have been sent already
+        let events = &sim.events[self.events.min(sim.events.len())..];
+        self.events = sim.events.len();
+        let tick = Tick {
+            tick: sim.tick,
+            frame,
+            events,
+        };
+        if let Ok(json) = serde_json::to_string(&tick) {
+            // A full queue drops the step, the clients are behind anyway and will see the next ones.
+            // Failing otherwise means the server thread is gone, so there is no one to send to.
+            let _ = self.sender.try_send(json);
+        }
+    }
+}
+
+fn accept(stream: TcpStream) -> Result<WebSocket<TcpStream>, String> {
+    stream
+        .set_nonblocking(false)
+        .and_then(|_| stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)))
+        .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+        .map_err(|e| format!("{e}"))?;
+    tungstenite::accept(stream).map_err(|e| match e {
+        HandshakeError::Interrupted(_) => String::from("The handshake timed out"),
+        HandshakeError::Failure(e) => format!("{e}"),
+    })
+}
+
+/// Accepts clients and passes every message on to all of them, until the stream is dropped.
+fn serve(listener: TcpListener, messages: Receiver<String>) {
+    let mut clients: Vec<WebSocket<TcpStream>> = Vec::new();
+    loop {
+        while let Ok((stream, address)) = listener.accept() {
+            match accept(stream) {
+                Ok(client) => {
+                    eprintln!("Telemetry client connected from {address}");
+                    clients.push(client);
+                }
+                Err(e) => eprintln!("Warning: Telemetry client {address} could not connect: {e}"),
+            }
+        }
+        let message = match messages.recv_timeout(ACCEPT_INTERVAL) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        clients.retain_mut(|client| client.send(Message::text(message.clone())).is_ok());
+    }
+}
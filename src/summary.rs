@@ -0,0 +1,272 @@
+use mazeparser::{
+    grid::{EAST, NORTH, SOUTH, WEST},
+    path::{self, diagonal_path},
+};
+use notan::{
+    egui::{self, Color32, Context, Pos2, Rect, Sense, Stroke},
+    math::Vec2,
+};
+
+use mimosi_core::internal::{events::Event, simulation::Simulation};
+
+use crate::png;
+
+/// Pixels per cell of the trajectory thumbnail.
+const THUMBNAIL_CELL: usize = 12;
+/// Height of the speed chart in the saved image.
+const CHART_HEIGHT: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Finished,
+    Crashed,
+    TimedOut,
+}
+
+impl RunOutcome {
+    /// How the simulation ended, if it did.
+    pub fn of(sim: &Simulation) -> Option<Self> {
+        if sim.collided {
+            Some(RunOutcome::Crashed)
+        } else if sim.finished {
+            Some(RunOutcome::Finished)
+        } else if sim.timed_out() {
+            Some(RunOutcome::TimedOut)
+        } else {
+            None
+        }
+    }
+}
+
+/// Key numbers of a run, shown when it ends.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub outcome: RunOutcome,
+    pub time: f32,
+    pub top_speed: f32,     // m/s
+    pub average_speed: f32, // m/s
+    pub wall_touches: usize,
+    pub energy: f32,                 // Sum of the squared wheel powers over time
+    pub path_length: f32,            // m
+    pub optimal_length: Option<f32>, // m, the shortest path using diagonals
+    pub coverage: f32,
+    trajectory: Vec<Vec2>,    // In cells
+    speeds: Vec<(f32, f32)>,  // Time and speed in m/s
+    walls: Vec<(Vec2, Vec2)>, // In cells
+    size: (usize, usize),
+}
+
+impl Summary {
+    pub fn new(sim: &Simulation, outcome: RunOutcome) -> Self {
+        let meters = sim.maze.meters_per_unit();
+        // Only the timed run counts, not the calibration before it
+        let frames = &sim.recorder.frames;
+        let frames = &frames[frames.partition_point(|f| f.calibrating)..];
+
+        let mut path_length = 0.0;
+        let mut top_speed = 0.0f32;
+        let mut energy = 0.0;
+        let mut speeds = Vec::new();
+        for pair in frames.windows(2) {
+            let dt = pair[1].time - pair[0].time;
+            if dt <= 0.0 {
+                continue;
+            }
+            let distance = pair[0].position.distance(pair[1].position) * meters;
+            path_length += distance;
+            top_speed = top_speed.max(distance / dt);
+            speeds.push((pair[1].time - sim.run_start, distance / dt));
+            energy += (pair[1].left_power.powi(2) + pair[1].right_power.powi(2)) * dt;
+        }
+        let time = sim.run_time();
+
+        let grid = &sim.maze.grid;
+        let optimal_length = grid
+            .shortest_path(sim.maze.start_cell, &sim.maze.finish_cells)
+            .map(|cells| path::path_length(&diagonal_path(&cells)) * sim.maze.cell_size_meters);
+
+        let mut walls = Vec::new();
+        for (x, y) in grid.cells() {
+            let (left, top) = (x as f32, y as f32);
+            let (right, bottom) = (left + 1.0, top + 1.0);
+            for (direction, (x1, y1, x2, y2)) in [
+                (NORTH, (left, top, right, top)),
+                (EAST, (right, top, right, bottom)),
+                (SOUTH, (left, bottom, right, bottom)),
+                (WEST, (left, top, left, bottom)),
+            ] {
+                if grid.has_wall((x, y), direction) {
+                    walls.push((Vec2::new(x1, y1), Vec2::new(x2, y2)));
+                }
+            }
+        }
+
+        // Keep the thumbnail and the chart light, a few hundred points are plenty
+        let every = (frames.len() / 500).max(1);
+        let speeds = speeds.into_iter().step_by(every).collect();
+        Self {
+            outcome,
+            time,
+            top_speed,
+            average_speed: if time > 0.0 { path_length / time } else { 0.0 },
+            wall_touches: sim
+                .events
+                .iter()
+                .filter(|e| matches!(e.event, Event::Crash | Event::WallTouch))
+                .count(),
+            energy,
+            path_length,
+            optimal_length,
+            coverage: sim.coverage(),
+            trajectory: frames
+                .iter()
+                .step_by(every)
+                .map(|f| f.position / sim.maze.cell_size)
+                .collect(),
+            speeds,
+            walls,
+            size: (grid.width, grid.height),
+        }
+    }
+
+    /// The stats as label and value, in the order they are shown.
+    pub fn stats(&self) -> Vec<(&'static str, String)> {
+        let mut stats = vec![
+            ("Outcome", format!("{:?}", self.outcome)),
+            ("Time", format!("{:.2}s", self.time)),
+            ("Top Speed", format!("{:.2} m/s", self.top_speed)),
+            ("Average Speed", format!("{:.2} m/s", self.average_speed)),
+            ("Wall Touches", format!("{}", self.wall_touches)),
+            ("Energy", format!("{:.1}", self.energy)),
+            ("Path Length", format!("{:.2} m", self.path_length)),
+        ];
+        if let Some(optimal) = self.optimal_length {
+            stats.push(("Optimal Length", format!("{optimal:.2} m")));
+        }
+        stats.push(("Coverage", format!("{:.1}%", self.coverage * 100.0)));
+        stats
+    }
+
+    /// Shows the summary in a window. Returns true if it should be saved.
+    pub fn show(&self, ctx: &Context, open: &mut bool) -> bool {
+        let mut save = false;
+        egui::Window::new("Run Summary")
+            .open(open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("summary").show(ui, |ui| {
+                    for (label, value) in self.stats() {
+                        ui.label(label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                self.paint_thumbnail(ui);
+                self.paint_chart(ui);
+                save = ui.button("Save as PNG (P)").clicked();
+            });
+        save
+    }
+
+    fn paint_thumbnail(&self, ui: &mut egui::Ui) {
+        let scale = 200.0 / self.size.0.max(self.size.1).max(1) as f32;
+        let size = egui::vec2(self.size.0 as f32, self.size.1 as f32) * scale;
+        let (response, painter) = ui.allocate_painter(size, Sense::hover());
+        let origin = response.rect.min;
+        let to_screen = |p: Vec2| origin + egui::vec2(p.x, p.y) * scale;
+        painter.rect_filled(response.rect, 0.0, Color32::WHITE);
+        for (a, b) in &self.walls {
+            painter.line_segment(
+                [to_screen(*a), to_screen(*b)],
+                Stroke::new(1.5, Color32::BLACK),
+            );
+        }
+        let points: Vec<Pos2> = self.trajectory.iter().map(|p| to_screen(*p)).collect();
+        painter.add(egui::Shape::line(points, Stroke::new(1.5, Color32::BLUE)));
+    }
+
+    fn paint_chart(&self, ui: &mut egui::Ui) {
+        ui.label("Speed");
+        let (response, painter) = ui.allocate_painter(egui::vec2(200.0, 50.0), Sense::hover());
+        let rect: Rect = response.rect;
+        painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::GRAY));
+        let max_speed = self.top_speed.max(f32::EPSILON);
+        let duration = self.time.max(f32::EPSILON);
+        let points: Vec<Pos2> = self
+            .speeds
+            .iter()
+            .map(|(time, speed)| {
+                Pos2::new(
+                    rect.left() + time / duration * rect.width(),
+                    rect.bottom() - speed / max_speed * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, Stroke::new(1.0, Color32::GREEN)));
+    }
+
+    /// Renders the trajectory and the speed chart as a PNG, with the stats as text entries.
+    pub fn to_png(&self) -> Vec<u8> {
+        let width = self.size.0 * THUMBNAIL_CELL + 1;
+        let maze_height = self.size.1 * THUMBNAIL_CELL + 1;
+        let height = maze_height + CHART_HEIGHT;
+        let mut image = Image {
+            width,
+            height,
+            pixels: vec![255; width * height * 3],
+        };
+
+        let cell = THUMBNAIL_CELL as f32;
+        for (a, b) in &self.walls {
+            image.line(*a * cell, *b * cell, [0, 0, 0]);
+        }
+        for pair in self.trajectory.windows(2) {
+            image.line(pair[0] * cell, pair[1] * cell, [0, 0, 255]);
+        }
+
+        let bottom = (height - 1) as f32;
+        let max_speed = self.top_speed.max(f32::EPSILON);
+        let duration = self.time.max(f32::EPSILON);
+        let to_chart = |(time, speed): (f32, f32)| {
+            Vec2::new(
+                time / duration * (width - 1) as f32,
+                bottom - speed / max_speed * (CHART_HEIGHT - 2) as f32,
+            )
+        };
+        image.line(
+            Vec2::new(0.0, maze_height as f32),
+            Vec2::new((width - 1) as f32, maze_height as f32),
+            [128, 128, 128],
+        );
+        for pair in self.speeds.windows(2) {
+            image.line(to_chart(pair[0]), to_chart(pair[1]), [0, 160, 0]);
+        }
+
+        let text: Vec<(&str, String)> = self.stats();
+        png::encode_rgb(width as u32, height as u32, &image.pixels, &text)
+    }
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn line(&mut self, a: Vec2, b: Vec2, color: [u8; 3]) {
+        let steps = a.distance(b).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let p = a.lerp(b, i as f32 / steps as f32).round();
+            if p.x < 0.0 || p.y < 0.0 {
+                continue;
+            }
+            let (x, y) = (p.x as usize, p.y as usize);
+            if x < self.width && y < self.height {
+                let index = (y * self.width + x) * 3;
+                self.pixels[index..index + 3].copy_from_slice(&color);
+            }
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use mimosi_core::internal::{
+    channels::ChannelValue,
+    error::Error,
+    replay::{Frame, SensorFrame},
+};
+use notan::math::vec2;
+
+const MAGIC: &[u8; 4] = b"MMTQ";
+const VERSION: u8 = 1;
+
+/// Fixed point scales used by the quantized format.
+const TIME_SCALE: f32 = 1000.0; // ms
+const POSITION_SCALE: f32 = 100.0;
+const ANGLE_SCALE: f32 = 10000.0;
+const POWER_SCALE: f32 = 1000.0;
+const VALUE_SCALE: f32 = 100.0;
+const CHANNEL_SCALE: f32 = 1000.0;
+
+/// Fixed point values reserved for floats that have none, like the reading of a saturated sensor.
+const INFINITY: i64 = i64::MAX;
+const NEG_INFINITY: i64 = i64::MIN;
+const NAN: i64 = i64::MIN + 1;
+
+/// The float in fixed point, keeping clear of the reserved values.
+fn to_fixed(value: f32, scale: f32) -> i64 {
+    match value {
+        f32::INFINITY => INFINITY,
+        f32::NEG_INFINITY => NEG_INFINITY,
+        _ if value.is_nan() => NAN,
+        _ => ((value * scale).round() as i64).clamp(NAN + 1, INFINITY - 1),
+    }
+}
+
+fn from_fixed(value: i64, scale: f32) -> f32 {
+    match value {
+        INFINITY => f32::INFINITY,
+        NEG_INFINITY => f32::NEG_INFINITY,
+        NAN => f32::NAN,
+        _ => value as f32 / scale,
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    /// Full precision, one line per tick
+    #[default]
+    Csv,
+    /// Fixed point values, delta encoded between ticks
+    Quantized,
+}
+
+/// Recorded frames together with the experiment they belong to.
+#[derive(Debug, Clone, Default)]
+pub struct Telemetry {
+    pub tag: Option<String>,
+    pub frames: Vec<Frame>,
+}
+
+pub fn write_telemetry(
+    path: &Path,
+    telemetry: &Telemetry,
+    format: TelemetryFormat,
+) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    match format {
+        TelemetryFormat::Csv => write_csv(&mut file, telemetry)?,
+        TelemetryFormat::Quantized => write_quantized(&mut file, telemetry)?,
+    }
+    file.flush()
+}
+
+/// Reads telemetry in any of the supported formats.
+pub fn read_telemetry(path: &Path) -> Result<Telemetry, Error> {
+    let bytes = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+    if bytes.starts_with(MAGIC) {
+        read_quantized(&mut &bytes[MAGIC.len()..])
+    } else {
+        read_csv(BufReader::new(bytes.as_slice()))
+    }
+    .map_err(|e| Error::format(path.display(), e))
+}
+
+fn sensor_names(frames: &[Frame]) -> Vec<String> {
+    frames
+        .first()
+        .map(|f| f.sensors.iter().map(|s| s.name.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Names of all channels the script logged, sorted.
+fn channel_names(frames: &[Frame]) -> Vec<String> {
+    let names: BTreeSet<&String> = frames
+        .iter()
+        .flat_map(|f| f.channels.iter().map(|c| &c.name))
+        .collect();
+    names.into_iter().cloned().collect()
+}
+
+fn channel_value(frame: &Frame, name: &str) -> Option<f32> {
+    frame
+        .channels
+        .binary_search_by(|c| c.name.as_str().cmp(name))
+        .ok()
+        .map(|i| frame.channels[i].value)
+}
+
+pub fn write_csv(
+    out: &mut impl Write,
+    Telemetry { tag, frames }: &Telemetry,
+) -> std::io::Result<()> {
+    let names = sensor_names(frames);
+    let channels = channel_names(frames);
+    if let Some(tag) = tag {
+        writeln!(out, "# tag: {tag}")?;
+    }
+    write!(
+        out,
+        "time,x,y,orientation,left_power,right_power,collided,finished"
+    )?;
+    for name in &names {
+        write!(
+            out,
+            ",{name}_value,{name}_origin_x,{name}_origin_y,{name}_hit_x,{name}_hit_y"
+        )?;
+    }
+    for name in &channels {
+        write!(out, ",channel:{name}")?;
+    }
+    writeln!(out, ",calibrating,layer,in_start,in_goal,wall_touches")?;
+    for f in frames {
+        write!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            f.time,
+            f.position.x,
+            f.position.y,
+            f.orientation,
+            f.left_power,
+            f.right_power,
+            f.collided,
+            f.finished
+        )?;
+        for s in &f.sensors {
+            write!(
+                out,
+                ",{},{},{},{},{}",
+                s.value, s.origin.x, s.origin.y, s.hit.x, s.hit.y
+            )?;
+        }
+        for name in &channels {
+            // Channels the script didn't log yet stay empty
+            match channel_value(f, name) {
+                Some(value) => write!(out, ",{value}")?,
+                None => write!(out, ",")?,
+            }
+        }
+        writeln!(
+            out,
+            ",{},{},{},{},{}",
+            f.calibrating, f.layer, f.in_start, f.in_goal, f.wall_touches
+        )?;
+    }
+    Ok(())
+}
+
+fn read_csv(input: impl BufRead) -> Result<Telemetry, String> {
+    let mut lines = input.lines().enumerate();
+    let mut tag = None;
+    let header = loop {
+        let (_, line) = lines.next().ok_or("Empty telemetry file")?;
+        let line = line.map_err(|e| e.to_string())?;
+        match line.strip_prefix("# tag:") {
+            Some(t) => tag = Some(t.trim().to_string()),
+            None => break line,
+        }
+    };
+    let names: Vec<String> = header
+        .split(',')
+        .filter(|c| !c.starts_with("channel:"))
+        .filter_map(|c| c.strip_suffix("_value"))
+        .map(String::from)
+        .collect();
+    let channels: Vec<(usize, String)> = header
+        .split(',')
+        .enumerate()
+        .filter_map(|(i, c)| c.strip_prefix("channel:").map(|name| (i, name.to_string())))
+        .collect();
+    // Files written before the calibration phase existed don't have the column
+    let calibrating = header.split(',').position(|c| c == "calibrating");
+    let layer = header.split(',').position(|c| c == "layer");
+    let in_start = header.split(',').position(|c| c == "in_start");
+    let in_goal = header.split(',').position(|c| c == "in_goal");
+    let wall_touches = header.split(',').position(|c| c == "wall_touches");
+
+    let mut frames = Vec::new();
+    for (i, line) in lines {
+        let line = line.map_err(|e| e.to_string())?;
+        let columns: Vec<&str> = line.split(',').collect();
+        let number = |index: usize| -> Result<f32, String> {
+            columns
+                .get(index)
+                .ok_or_else(|| format!("Error in line {}! Missing column {index}", i + 1))?
+                .parse()
+                .map_err(|e| format!("Error in line {}! {e}", i + 1))
+        };
+        let flag = |index: usize| columns.get(index) == Some(&"true");
+        let sensors = names
+            .iter()
+            .enumerate()
+            .map(|(n, name)| {
+                let base = 8 + n * 5;
+                Ok(SensorFrame {
+                    name: name.clone(),
+                    value: number(base)?,
+                    origin: vec2(number(base + 1)?, number(base + 2)?),
+                    hit: vec2(number(base + 3)?, number(base + 4)?),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        let channels = channels
+            .iter()
+            .filter(|(index, _)| columns.get(*index).is_some_and(|c| !c.is_empty()))
+            .map(|(index, name)| {
+                Ok(ChannelValue {
+                    name: name.clone(),
+                    value: number(*index)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        frames.push(Frame {
+            time: number(0)?,
+            position: vec2(number(1)?, number(2)?),
+            orientation: number(3)?,
+            left_power: number(4)?,
+            right_power: number(5)?,
+            collided: flag(6),
+            finished: flag(7),
+            calibrating: calibrating.is_some_and(flag),
+            sensors,
+            channels,
+            layer: layer.map_or(Ok(0.0), number)? as usize,
+            in_start: in_start.is_some_and(flag),
+            in_goal: in_goal.is_some_and(flag),
+            wall_touches: wall_touches.map_or(Ok(0.0), number)? as usize,
+        });
+    }
+    Ok(Telemetry { tag, frames })
+}
+
+fn write_varint(out: &mut impl Write, value: i64) -> std::io::Result<()> {
+    // Zigzag encoding keeps small negative deltas small
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(input: &mut impl Read) -> Result<i64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        input.read_exact(&mut byte).map_err(|e| e.to_string())?;
+        value |= ((byte[0] & 0x7F) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(((value >> 1) as i64) ^ -((value & 1) as i64));
+        }
+    }
+    Err(String::from("Invalid number in telemetry file"))
+}
+
+/// Values of a frame before its sensors: the pose, the powers, the flags and the layer.
+const HEAD: usize = 8;
+
+/// The quantized values of a frame, in the order they are written.
+fn quantize(f: &Frame) -> Vec<i64> {
+    let q = to_fixed;
+    let mut values = vec![
+        q(f.time, TIME_SCALE),
+        q(f.position.x, POSITION_SCALE),
+        q(f.position.y, POSITION_SCALE),
+        q(f.orientation, ANGLE_SCALE),
+        q(f.left_power, POWER_SCALE),
+        q(f.right_power, POWER_SCALE),
+        f.collided as i64
+            | (f.finished as i64) << 1
+            | (f.calibrating as i64) << 2
+            | (f.in_start as i64) << 3
+            | (f.in_goal as i64) << 4,
+        f.layer as i64,
+    ];
+    for s in &f.sensors {
+        values.extend([
+            q(s.value, VALUE_SCALE),
+            q(s.origin.x, POSITION_SCALE),
+            q(s.origin.y, POSITION_SCALE),
+            q(s.hit.x, POSITION_SCALE),
+            q(s.hit.y, POSITION_SCALE),
+        ]);
+    }
+    values.push(f.wall_touches as i64);
+    values
+}
+
+pub fn write_quantized(
+    out: &mut impl Write,
+    Telemetry { tag, frames }: &Telemetry,
+) -> std::io::Result<()> {
+    let names = sensor_names(frames);
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    // An empty tag means the run wasn't tagged
+    let tag = tag.as_deref().unwrap_or_default();
+    write_varint(out, tag.len() as i64)?;
+    out.write_all(tag.as_bytes())?;
+    write_varint(out, names.len() as i64)?;
+    for name in &names {
+        write_varint(out, name.len() as i64)?;
+        out.write_all(name.as_bytes())?;
+    }
+    // Channels are written from the first frame they were logged in
+    let channels: Vec<(String, usize)> = channel_names(frames)
+        .into_iter()
+        .map(|name| {
+            let first = frames
+                .iter()
+                .position(|f| channel_value(f, &name).is_some())
+                .unwrap_or(frames.len());
+            (name, first)
+        })
+        .collect();
+    write_varint(out, channels.len() as i64)?;
+    for (name, first) in &channels {
+        write_varint(out, name.len() as i64)?;
+        out.write_all(name.as_bytes())?;
+        write_varint(out, *first as i64)?;
+    }
+    write_varint(out, frames.len() as i64)?;
+
+    let fixed = HEAD + names.len() * 5 + 1; // The wall touches follow the sensors
+    let mut previous = vec![0; fixed + channels.len()];
+    for (i, frame) in frames.iter().enumerate() {
+        let mut values = quantize(frame);
+        for (n, (name, first)) in channels.iter().enumerate() {
+            // A channel keeps its last value in frames that miss it
+            values.push(match channel_value(frame, name) {
+                Some(value) => to_fixed(value, CHANNEL_SCALE),
+                None => previous[fixed + n],
+            });
+            if i < *first {
+                values[fixed + n] = 0;
+            }
+        }
+        for (n, (value, previous)) in values.iter().zip(previous.iter()).enumerate() {
+            if n < fixed || i >= channels[n - fixed].1 {
+                // Wrapping, as the deltas to and from the reserved values don't fit
+                write_varint(out, value.wrapping_sub(*previous))?;
+            }
+        }
+        previous = values;
+    }
+    Ok(())
+}
+
+fn read_string(input: &mut impl Read) -> Result<String, String> {
+    let mut bytes = vec![0u8; read_varint(input)? as usize];
+    input.read_exact(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn read_quantized(input: &mut impl Read) -> Result<Telemetry, String> {
+    let mut version = [0u8];
+    input.read_exact(&mut version).map_err(|e| e.to_string())?;
+    if version[0] != VERSION {
+        return Err(format!("Unsupported telemetry version {}", version[0]));
+    }
+    let tag = Some(read_string(input)?).filter(|tag| !tag.is_empty());
+    let mut names = Vec::new();
+    for _ in 0..read_varint(input)? {
+        names.push(read_string(input)?);
+    }
+    let mut channels = Vec::new();
+    for _ in 0..read_varint(input)? {
+        channels.push((read_string(input)?, read_varint(input)? as usize));
+    }
+
+    let count = read_varint(input)?;
+    let touches = HEAD + names.len() * 5;
+    let fixed = touches + 1;
+    let mut values = vec![0i64; fixed + channels.len()];
+    let mut frames = Vec::new();
+    for i in 0..count as usize {
+        for (n, value) in values.iter_mut().enumerate() {
+            if n < fixed || i >= channels[n - fixed].1 {
+                *value = value.wrapping_add(read_varint(input)?);
+            }
+        }
+        let v = |index: usize, scale: f32| from_fixed(values[index], scale);
+        frames.push(Frame {
+            time: v(0, TIME_SCALE),
+            position: vec2(v(1, POSITION_SCALE), v(2, POSITION_SCALE)),
+            orientation: v(3, ANGLE_SCALE),
+            left_power: v(4, POWER_SCALE),
+            right_power: v(5, POWER_SCALE),
+            collided: values[6] & 1 != 0,
+            finished: values[6] & 2 != 0,
+            calibrating: values[6] & 4 != 0,
+            in_start: values[6] & 8 != 0,
+            in_goal: values[6] & 16 != 0,
+            layer: values[7] as usize,
+            wall_touches: values[touches] as usize,
+            sensors: names
+                .iter()
+                .enumerate()
+                .map(|(n, name)| {
+                    let base = HEAD + n * 5;
+                    SensorFrame {
+                        name: name.clone(),
+                        value: v(base, VALUE_SCALE),
+                        origin: vec2(v(base + 1, POSITION_SCALE), v(base + 2, POSITION_SCALE)),
+                        hit: vec2(v(base + 3, POSITION_SCALE), v(base + 4, POSITION_SCALE)),
+                    }
+                })
+                .collect(),
+            channels: channels
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, first))| i >= *first)
+                .map(|(n, (name, _))| ChannelValue {
+                    name: name.clone(),
+                    value: v(fixed + n, CHANNEL_SCALE),
+                })
+                .collect(),
+        });
+    }
+    Ok(Telemetry { tag, frames })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(time: f32, layer: usize, channel: Option<f32>) -> Frame {
+        Frame {
+            time,
+            position: vec2(12.5, 37.25),
+            orientation: 1.25,
+            left_power: 0.25,
+            right_power: -0.5,
+            sensors: vec![SensorFrame {
+                name: String::from("FRONT"),
+                value: 42.1,
+                origin: vec2(20.0, 37.25),
+                hit: vec2(62.1, 37.25),
+            }],
+            collided: false,
+            finished: true,
+            calibrating: false,
+            channels: channel
+                .map(|value| ChannelValue {
+                    name: String::from("speed"),
+                    value,
+                })
+                .into_iter()
+                .collect(),
+            layer,
+            in_start: false,
+            in_goal: true,
+            wall_touches: 3,
+        }
+    }
+
+    #[test]
+    fn quantized_round_trip_keeps_flags_layers_and_channels() {
+        // A saturated sensor reads infinity
+        let mut saturated = frame(0.002, 2, Some(f32::NAN));
+        saturated.sensors[0].value = f32::INFINITY;
+        let telemetry = Telemetry {
+            tag: Some(String::from("baseline")),
+            frames: vec![frame(0.0, 0, None), frame(0.001, 2, Some(0.75)), saturated],
+        };
+        let mut bytes = Vec::new();
+        write_quantized(&mut bytes, &telemetry).unwrap();
+        let read = read_quantized(&mut &bytes[MAGIC.len()..]).unwrap();
+
+        assert_eq!(read.tag.as_deref(), Some("baseline"));
+        assert_eq!(read.frames.len(), 3);
+        let (first, second) = (&read.frames[0], &read.frames[1]);
+        assert!(first.channels.is_empty());
+        assert_eq!(second.layer, 2);
+        assert!(second.finished && second.in_goal && !second.in_start && !second.collided);
+        assert_eq!(second.wall_touches, 3);
+        assert_eq!(second.position, vec2(12.5, 37.25));
+        assert_eq!(second.sensors[0].value, 42.1);
+        assert_eq!(second.channels[0].value, 0.75);
+        let saturated = &read.frames[2];
+        assert_eq!(saturated.sensors[0].value, f32::INFINITY);
+        assert!(saturated.channels[0].value.is_nan());
+        assert_eq!(saturated.sensors[0].hit, vec2(62.1, 37.25));
+    }
+}
@@ -0,0 +1,63 @@
+use mimosi_core::internal::simulation::Simulation;
+use notan::{app::Color, math::Vec2};
+
+use crate::{renderer::Renderer, view::layer_offset};
+
+const HANDLE_RADIUS: f32 = 4.0;
+
+/// What the user is currently dragging while the simulation is paused.
+#[derive(Debug, Clone, Copy)]
+pub enum Drag {
+    /// Moving the chassis, keeping the grab point under the cursor
+    Move { grab_offset: Vec2 },
+    /// Rotating the chassis around its center
+    Rotate,
+}
+
+/// Position of the handle used to rotate the mouse.
+pub fn rotation_handle(sim: &Simulation) -> Vec2 {
+    let mouse = &sim.mouse;
+    mouse.position + Vec2::from_angle(mouse.orientation) * (mouse.length + mouse.width)
+}
+
+/// Starts a drag if the cursor is over the chassis or the rotation handle.
+pub fn start_drag(sim: &Simulation, cursor: Vec2) -> Option<Drag> {
+    let mouse = &sim.mouse;
+    if cursor.distance(rotation_handle(sim)) <= HANDLE_RADIUS * 2.0 {
+        return Some(Drag::Rotate);
+    }
+    let local = (cursor - mouse.position).rotate(Vec2::from_angle(-mouse.orientation));
+    let half_width = mouse.width / 2.0;
+    let half_length = mouse.length / 2.0;
+    if (-half_length..=half_length + half_width).contains(&local.x)
+        && (-half_width..=half_width).contains(&local.y)
+    {
+        return Some(Drag::Move {
+            grab_offset: mouse.position - cursor,
+        });
+    }
+    None
+}
+
+/// Moves the mouse along with the cursor, without recording an event.
+pub fn drag(sim: &mut Simulation, drag: Drag, cursor: Vec2) {
+    match drag {
+        Drag::Move { grab_offset } => {
+            let orientation = sim.mouse.orientation;
+            sim.set_pose(cursor + grab_offset, orientation);
+        }
+        Drag::Rotate => {
+            let delta = cursor - sim.mouse.position;
+            let position = sim.mouse.position;
+            sim.set_pose(position, delta.y.atan2(delta.x));
+        }
+    }
+}
+
+pub fn render_handle(sim: &Simulation, draw: &mut impl Renderer) {
+    let offset = layer_offset(&sim.maze, sim.mouse.layer);
+    let handle = rotation_handle(sim) + offset;
+    let center = sim.mouse.position + offset;
+    draw.line(center, handle, 1.0, Color::WHITE);
+    draw.circle(handle, HANDLE_RADIUS, Color::WHITE);
+}
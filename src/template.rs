@@ -0,0 +1,88 @@
+use std::f32::consts::PI;
+
+use mimosi_core::internal::mouse::MouseConfig;
+
+/// Finds the sensor whose mounting angle is closest to the given angle in degrees.
+fn closest_sensor(config: &MouseConfig, angle: f32) -> Option<&str> {
+    config
+        .sensors
+        .iter()
+        .map(|(name, sensor)| {
+            let diff = (sensor.angle - angle).rem_euclid(360.0);
+            (name, diff.min(360.0 - diff))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(name, _)| name.as_str())
+}
+
+/// Generates a starter script for the given mouse.
+pub fn generate_script(config: &MouseConfig) -> String {
+    let mut names: Vec<_> = config.sensors.keys().cloned().collect();
+    names.sort();
+
+    let front = closest_sensor(config, 0.0).unwrap_or("FRONT");
+    let left = closest_sensor(config, 315.0).unwrap_or(front);
+    let right = closest_sensor(config, 45.0).unwrap_or(front);
+
+    let ticks_per_unit = config.encoder_resolution as f32 / (2.0 * PI * config.wheel_radius);
+    let turn_ticks = (PI / 2.0) * (config.wheel_base / 2.0) * ticks_per_unit;
+
+    let mut script = String::new();
+    script.push_str("// Generated by `mimosi new-script`\n");
+    script.push_str(&format!("// Available sensors: {}\n\n", names.join(", ")));
+
+    script.push_str("// Encoder ticks per distance unit traveled by a wheel\n");
+    script.push_str(&format!("const TICKS_PER_UNIT = {ticks_per_unit:.4};\n"));
+    script.push_str("// Encoder ticks each wheel needs for a 90 degree turn in place\n");
+    script.push_str(&format!("const TURN_TICKS = {turn_ticks:.1};\n"));
+    // Sensor values are squared distances
+    script.push_str(&format!(
+        "// Sensor value at which a wall counts as being in front of the mouse\nconst WALL_DISTANCE = {:.1};\n\n",
+        config.length.powi(2)
+    ));
+
+    script.push_str(&format!(
+        r#"let front = mouse.sensors["{front}"].value;
+let left = mouse.sensors["{left}"].value;
+let right = mouse.sensors["{right}"].value;
+
+if state.phase == () {{
+    state.phase = "forward";
+}}
+
+switch state.phase {{
+    "forward" => {{
+        if front < WALL_DISTANCE {{
+            // Turn towards the side with more space, measuring with the wheel that drives forward
+            if left > right {{
+                state.phase = "turn_left";
+                state.turn_start = mouse.right_encoder;
+            }} else {{
+                state.phase = "turn_right";
+                state.turn_start = mouse.left_encoder;
+            }}
+        }} else {{
+            mouse.left_power = 0.5;
+            mouse.right_power = 0.5;
+        }}
+    }}
+    "turn_left" => {{
+        mouse.left_power = -0.3;
+        mouse.right_power = 0.3;
+        if (mouse.right_encoder - state.turn_start).abs() >= TURN_TICKS {{
+            state.phase = "forward";
+        }}
+    }}
+    "turn_right" => {{
+        mouse.left_power = 0.3;
+        mouse.right_power = -0.3;
+        if (mouse.left_encoder - state.turn_start).abs() >= TURN_TICKS {{
+            state.phase = "forward";
+        }}
+    }}
+}}
+"#
+    ));
+
+    script
+}
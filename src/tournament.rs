@@ -0,0 +1,176 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use rayon::prelude::*;
+
+use mimosi_core::internal::{error::Error, maze::Maze, replay::Frame};
+
+use crate::{
+    batch::{name, Exports},
+    bench::{self, Setup},
+    html::{self, RunView},
+    report::{OutputFormat, Report, Value},
+    results::{self, RunResult},
+};
+
+/// How an entrant did over all mazes.
+#[derive(Default)]
+struct Standing {
+    script: String,
+    solved: usize,    // Mazes with at least one scored run
+    total_score: f32, // Sum of the best scores on the solved mazes
+    runs: usize,
+    crashes: usize,
+    errors: usize,
+}
+
+impl Standing {
+    /// Solving more mazes beats a lower total score.
+    fn compare(&self, other: &Self) -> Ordering {
+        other
+            .solved
+            .cmp(&self.solved)
+            .then(self.total_score.total_cmp(&other.total_score))
+    }
+}
+
+/// Runs every script on every maze under the scoring rules of the scenario and prints a ranked leaderboard.
+/// Each entrant gets all the runs the rules allow on a maze, and its best run there counts.
+///
+/// Entrants are ranked by the number of mazes they solved, then by the sum of their best scores.
+pub fn tournament(
+    mazes: &[(PathBuf, String)],
+    scripts: &[(PathBuf, String)],
+    setup: &Setup,
+    duration: f32,
+    dt: f32,
+    output: OutputFormat,
+    Exports { html, results }: Exports,
+) -> Result<(), Error> {
+    let combinations: Vec<_> = scripts
+        .iter()
+        .enumerate()
+        .flat_map(|(entrant, script)| {
+            mazes
+                .iter()
+                .enumerate()
+                .map(move |(i, maze)| (entrant, i, maze, script))
+        })
+        .collect();
+
+    let runs: Vec<(RunResult, Vec<Frame>)> = combinations
+        .par_iter()
+        .map(|(_, _, (maze_path, maze), (script_path, script))| {
+            let setup = Setup {
+                script,
+                all_runs: true,
+                ..*setup
+            };
+            let names = (name(maze_path), name(script_path));
+            match bench::simulate(maze, &setup, 0, duration, dt) {
+                Ok((_, sim)) => {
+                    let result = RunResult::new(&sim, names.0, names.1);
+                    let frames = if html.is_some() {
+                        sim.recorder.frames
+                    } else {
+                        Vec::new()
+                    };
+                    (result, frames)
+                }
+                Err(e) => (
+                    RunResult::failed(names.0, names.1, e.to_string()),
+                    Vec::new(),
+                ),
+            }
+        })
+        .collect();
+    if let Some(path) = results {
+        let results: Vec<RunResult> = runs.iter().map(|(result, _)| result.clone()).collect();
+        results::write_results(path, &results)?;
+    }
+
+    let mut standings: Vec<Standing> = scripts
+        .iter()
+        .map(|(path, _)| Standing {
+            script: name(path),
+            ..Default::default()
+        })
+        .collect();
+    for ((entrant, ..), (result, _)) in combinations.iter().zip(&runs) {
+        let standing = &mut standings[*entrant];
+        if result.error.is_some() {
+            standing.errors += 1;
+            continue;
+        }
+        standing.runs += result.run.runs as usize;
+        standing.crashes += result.crashed as usize;
+        if result.run.best_score >= 0.0 {
+            standing.solved += 1;
+            standing.total_score += result.run.best_score;
+        }
+    }
+    standings.sort_by(Standing::compare);
+
+    let mut report = Report::new(&[
+        "rank",
+        "script",
+        "solved",
+        "total_score",
+        "runs",
+        "crashes",
+        "errors",
+    ]);
+    let mut rank = 0;
+    for (i, standing) in standings.iter().enumerate() {
+        // Ties share the rank
+        if i == 0 || standing.compare(&standings[i - 1]) != Ordering::Equal {
+            rank = i + 1;
+        }
+        report.push(vec![
+            rank.into(),
+            standing.script.clone().into(),
+            standing.solved.into(),
+            Value::number(standing.total_score, 2),
+            standing.runs.into(),
+            standing.crashes.into(),
+            standing.errors.into(),
+        ]);
+    }
+    if let Some(rules) = setup.scenario.scoring {
+        report.header(format!(
+            "{} mazes, up to {} runs in {:.0}s per maze",
+            mazes.len(),
+            rules.max_runs,
+            rules.maze_time
+        ));
+    }
+
+    if let Some(html) = html {
+        let parsed = mazes
+            .iter()
+            .map(|(path, maze)| Maze::parse(maze).map_err(|e| Error::maze(&name(path), e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let views: Vec<RunView> = combinations
+            .iter()
+            .zip(&runs)
+            .filter(|(_, (result, _))| result.error.is_none())
+            .map(
+                |((_, index, (maze, _), (script, _)), (result, frames))| RunView {
+                    title: format!(
+                        "{} / {}: {}",
+                        name(maze),
+                        name(script),
+                        if result.run.best_score >= 0.0 {
+                            format!("best score {:.2}", result.run.best_score)
+                        } else {
+                            String::from("not solved")
+                        }
+                    ),
+                    maze: &parsed[*index],
+                    frames,
+                },
+            )
+            .collect();
+        html::save(html, "Tournament", &report, &views)?;
+    }
+    report.print(output)
+}
@@ -0,0 +1,117 @@
+use std::ops::RangeInclusive;
+
+use mimosi_core::internal::{mouse::MouseConfig, simulation::Simulation};
+use notan::egui::{self, Color32, Ui};
+
+/// The physics parameters that can be changed while the simulation runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    pub wheel_friction: f32,
+    pub brake_friction: f32,
+    pub mass: f32,
+    pub max_speed: f32,
+    pub maze_friction: f32,
+    pub disturbance_scale: f32,
+    pub drift_fluctuation: Option<f32>,
+}
+
+impl Params {
+    pub fn from_sim(sim: &Simulation) -> Self {
+        Self {
+            wheel_friction: sim.mouse.wheel_friction,
+            brake_friction: sim.mouse.brake_friction,
+            mass: sim.mouse.mass,
+            max_speed: sim.mouse.max_speed,
+            maze_friction: sim.maze.friction,
+            disturbance_scale: sim.disturbance.as_ref().map_or(1.0, |d| d.scale),
+            drift_fluctuation: sim.drift.as_ref().map(|d| d.fluctuation()),
+        }
+    }
+
+    pub fn apply(&self, sim: &mut Simulation) {
+        sim.mouse.wheel_friction = self.wheel_friction;
+        sim.mouse.brake_friction = self.brake_friction;
+        sim.mouse.mass = self.mass;
+        sim.mouse.max_speed = self.max_speed;
+        sim.maze.friction = self.maze_friction;
+        if let Some(disturbance) = &mut sim.disturbance {
+            disturbance.scale = self.disturbance_scale;
+        }
+        if let (Some(drift), Some(fluctuation)) = (&mut sim.drift, self.drift_fluctuation) {
+            drift.set_fluctuation(fluctuation);
+        }
+    }
+
+    /// Writes the mouse parameters into a config, e.g. to export it.
+    pub fn apply_to_config(&self, config: &mut MouseConfig) {
+        config.wheel_friction = self.wheel_friction;
+        config.brake_friction = self.brake_friction;
+        config.mass = self.mass;
+        config.max_speed = self.max_speed;
+    }
+}
+
+fn slider(ui: &mut Ui, text: &str, value: &mut f32, original: f32, range: RangeInclusive<f32>) {
+    ui.horizontal(|ui| {
+        ui.add(egui::Slider::new(value, range).text(text));
+        if *value != original {
+            ui.colored_label(Color32::from_rgb(255, 165, 0), "changed")
+                .on_hover_text(format!("Config: {original}"));
+            if ui.small_button("Reset").clicked() {
+                *value = original;
+            }
+        }
+    });
+}
+
+/// Shows sliders for the physics parameters, marking the ones that differ from the config.
+///
+/// Returns true if anything differs from the config.
+pub fn tuning_panel(ui: &mut Ui, sim: &mut Simulation, original: &Params) -> bool {
+    let mut params = Params::from_sim(sim);
+    slider(
+        ui,
+        "Wheel Friction",
+        &mut params.wheel_friction,
+        original.wheel_friction,
+        0.0..=5.0,
+    );
+    slider(
+        ui,
+        "Brake Friction",
+        &mut params.brake_friction,
+        original.brake_friction,
+        0.0..=50.0,
+    );
+    slider(ui, "Mass", &mut params.mass, original.mass, 0.1..=10.0);
+    slider(
+        ui,
+        "Max Speed",
+        &mut params.max_speed,
+        original.max_speed,
+        10.0..=1000.0,
+    );
+    slider(
+        ui,
+        "Maze Friction",
+        &mut params.maze_friction,
+        original.maze_friction,
+        0.0..=5.0,
+    );
+    if sim.disturbance.is_some() {
+        slider(
+            ui,
+            "Disturbance Scale",
+            &mut params.disturbance_scale,
+            original.disturbance_scale,
+            0.0..=5.0,
+        );
+    }
+    if let (Some(fluctuation), Some(original)) =
+        (&mut params.drift_fluctuation, original.drift_fluctuation)
+    {
+        slider(ui, "Drift Fluctuation", fluctuation, original, 0.0..=10.0);
+    }
+    params.apply(sim);
+    params != *original
+}
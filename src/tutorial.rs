@@ -0,0 +1,115 @@
+use notan::egui::{self, Align2, Context};
+use serde::Deserialize;
+
+use mimosi_core::internal::{error::Error, events::Event, simulation::Simulation};
+
+/// What has to happen before a hint of the tutorial is shown.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// Right away, or as soon as the previous hint is closed
+    Start,
+    /// The simulated time reached this many seconds
+    Time(f32),
+    Crash,
+    WallTouch,
+    Finish,
+    /// A sensor measures less than the distance
+    SensorBelow {
+        sensor: String,
+        distance: f32,
+    },
+}
+
+/// A hint of the tutorial.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Step {
+    pub trigger: Trigger,
+    pub title: String,
+    pub text: String,
+    #[serde(default)]
+    pub pause: bool, // Pause the simulation while the hint is shown
+}
+
+/// Hints shown one after another, each once its trigger happened after the previous one was closed.
+#[derive(Deserialize, Debug)]
+pub struct Tutorial {
+    #[serde(rename = "step")]
+    pub steps: Vec<Step>,
+    #[serde(skip)]
+    next: usize, // The step that waits for its trigger, or is shown
+    #[serde(skip)]
+    shown: bool,
+    #[serde(skip)]
+    seen_events: usize, // Events before this index happened before the step was armed
+}
+
+impl Tutorial {
+    pub fn load(path: &std::path::Path) -> Result<Self, Error> {
+        let text = mimosi_core::internal::error::read(path)?;
+        mimosi_core::internal::error::from_toml(&path.display().to_string(), &text)
+    }
+
+    fn triggered(&self, trigger: &Trigger, sim: &Simulation) -> bool {
+        let happened = |wanted: fn(&Event) -> bool| {
+            sim.events
+                .iter()
+                .skip(self.seen_events)
+                .any(|e| wanted(&e.event))
+        };
+        match trigger {
+            Trigger::Start => true,
+            Trigger::Time(time) => sim.time >= *time,
+            Trigger::Crash => happened(|e| matches!(e, Event::Crash)),
+            Trigger::WallTouch => happened(|e| matches!(e, Event::WallTouch)),
+            Trigger::Finish => happened(|e| matches!(e, Event::Finish)),
+            Trigger::SensorBelow { sensor, distance } => sim
+                .mouse
+                .sensors
+                .get(sensor)
+                .is_some_and(|s| s.value < *distance),
+        }
+    }
+
+    /// Shows the next hint once its trigger happened. Returns true if the simulation should pause for it.
+    pub fn update(&mut self, sim: &Simulation) -> bool {
+        let Some(step) = self.steps.get(self.next).filter(|_| !self.shown) else {
+            return false;
+        };
+        if !self.triggered(&step.trigger, sim) {
+            return false;
+        }
+        self.shown = true;
+        step.pause
+    }
+
+    /// Draws the current hint, with a button that moves on to the next step.
+    pub fn show(&mut self, ctx: &Context, sim: &Simulation) {
+        let Some(step) = self.steps.get(self.next).filter(|_| self.shown) else {
+            return;
+        };
+        let mut next = false;
+        egui::Window::new(&step.title)
+            .id(egui::Id::new("tutorial"))
+            .anchor(Align2::CENTER_TOP, [0.0, 10.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(&step.text);
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}/{}", self.next + 1, self.steps.len()));
+                    let label = if self.next + 1 < self.steps.len() {
+                        "Next"
+                    } else {
+                        "Done"
+                    };
+                    next = ui.button(label).clicked();
+                });
+            });
+        if next {
+            self.next += 1;
+            self.shown = false;
+            self.seen_events = sim.events.len();
+        }
+    }
+}
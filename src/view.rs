@@ -0,0 +1,136 @@
+use mimosi_core::internal::maze::Maze;
+use notan::{
+    math::{vec2, Mat3, Vec2},
+    prelude::{Graphics, RenderTexture},
+};
+
+/// Margin around the maze, in world units.
+const MARGIN: f32 = 5.0;
+/// Size of the texture the robot view is rendered to, in pixels.
+pub const ROBOT_VIEW_SIZE: u32 = 512;
+/// How many cells fit across the robot view.
+const ROBOT_VIEW_CELLS: f32 = 5.0;
+
+/// Zoom limits relative to the scale that fits the maze.
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 20.0;
+
+/// Where a layer of the maze is drawn, relative to the floor. The upper layer is shown next to it.
+pub fn layer_offset(maze: &Maze, layer: usize) -> Vec2 {
+    vec2(
+        (maze.grid.width + 1) as f32 * maze.cell_size * layer as f32,
+        0.0,
+    )
+}
+
+/// The camera of the maze view.
+///
+/// Scales the maze to fill the part of the window that isn't covered by the side panel,
+/// which can be zoomed and panned from there or follow the mouse.
+#[derive(Debug, Clone, Copy)]
+pub struct View {
+    pub scale: f32, // Fits the whole maze into the window
+    pub zoom: f32,  // On top of the scale
+    pub pan: Vec2,  // Point shown in the top left corner, in drawn units
+    pub follow: bool,
+    viewport: Vec2, // Size of the area the maze is drawn in, in pixels
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            follow: false,
+            viewport: Vec2::ONE,
+        }
+    }
+}
+
+impl View {
+    /// Updates the scale for the current window size, keeping the zoom and pan.
+    pub fn fit(&mut self, window: (f32, f32), panel_width: f32, maze: &Maze) {
+        let layers = 1 + maze.upper.is_some() as usize;
+        let extent = layer_offset(maze, layers - 1)
+            + vec2(
+                maze.grid.width as f32 * maze.cell_size,
+                maze.grid.height as f32 * maze.cell_size,
+            )
+            + vec2(MARGIN, MARGIN) * 2.0;
+        self.viewport = vec2((window.0 - panel_width).max(1.0), window.1.max(1.0));
+        let scale = (self.viewport / extent).min_element();
+        self.scale = if scale.is_finite() && scale > 0.0 {
+            scale
+        } else {
+            1.0
+        };
+    }
+
+    /// Shows the whole maze again.
+    pub fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+        self.follow = false;
+    }
+
+    fn total_scale(self) -> f32 {
+        self.scale * self.zoom
+    }
+
+    /// Maps world coordinates to the window, leaving the margin around the maze.
+    pub fn matrix(self) -> Mat3 {
+        let scale = self.total_scale();
+        Mat3::from_scale(vec2(scale, scale))
+            * Mat3::from_translation(vec2(MARGIN, MARGIN) - self.pan)
+    }
+
+    /// Converts a position in the window to world coordinates.
+    pub fn to_world(self, screen: Vec2) -> Vec2 {
+        self.matrix().inverse().transform_point2(screen)
+    }
+
+    /// Zooms by the factor, keeping the point under the cursor in place.
+    pub fn zoom_at(&mut self, screen: Vec2, factor: f32) {
+        let anchor = screen / self.total_scale() + self.pan;
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = anchor - screen / self.total_scale();
+    }
+
+    /// Moves the maze along with the cursor.
+    pub fn pan_by(&mut self, screen_delta: Vec2) {
+        self.pan -= screen_delta / self.total_scale();
+        self.follow = false;
+    }
+
+    /// Centers the view on a world position, if following is enabled.
+    pub fn follow(&mut self, position: Vec2) {
+        if self.follow {
+            self.pan += position - self.to_world(self.viewport / 2.0);
+        }
+    }
+}
+
+/// Shows the maze from the point of view of the mouse, which stays in the center facing up.
+pub struct RobotView {
+    pub texture: RenderTexture,
+}
+
+impl RobotView {
+    pub fn new(gfx: &mut Graphics) -> Result<Self, String> {
+        let texture = gfx
+            .create_render_texture(ROBOT_VIEW_SIZE, ROBOT_VIEW_SIZE)
+            .build()?;
+        Ok(Self { texture })
+    }
+
+    /// Moves and rotates the world so the mouse at the given pose ends up in the center.
+    pub fn matrix(position: Vec2, orientation: f32, cell_size: f32) -> Mat3 {
+        let size = ROBOT_VIEW_SIZE as f32;
+        let zoom = size / (cell_size * ROBOT_VIEW_CELLS);
+        Mat3::from_translation(vec2(size, size) / 2.0)
+            * Mat3::from_angle(-std::f32::consts::FRAC_PI_2 - orientation)
+            * Mat3::from_scale(vec2(zoom, zoom))
+            * Mat3::from_translation(-position)
+    }
+}